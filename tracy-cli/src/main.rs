@@ -7,10 +7,17 @@ use clap::{Arg, ArgAction, Command};
 use petgraph::dot::Dot;
 use petgraph::stable_graph::{DefaultIx, NodeIndex};
 use petgraph::{Graph, Undirected};
-use tracy::dex::DexAgg;
-use tracy::pools::juno_pool::{fetch_juno_pools, load_juno_pools_from_file};
-use tracy::pools::osmosis_pool::{fetch_osmosis_pools, load_osmo_pools_from_file_boxed};
-use tracy::PoolConfig;
+use tracy::assets::{AssetId, AssetRegistry};
+use tracy::config::{PoolAdapter, TracyConfig};
+use tracy::dex::{DexAgg, QuoteOptions};
+use tracy::pools::discovery::discover_contracts;
+use tracy::pools::juno_pool::{fetch_juno_pools, report_path, LoadMode};
+use tracy::pools::osmosis_pool::fetch_osmosis_pool_list;
+use tracy::pools::terraswap_pool::fetch_terraswap_pools;
+use tracy::router::DEFAULT_MAX_HOPS;
+use tracy::split::SplitOptions;
+use tracy::util::endpoint::EndpointPool;
+use tracy::{PoolConfig, QuoteSource};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -20,6 +27,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .subcommand_required(true)
         .arg_required_else_help(true)
         .author("Daubit")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help("Chain config file, see tracy.example.json")
+                .action(ArgAction::Set)
+                .num_args(1)
+                .global(true),
+        )
         .subcommand(
             Command::new("quote")
                 .about("Get the quote of two tokens")
@@ -40,7 +55,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .arg(
                     Arg::new("amount")
                         .long("amount")
-                        .help("Token in amount")
+                        .help("Token in amount, e.g. 1.5ATOM, 250000uosmo or 250000 in base units")
                         .action(ArgAction::Set)
                         .num_args(1),
                 )
@@ -57,6 +72,78 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .help("Node to query from")
                         .action(ArgAction::Set)
                         .num_args(1),
+                )
+                .arg(
+                    Arg::new("exact_out")
+                        .long("exact-out")
+                        .help("Treat amount as the token out amount and quote the needed token in")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("route")
+                .about("Find the best route between two tokens over multiple pools")
+                .arg(
+                    Arg::new("token_in")
+                        .long("token_in")
+                        .help("Token in")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("token_out")
+                        .long("token_out")
+                        .help("Token out")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("amount")
+                        .long("amount")
+                        .help("Token in amount, e.g. 1.5ATOM, 250000uosmo or 250000 in base units")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("max_hops")
+                        .long("max_hops")
+                        .help("Maximum number of pools in the route")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new("split")
+                .about("Split an amount over several pools for the best total output")
+                .arg(
+                    Arg::new("token_in")
+                        .long("token_in")
+                        .help("Token in")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("token_out")
+                        .long("token_out")
+                        .help("Token out")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("amount")
+                        .long("amount")
+                        .help("Token in amount, e.g. 1.5ATOM, 250000uosmo or 250000 in base units")
+                        .action(ArgAction::Set)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("max_hops")
+                        .long("max_hops")
+                        .help(
+                            "Maximum number of pools per route, 1 only uses pools with both tokens",
+                        )
+                        .action(ArgAction::Set)
+                        .num_args(1),
                 ),
         )
         .subcommand(
@@ -77,17 +164,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .action(ArgAction::Set)
                         .num_args(1..)
                         .help("Node to connect to"),
+                )
+                .arg(
+                    Arg::new("update")
+                        .long("update")
+                        .help("Keep the WasmSwap snapshot and only fetch new or changed pools")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("resume")
+                        .long("resume")
+                        .help("Continue an interrupted WasmSwap load from its checkpoint")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("assets")
+                .about("List the symbols and exponents of the known denoms")
+                .arg(
+                    Arg::new("chain")
+                        .long("chain")
+                        .help("Only list the assets of this chain")
+                        .action(ArgAction::Set)
+                        .num_args(1),
                 ),
         )
         .subcommand(Command::new("graph").about("generator dotfile"))
         .get_matches();
 
+    let tracy_config = match matches.get_one::<String>("config") {
+        Some(path) => TracyConfig::load(Path::new(path))?,
+        None => TracyConfig::default(),
+    };
+
     match matches.subcommand() {
         Some(("quote", query_matches)) => {
             let token_in = query_matches.get_one::<String>("token_in");
             let token_out = query_matches.get_one::<String>("token_out");
             let node = query_matches.get_one::<String>("node");
             let amount = query_matches.get_one::<String>("amount");
+            let exact_out = query_matches.get_flag("exact_out");
             if token_in.is_none() {
                 println!("Provide token_in argument!");
                 return Ok(());
@@ -104,158 +220,370 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("Provide amount argument!");
                 return Ok(());
             }
-            let dex = DexAgg::new(None)?;
+            let mut dex = DexAgg::from_config(&tracy_config, None)?;
             let token_in = token_in.unwrap();
             let token_out = token_out.unwrap();
             let node = node.unwrap();
-            let amount = amount.unwrap().parse::<u128>()?;
-            let pools = dex
-                .with_denoms(vec![token_in.to_string(), token_out.to_string()])
-                .await;
-            let config = PoolConfig {
-                rest_url: Some(node.to_string()),
-                grpc_url: None,
-                rpc_url: None,
-                estimate_quote: false,
+            // the amount is in token_out for exact out quotes
+            let amount_denom = if exact_out { token_out } else { token_in };
+            let amount = dex.parse_amount(amount.unwrap(), amount_denom).await?;
+            for config in dex.config.values_mut() {
+                *config = PoolConfig {
+                    rest: EndpointPool::new(vec![node.to_string()]),
+                    estimate_quote: false,
+                    ..config.clone()
+                };
+            }
+            let options = QuoteOptions {
+                exact_out,
+                ..Default::default()
             };
-            for pool in pools {
-                let quote = pool.get_quote(amount, token_in, token_out, &config).await;
-                if quote.is_ok() {
+            let quotes = dex.quote_all(amount, token_in, token_out, &options).await?;
+            for quote in quotes {
+                let pool_address = quote.pool_address.clone().unwrap_or_default();
+                let chain = quote.chain.clone().unwrap_or_else(|| "unknown".to_owned());
+                if let Some(error) = &quote.error {
                     println!(
-                        "Chain: {}\nPool Address: {}\nPrice for {} {} -> {} {}\n\n",
-                        pool.chain(),
-                        pool.address()?,
-                        token_in,
-                        amount,
-                        token_out,
-                        quote?.token_out.unwrap(),
+                        "Chain: {}\nPool Address: {}\nError: {}\n\n",
+                        chain, pool_address, error
                     );
+                    continue;
                 }
+                let or_unknown = |x: Option<String>| x.unwrap_or_else(|| "unknown".to_owned());
+                let source = quote.source.map(|x| match x {
+                    QuoteSource::Offline => "offline".to_owned(),
+                    QuoteSource::Simulation => "node simulation".to_owned(),
+                });
+                let fee = match (&quote.fee_amount, &quote.fee) {
+                    (Some(amount), Some(fee)) => Some(format!("{} ({})", amount, fee.rate)),
+                    (None, Some(fee)) => {
+                        Some(format!("{}{} ({})", fee.amount, fee.denom, fee.rate))
+                    }
+                    _ => None,
+                };
+                let block = quote
+                    .block
+                    .as_ref()
+                    .map(|x| format!("{} at {}", x.height, x.time));
+                println!(
+                    "Chain: {}\nPool Address: {}\nPrice for {}\nSource: {}\nFee: {}\n\
+                    Spot price before: {}\nSpot price after: {}\nEffective rate: {}\n\
+                    Price impact: {}\nBlock: {}\n\n",
+                    chain,
+                    pool_address,
+                    quote,
+                    or_unknown(source),
+                    or_unknown(fee),
+                    or_unknown(quote.spot_price_before.map(|x| x.to_string())),
+                    or_unknown(quote.spot_price_after.map(|x| x.to_string())),
+                    or_unknown(quote.effective_rate.map(|x| x.to_string())),
+                    or_unknown(quote.price_impact.map(|x| format!("{}%", x))),
+                    block.unwrap_or_else(|| "snapshot".to_owned()),
+                );
             }
         }
+        Some(("route", query_matches)) => {
+            let token_in = query_matches.get_one::<String>("token_in");
+            let token_out = query_matches.get_one::<String>("token_out");
+            let amount = query_matches.get_one::<String>("amount");
+            let max_hops = query_matches.get_one::<String>("max_hops");
+            if token_in.is_none() {
+                println!("Provide token_in argument!");
+                return Ok(());
+            }
+            if token_out.is_none() {
+                println!("Provide token_out argument!");
+                return Ok(());
+            }
+            if amount.is_none() {
+                println!("Provide amount argument!");
+                return Ok(());
+            }
+            let dex = DexAgg::from_config(&tracy_config, None)?;
+            let amount = dex.parse_amount(amount.unwrap(), token_in.unwrap()).await?;
+            let max_hops = match max_hops {
+                Some(x) => x.parse::<usize>()?,
+                None => DEFAULT_MAX_HOPS,
+            };
+            let route = dex
+                .best_route(amount, token_in.unwrap(), token_out.unwrap(), max_hops)
+                .await?;
+            for (i, hop) in route.hops.iter().enumerate() {
+                println!(
+                    "Hop {}\nChain: {}\nPool Address: {}\nPrice for {} -> {}\n",
+                    i + 1,
+                    hop.chain,
+                    hop.pool_address,
+                    dex.assets.amount(hop.amount_in, &hop.token_in),
+                    dex.assets.amount(hop.amount_out, &hop.token_out),
+                );
+            }
+            println!(
+                "Route: {} -> {}",
+                dex.assets.amount(route.amount_in, &route.token_in),
+                dex.assets.amount(route.amount_out, &route.token_out)
+            );
+        }
+        Some(("split", query_matches)) => {
+            let token_in = query_matches.get_one::<String>("token_in");
+            let token_out = query_matches.get_one::<String>("token_out");
+            let amount = query_matches.get_one::<String>("amount");
+            let max_hops = query_matches.get_one::<String>("max_hops");
+            if token_in.is_none() {
+                println!("Provide token_in argument!");
+                return Ok(());
+            }
+            if token_out.is_none() {
+                println!("Provide token_out argument!");
+                return Ok(());
+            }
+            if amount.is_none() {
+                println!("Provide amount argument!");
+                return Ok(());
+            }
+            let dex = DexAgg::from_config(&tracy_config, None)?;
+            let amount = dex.parse_amount(amount.unwrap(), token_in.unwrap()).await?;
+            let mut options = SplitOptions::default();
+            if let Some(x) = max_hops {
+                options.max_hops = x.parse::<usize>()?;
+            }
+            let split = dex
+                .best_split(amount, token_in.unwrap(), token_out.unwrap(), &options)
+                .await?;
+            for route in &split.allocations {
+                println!(
+                    "Allocation {} -> {}",
+                    dex.assets.amount(route.amount_in, &route.token_in),
+                    dex.assets.amount(route.amount_out, &route.token_out)
+                );
+                for hop in &route.hops {
+                    println!(
+                        "  Chain: {}\n  Pool Address: {}\n  Price for {} -> {}\n",
+                        hop.chain,
+                        hop.pool_address,
+                        dex.assets.amount(hop.amount_in, &hop.token_in),
+                        dex.assets.amount(hop.amount_out, &hop.token_out),
+                    );
+                }
+            }
+            println!(
+                "Split: {} -> {}",
+                dex.assets.amount(split.amount_in, &split.token_in),
+                dex.assets.amount(split.amount_out, &split.token_out)
+            );
+        }
         Some(("load", query_matches)) => {
             let chain = query_matches.get_one::<String>("chain");
             let node = query_matches.get_one::<String>("node");
+            let mode = if query_matches.get_flag("resume") {
+                LoadMode::Resume
+            } else if query_matches.get_flag("update") {
+                LoadMode::Update
+            } else {
+                LoadMode::Full
+            };
             if chain.is_none() {
                 println!("Provide a chain!");
                 return Ok(());
             }
-            if node.is_none() {
+            let chain = match tracy_config.chain(chain.unwrap()) {
+                Some(x) => x,
+                None => {
+                    println!("Chain not yet implemented!");
+                    return Ok(());
+                }
+            };
+            let mut config = PoolConfig::try_from(chain)?;
+            // an explicit REST node replaces the configured nodes
+            if let Some(node) = node {
+                config.grpc = EndpointPool::new(vec![]);
+                config.rest = EndpointPool::new(vec![node.to_string()]);
+            }
+            if config.rest.is_empty() {
                 println!("Provide a node!");
                 return Ok(());
             }
-            let chain = chain.unwrap();
-            let node = node.unwrap();
             println!("Loading...");
-            match chain.to_string().as_str() {
-                "juno" => {
-                    let res = fetch_juno_pools(node).await;
-                    if res.is_err() {
-                        println!("Something went wrong while fetching the data for Juno")
-                    } else {
-                        println!("Successfully fetched the data for Juno");
+            let mut registry = AssetRegistry::from_config(&tracy_config)?;
+            // chains without bank metadata still have the assets of their asset list
+            if let Err(e) = registry
+                .fetch_denoms_metadata(&config.rest, &chain.name)
+                .await
+            {
+                println!(
+                    "Could not fetch the denom metadata of {}: {:#}",
+                    chain.name, e
+                );
+            }
+            // balancer and stableswap pools come from the same pool list, fetch it only once
+            let mut osmosis_pools = None;
+            let mut skipped_reported = false;
+            // cosmwasm contracts are discovered once for all adapters and sorted by their queries
+            let mut contracts = None;
+            for adapter in &chain.pools {
+                let path = Path::new(&adapter.snapshot);
+                let res = match adapter.adapter {
+                    PoolAdapter::Wasmswap | PoolAdapter::Terraswap => {
+                        if contracts.is_none() {
+                            let discovered = discover_contracts(&config.rest, &chain.pools).await;
+                            if let Ok(discovered) = &discovered {
+                                for address in &discovered.unknown {
+                                    println!("Skipped contract {}: no pool interface", address);
+                                }
+                            }
+                            contracts = Some(discovered);
+                        }
+                        let contracts = match contracts.as_ref().unwrap() {
+                            Ok(x) => x.get(adapter.adapter),
+                            Err(e) => {
+                                println!(
+                                    "Something went wrong while fetching the data for {}: {:#}",
+                                    chain.name, e
+                                );
+                                continue;
+                            }
+                        };
+                        match adapter.adapter {
+                            PoolAdapter::Wasmswap => {
+                                fetch_juno_pools(
+                                    &config.rest,
+                                    contracts,
+                                    path,
+                                    mode,
+                                    &mut registry,
+                                    &chain.name,
+                                )
+                                .await
+                                    .map(|(_, report)| {
+                                        println!(
+                                            "Contracts loaded: {}, updated: {}, unchanged: {}, empty: {}, failed: {}",
+                                            report.loaded.len(),
+                                            report.updated.len(),
+                                            report.unchanged.len(),
+                                            report.empty.len(),
+                                            report.failed.len(),
+                                        );
+                                        if !report.failed.is_empty() {
+                                            println!("Errors: {}", report_path(path).display());
+                                        }
+                                        vec![]
+                                    })
+                            }
+                            _ => fetch_terraswap_pools(
+                                &config.rest,
+                                contracts,
+                                path,
+                                &mut registry,
+                                &chain.name,
+                            )
+                            .await
+                            .map(|_| vec![]),
+                        }
                     }
-                }
-                "osmosis" => {
-                    let res = fetch_osmosis_pools("https://lcd.osmosis.zone").await;
-                    if res.is_err() {
-                        println!("Something went wrong while fetching the data for Osmosis")
-                    } else {
-                        println!("Successfully fetched the data for Osmosis");
+                    PoolAdapter::Osmosis | PoolAdapter::Stableswap | PoolAdapter::Concentrated => {
+                        if osmosis_pools.is_none() {
+                            osmosis_pools = Some(
+                                fetch_osmosis_pool_list(&config, &mut registry, &chain.name).await,
+                            );
+                        }
+                        let pools = match osmosis_pools.as_ref().unwrap() {
+                            Ok(x) => x,
+                            Err(e) => {
+                                println!(
+                                    "Something went wrong while fetching the data for {}: {:#}",
+                                    chain.name, e
+                                );
+                                continue;
+                            }
+                        };
+                        let saved = pools.save(adapter);
+                        // every adapter shares the list, so report the skipped pools once
+                        let skipped = if skipped_reported {
+                            vec![]
+                        } else {
+                            skipped_reported = true;
+                            pools.skipped.clone()
+                        };
+                        saved.map(|_| skipped)
                     }
+                };
+                match res {
+                    Ok(skipped) => {
+                        for pool in &skipped {
+                            println!(
+                                "Skipped pool {} {}: {}",
+                                pool.id, pool.type_url, pool.reason
+                            );
+                        }
+                        println!("Successfully fetched the data for {}", chain.name);
+                    }
+                    Err(e) => println!(
+                        "Something went wrong while fetching the data for {}: {:#}",
+                        chain.name, e
+                    ),
                 }
-                _ => println!("Chain not yet implemented!"),
             }
+            registry.save(Path::new(&tracy_config.assets))?;
         }
-        Some(("graph", _)) => {
-            // TODO: use DexAgg
-            let osmo_pools =
-                load_osmo_pools_from_file_boxed(&Path::new("./osmosis_pools_hackathon.json"))?;
-            let juno_pools = load_juno_pools_from_file(&Path::new("./juno_pools.json"))?;
-
-            let mut graph = Graph::<String, String, Undirected>::new_undirected();
-
-            let mut token_map: HashMap<String, NodeIndex<DefaultIx>> = HashMap::new();
-
-            for pool in osmo_pools.clone() {
-                for asset in pool.pool_assets.clone() {
-                    if !token_map.contains_key(&asset.token.native_name.clone().unwrap()) {
-                        let node = graph.add_node(asset.token.native_name.clone().unwrap());
-                        token_map.insert(asset.token.native_name.clone().unwrap(), node);
-                    }
+        Some(("assets", query_matches)) => {
+            let chain = query_matches.get_one::<String>("chain");
+            let registry = AssetRegistry::from_config(&tracy_config)?;
+            for asset in registry.assets() {
+                if chain.is_some_and(|x| x != &asset.chain) {
+                    continue;
                 }
-            }
-
-            for pool in juno_pools.clone() {
-                let token_1 = if pool.token1_denom.cw20.is_some() {
-                    format!("cw20:{}", pool.token1_denom.cw20.clone().unwrap())
-                } else {
-                    pool.token1.clone().unwrap().symbol.clone().unwrap()
-                };
-                let token_2 = if pool.token2_denom.cw20.is_some() {
-                    format!("cw20:{}", pool.token2_denom.cw20.clone().unwrap())
-                } else {
-                    pool.token2.clone().unwrap().symbol.clone().unwrap()
+                let exponent = match asset.exponent {
+                    Some(x) => x.to_string(),
+                    None => "unknown".to_owned(),
                 };
-
-                if !token_map.contains_key(&token_1) {
-                    let node = graph.add_node(token_1.clone());
-                    token_map.insert(token_1.clone(), node);
+                println!(
+                    "{} {}\n  Chain: {}\n  Exponent: {}\n  Origin: {} {}",
+                    asset.symbol,
+                    asset.denom,
+                    asset.chain,
+                    exponent,
+                    asset.origin_chain.as_deref().unwrap_or("unknown"),
+                    asset.base_denom,
+                );
+                if let Some(path) = &asset.ibc_path {
+                    println!("  IBC path: {}", path);
                 }
-
-                if !token_map.contains_key(&token_2) {
-                    let node = graph.add_node(token_2.clone());
-                    token_map.insert(token_2.clone(), node);
+                if let Some(address) = &asset.cw20_address {
+                    println!("  CW20: {}", address);
                 }
             }
+        }
+        Some(("graph", _)) => {
+            let dex = DexAgg::from_config(&tracy_config, None)?;
 
-            for pool in osmo_pools {
-                for asset in pool.pool_assets.clone() {
-                    let index = pool
-                        .pool_assets
-                        .iter()
-                        .position(|x| {
-                            x.token.amount == asset.token.amount
-                                && x.token.denom == asset.token.denom
-                                && x.token.native_name == asset.token.native_name
-                        })
-                        .unwrap();
-                    if pool.pool_assets.len() < index + 1 {
-                        println!("{} {}", pool.pool_assets.len(), index);
-                        continue;
-                    }
-                    for other_asset in &pool.pool_assets.clone()[index + 1..] {
-                        let node_1 = token_map
-                            .get(&asset.token.native_name.clone().unwrap())
-                            .unwrap();
-                        let node_2 = token_map
-                            .get(&other_asset.token.native_name.clone().unwrap())
-                            .unwrap();
+            let mut graph = Graph::<String, String, Undirected>::new_undirected();
 
-                        graph.add_edge(*node_1, *node_2, pool.id.clone());
+            // one node per asset id, the same token on several chains is one node
+            let mut token_map: HashMap<AssetId, NodeIndex<DefaultIx>> = HashMap::new();
+
+            // every pair of assets of a pool can be swapped, also in pools with more than 2 assets
+            for pool in dex.pools.lock().await.iter() {
+                let ids: Vec<AssetId> = dex
+                    .assets
+                    .pool_assets(pool.as_ref())
+                    .iter()
+                    .map(|x| x.id())
+                    .collect();
+                for id in &ids {
+                    if !token_map.contains_key(id) {
+                        let node = graph.add_node(id.to_string());
+                        token_map.insert(id.clone(), node);
+                    }
+                }
+                let address = pool.address()?;
+                for (index, id) in ids.iter().enumerate() {
+                    for other_id in &ids[index + 1..] {
+                        graph.add_edge(token_map[id], token_map[other_id], address.clone());
                     }
                 }
             }
 
-            for pool in juno_pools {
-                let token_1 = if pool.token1_denom.cw20.is_some() {
-                    format!("cw20:{}", pool.token1_denom.cw20.unwrap())
-                } else {
-                    pool.token1.unwrap().symbol.unwrap()
-                };
-                let token_2 = if pool.token2_denom.cw20.is_some() {
-                    format!("cw20:{}", pool.token2_denom.cw20.unwrap())
-                } else {
-                    pool.token2.unwrap().symbol.unwrap()
-                };
-
-                let node_1 = token_map.get(&token_1).unwrap();
-                let node_2 = token_map.get(&token_2).unwrap();
-
-                graph.add_edge(*node_1, *node_2, pool.pool_address.unwrap());
-            }
-
-            let dot_config = &vec![];
+            let dot_config = &[];
             let dot = Dot::with_config(&graph, dot_config);
 
             let mut f = File::create(Path::new("graph.dot"))?;
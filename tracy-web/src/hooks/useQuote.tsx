@@ -6,11 +6,14 @@ const fetchQuote = async (
   amount: string
 ) => {
   console.log("Fetching quote!");
-  const quote = await (
-    await fetch(
-      `http://localhost:8080/quote/${token_in}/${token_out}/${amount}`
-    )
-  ).json();
+  const response = await fetch(
+    `http://localhost:8080/quote/${token_in}/${token_out}/${amount}`
+  );
+  const quote = await response.json();
+  // failed requests answer with {"error": "..."} instead of the quotes
+  if (!response.ok) {
+    throw new Error(quote.error);
+  }
   return quote;
 };
 
@@ -64,10 +64,12 @@ const Home: NextPage = () => {
     }
     console.log({ token1, token2, amount });
     setLpPool([]);
-    fetchQuote(token1, token2, amount).then((r) => {
-      console.log(r);
-      setLpPool(r);
-    });
+    fetchQuote(token1, token2, amount)
+      .then((r) => {
+        console.log(r);
+        setLpPool(r);
+      })
+      .catch((e) => console.log(e));
     onOpen();
   }, [amount, data, onOpen, toast, token1, token2]);
   const swap = useCallback(() => {
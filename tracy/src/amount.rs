@@ -0,0 +1,208 @@
+use std::fmt;
+
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::assets::AssetId;
+
+/// Amount of an asset in base units together with what is needed to show it in the
+/// display unit, e.g. 1500000 uatom as `1.5 ATOM`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Amount {
+    pub raw: u128,
+    pub asset: AssetId,
+    pub symbol: String,
+    // decimals of the display unit, without them the amount is shown in base units
+    pub exponent: Option<u32>,
+    pub formatted: String,
+}
+
+impl Amount {
+    pub fn new(raw: u128, asset: &AssetId, symbol: &str, exponent: Option<u32>) -> Self {
+        let formatted = match exponent {
+            Some(exponent) => format!("{} {}", format_units(raw, exponent), symbol),
+            None => format!("{} {}", raw, asset.base_denom),
+        };
+        Amount {
+            raw,
+            asset: asset.clone(),
+            symbol: symbol.to_owned(),
+            exponent,
+            formatted,
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.formatted)
+    }
+}
+
+/// Base units as decimal number of the display unit, `(1500000, 6)` -> `1.5`.
+pub fn format_units(raw: u128, exponent: u32) -> String {
+    let scale = match 10u128.checked_pow(exponent) {
+        Some(x) => x,
+        None => return raw.to_string(),
+    };
+    let fraction = raw % scale;
+    if fraction == 0 {
+        return (raw / scale).to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = exponent as usize);
+    format!("{}.{}", raw / scale, fraction.trim_end_matches('0'))
+}
+
+/// Decimal number of the display unit as base units, `("1.5", 6)` -> `1500000`.
+pub fn parse_units(value: &str, exponent: u32) -> Result<u128> {
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |x: &str| x.chars().all(|x| x.is_ascii_digit());
+    if (integer.is_empty() && fraction.is_empty()) || !digits(integer) || !digits(fraction) {
+        return Err(eyre!("Invalid amount {}", value));
+    }
+    if fraction.len() > exponent as usize {
+        return Err(eyre!("{} has more than {} decimals", value, exponent));
+    }
+    let overflow = || eyre!("Amount {} is too large", value);
+    let scale = 10u128.checked_pow(exponent).ok_or_else(overflow)?;
+    let integer = match integer {
+        "" => 0,
+        x => x.parse::<u128>().map_err(|_| overflow())?,
+    };
+    let fraction = match fraction {
+        "" => 0,
+        x => x.parse::<u128>()? * 10u128.pow(exponent - fraction.len() as u32),
+    };
+    integer
+        .checked_mul(scale)
+        .and_then(|x| x.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+/// Splits user input like `1.5 ATOM`, `250000uosmo` or `1000` into number and unit.
+pub fn split_amount(input: &str) -> Result<(&str, Option<&str>)> {
+    let input = input.trim();
+    let end = input
+        .find(|x: char| !x.is_ascii_digit() && x != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(end);
+    if number.is_empty() {
+        return Err(eyre!("Amount {} does not start with a number", input));
+    }
+    let unit = unit.trim();
+    Ok((number, (!unit.is_empty()).then_some(unit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // u128::MAX with 18 decimals
+    const MAX: &str = "340282366920938463463.374607431768211455";
+
+    #[test]
+    fn parse_decimal_numbers() {
+        for (value, exponent, raw) in [
+            ("1.5", 6, 1500000),
+            (".5", 6, 500000),
+            ("1.", 6, 1000000),
+            ("1000", 6, 1000000000),
+            ("0.000001", 6, 1),
+            ("007.250", 6, 7250000),
+            ("250000", 0, 250000),
+            ("250000.", 0, 250000),
+            (MAX, 18, u128::MAX),
+        ] {
+            assert_eq!(parse_units(value, exponent).unwrap(), raw, "{}", value);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_numbers() {
+        for (value, exponent, error) in [
+            (".", 6, "Invalid amount ."),
+            ("", 6, "Invalid amount "),
+            ("1.5.0", 6, "Invalid amount 1.5.0"),
+            ("-1", 6, "Invalid amount -1"),
+            ("1,5", 6, "Invalid amount 1,5"),
+            ("1.5 ", 6, "Invalid amount 1.5 "),
+            ("0.0000001", 6, "0.0000001 has more than 6 decimals"),
+            ("1.0", 0, "1.0 has more than 0 decimals"),
+            (
+                "340282366920938463463.374607431768211456",
+                18,
+                "Amount 340282366920938463463.374607431768211456 is too large",
+            ),
+            (
+                "340282366920938463464",
+                18,
+                "Amount 340282366920938463464 is too large",
+            ),
+            (
+                "340282366920938463463374607431768211456",
+                0,
+                "Amount 340282366920938463463374607431768211456 is too large",
+            ),
+            ("1", 39, "Amount 1 is too large"),
+        ] {
+            let result = parse_units(value, exponent);
+            assert_eq!(result.unwrap_err().to_string(), error, "{}", value);
+        }
+    }
+
+    #[test]
+    fn format_base_units() {
+        for (raw, exponent, value) in [
+            (1500000, 6, "1.5"),
+            (500000, 6, "0.5"),
+            (1000000, 6, "1"),
+            (1, 6, "0.000001"),
+            (0, 6, "0"),
+            (250000, 0, "250000"),
+            (u128::MAX, 18, MAX),
+            // no display unit that large, shown in base units
+            (1, 39, "1"),
+        ] {
+            assert_eq!(format_units(raw, exponent), value, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        for (raw, exponent) in [(1500000, 6), (1, 18), (250000, 0), (u128::MAX, 18)] {
+            let value = format_units(raw, exponent);
+            assert_eq!(parse_units(&value, exponent).unwrap(), raw, "{}", value);
+        }
+    }
+
+    #[test]
+    fn split_number_and_unit() {
+        for (input, number, unit) in [
+            ("250000uosmo", "250000", Some("uosmo")),
+            ("1.5 ATOM", "1.5", Some("ATOM")),
+            ("  1.5   ATOM  ", "1.5", Some("ATOM")),
+            ("1000", "1000", None),
+            (".5", ".5", None),
+            ("1ibc/27394FB0", "1", Some("ibc/27394FB0")),
+        ] {
+            assert_eq!(split_amount(input).unwrap(), (number, unit), "{}", input);
+        }
+        for input in ["ATOM", "", " ", "-1 ATOM"] {
+            assert!(split_amount(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn amount_in_display_or_base_units() {
+        let id = AssetId::new("cosmoshub", "uatom");
+        assert_eq!(
+            Amount::new(1500000, &id, "ATOM", Some(6)).to_string(),
+            "1.5 ATOM"
+        );
+        assert_eq!(
+            Amount::new(1500000, &id, "ATOM", None).to_string(),
+            "1500000 uatom"
+        );
+        assert_eq!(Amount::new(7, &id, "ATOM", Some(0)).to_string(), "7 ATOM");
+    }
+}
@@ -0,0 +1,797 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::amount::{parse_units, split_amount, Amount};
+use crate::config::TracyConfig;
+use crate::pools::juno_pool::{get_token_info, JunoToken};
+use crate::util::denom_trace::{channel_chain_id, denom_trace};
+use crate::util::endpoint::EndpointPool;
+use crate::Pool;
+
+const PAGE_LIMIT: u64 = 100;
+
+/// Identity of a token across chains, the chain which issued it and its denom there,
+/// written as `<origin>:<base_denom>`, e.g. `cosmoshub:uatom`.
+///
+/// If the origin of an IBC token is not known, the origin is the chain it was found on with
+/// the path of its denom trace, e.g. `osmosis/transfer/channel-9:uatom`. Tokens with the same
+/// base denom reached over different paths never share an id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct AssetId {
+    pub origin: String,
+    pub base_denom: String,
+}
+
+impl AssetId {
+    pub fn new(origin: &str, base_denom: &str) -> Self {
+        AssetId {
+            origin: origin.to_owned(),
+            base_denom: base_denom.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.origin, self.base_denom)
+    }
+}
+
+impl FromStr for AssetId {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            Some((origin, base_denom)) if !origin.is_empty() && !base_denom.is_empty() => {
+                Ok(AssetId::new(origin, base_denom))
+            }
+            _ => Err(eyre!(
+                "Invalid asset id {}, expected <origin>:<base_denom>",
+                s
+            )),
+        }
+    }
+}
+
+impl From<AssetId> for String {
+    fn from(id: AssetId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for AssetId {
+    type Error = eyre::Report;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// Metadata of a token on one chain.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Asset {
+    // on-chain denom, `cw20:<address>` for cw20 tokens
+    pub denom: String,
+    // chain the denom lives on
+    pub chain: String,
+    pub symbol: String,
+    // denom of the display unit, e.g. "atom" for "uatom"
+    pub display: Option<String>,
+    pub name: Option<String>,
+    // decimals of the display unit, None if no source knows them
+    pub exponent: Option<u32>,
+    // chain which issued the token, None if the channel of an ibc token is not known
+    pub origin_chain: Option<String>,
+    // denom on the origin chain
+    pub base_denom: String,
+    // e.g. "transfer/channel-0" for ibc tokens
+    pub ibc_path: Option<String>,
+    pub cw20_address: Option<String>,
+}
+
+impl Asset {
+    // a denom no source knows anything about, native to `chain` unless it is an ibc denom
+    fn unknown(chain: &str, denom: &str) -> Self {
+        Asset {
+            denom: denom.to_owned(),
+            chain: chain.to_owned(),
+            symbol: denom.to_owned(),
+            display: None,
+            name: None,
+            exponent: None,
+            origin_chain: (!denom.starts_with("ibc/")).then(|| chain.to_owned()),
+            base_denom: denom.to_owned(),
+            ibc_path: None,
+            cw20_address: None,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        match (&self.origin_chain, &self.ibc_path) {
+            (Some(origin), _) => AssetId::new(origin, &self.base_denom),
+            (None, Some(path)) => {
+                AssetId::new(&format!("{}/{}", self.chain, path), &self.base_denom)
+            }
+            (None, None) => AssetId::new(&self.chain, &self.base_denom),
+        }
+    }
+}
+
+/// Unit of a bank denom, see cosmos.bank.v1beta1.DenomUnit.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DenomUnit {
+    pub denom: String,
+    #[serde(default)]
+    pub exponent: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceCounterparty {
+    pub chain_name: String,
+    pub base_denom: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceChain {
+    // full path including the base denom, e.g. "transfer/channel-0/uatom"
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetTrace {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub counterparty: TraceCounterparty,
+    pub chain: Option<TraceChain>,
+}
+
+/// Bank `denoms_metadata` entry and asset of a chain-registry `assetlist.json`, which
+/// extends the bank metadata by traces and cw20 addresses.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DenomMetadata {
+    #[serde(default)]
+    pub denom_units: Vec<DenomUnit>,
+    pub base: String,
+    pub display: Option<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub traces: Vec<AssetTrace>,
+    pub address: Option<String>,
+}
+
+/// Asset list of a chain in the chain-registry format.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetList {
+    pub chain_name: String,
+    pub assets: Vec<DenomMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestPagination {
+    next_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DenomsMetadataPage {
+    metadatas: Vec<DenomMetadata>,
+    pagination: Option<RestPagination>,
+}
+
+impl DenomMetadata {
+    pub fn to_asset(&self, chain: &str) -> Asset {
+        let exponent = self
+            .denom_units
+            .iter()
+            .find(|x| Some(&x.denom) == self.display.as_ref())
+            .or_else(|| self.denom_units.iter().max_by_key(|x| x.exponent))
+            .map(|x| x.exponent);
+        let trace = self.traces.iter().find(|x| x.kind == "ibc");
+        let base_denom = match trace {
+            Some(x) => x.counterparty.base_denom.clone(),
+            None => self.base.clone(),
+        };
+        // the path of a trace ends with the base denom
+        let ibc_path = trace
+            .and_then(|x| x.chain.as_ref())
+            .and_then(|x| x.path.as_ref())
+            .map(|x| {
+                x.strip_suffix(&format!("/{}", base_denom))
+                    .unwrap_or(x)
+                    .to_owned()
+            });
+        Asset {
+            denom: self.base.clone(),
+            chain: chain.to_owned(),
+            symbol: self.symbol.clone().unwrap_or_else(|| self.base.clone()),
+            display: self.display.clone(),
+            name: self.name.clone(),
+            exponent,
+            origin_chain: Some(match trace {
+                Some(x) => x.counterparty.chain_name.clone(),
+                None => chain.to_owned(),
+            }),
+            base_denom,
+            ibc_path,
+            cw20_address: self.address.clone(),
+        }
+    }
+}
+
+/// Assets of every chain by their on-chain denom.
+#[derive(Debug, Clone, Default)]
+pub struct AssetRegistry {
+    assets: HashMap<String, HashMap<String, Asset>>,
+    // names of the configured chains by chain id, to find the origin of ibc tokens
+    chain_names: HashMap<String, String>,
+}
+
+impl AssetRegistry {
+    /// The snapshot of the config (if it was loaded before) and the asset lists of its chains,
+    /// entries of the asset lists replace the ones of the snapshot.
+    pub fn from_config(config: &TracyConfig) -> Result<Self> {
+        let mut registry = AssetRegistry::default();
+        let snapshot = Path::new(&config.assets);
+        if snapshot.exists() {
+            registry.load(snapshot)?;
+        }
+        for chain in &config.chains {
+            registry
+                .chain_names
+                .insert(chain.chain_id.clone(), chain.name.clone());
+            if let Some(path) = &chain.asset_list {
+                registry.add_asset_list(Path::new(path))?;
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn get(&self, chain: &str, denom: &str) -> Option<&Asset> {
+        self.assets.get(chain)?.get(denom)
+    }
+
+    pub fn insert(&mut self, asset: Asset) {
+        self.assets
+            .entry(asset.chain.clone())
+            .or_default()
+            .insert(asset.denom.clone(), asset);
+    }
+
+    /// Every asset, sorted by chain and denom.
+    pub fn assets(&self) -> Vec<&Asset> {
+        let mut assets: Vec<&Asset> = self.assets.values().flat_map(|x| x.values()).collect();
+        assets.sort_by(|a, b| (&a.chain, &a.denom).cmp(&(&b.chain, &b.denom)));
+        assets
+    }
+
+    /// Assets with the symbol on any chain, case insensitive.
+    pub fn by_symbol(&self, symbol: &str) -> Vec<&Asset> {
+        self.assets()
+            .into_iter()
+            .filter(|x| x.symbol.eq_ignore_ascii_case(symbol))
+            .collect()
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let mut file = File::open(path)?;
+        let mut text: String = "".to_string();
+        file.read_to_string(&mut text)?;
+        for asset in serde_json::from_str::<Vec<Asset>>(&text)? {
+            self.insert(asset);
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string(&self.assets())?;
+        let mut file = File::create(path)?;
+        file.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Adds the assets of a chain-registry style `assetlist.json`.
+    pub fn add_asset_list(&mut self, path: &Path) -> Result<()> {
+        let mut file = File::open(path)?;
+        let mut text: String = "".to_string();
+        file.read_to_string(&mut text)?;
+        let list: AssetList = serde_json::from_str(&text)?;
+        for asset in &list.assets {
+            self.insert(asset.to_asset(&list.chain_name));
+        }
+        Ok(())
+    }
+
+    /// Adds the bank metadata of every denom of `chain`, known assets are kept.
+    pub async fn fetch_denoms_metadata(&mut self, api: &EndpointPool, chain: &str) -> Result<()> {
+        let mut key: Option<String> = None;
+        loop {
+            let page: DenomsMetadataPage = api
+                .run(|url| {
+                    let key = key.clone();
+                    async move {
+                        let url = format!("{}/cosmos/bank/v1beta1/denoms_metadata", url);
+                        let mut query =
+                            vec![("pagination.limit".to_owned(), PAGE_LIMIT.to_string())];
+                        if let Some(key) = key {
+                            query.push(("pagination.key".to_owned(), key));
+                        }
+                        let http = api.connections().http();
+                        Ok(http.get(url).query(&query).send().await?.json().await?)
+                    }
+                })
+                .await?;
+            let page_size = page.metadatas.len();
+            for metadata in page.metadatas {
+                // without a trace the origin of an ibc denom is only known from `resolve`
+                let untraced = metadata.base.starts_with("ibc/") && metadata.traces.is_empty();
+                if !untraced && self.get(chain, &metadata.base).is_none() {
+                    self.insert(metadata.to_asset(chain));
+                }
+            }
+            let next_key = page
+                .pagination
+                .and_then(|x| x.next_key)
+                .filter(|x| !x.is_empty());
+            match next_key {
+                None => return Ok(()),
+                Some(x) if Some(&x) == key.as_ref() || page_size == 0 => {
+                    return Err(eyre!("Denom metadata pagination did not advance"))
+                }
+                Some(x) => key = Some(x),
+            }
+        }
+    }
+
+    // exponent and symbol of the same token on its origin chain or another chain
+    fn origin_asset(&self, base_denom: &str) -> Option<&Asset> {
+        self.assets()
+            .into_iter()
+            .filter(|x| x.base_denom == base_denom && x.exponent.is_some())
+            .min_by_key(|x| x.ibc_path.is_some())
+    }
+
+    // origin chain of an ibc token from the path of its denom trace, only single hop paths
+    // can be followed: by a known token over the same channel or the chain id of the channel
+    async fn trace_origin(&self, api: &EndpointPool, chain: &str, path: &str) -> Option<String> {
+        let (port, channel) = path.split_once('/')?;
+        if channel.contains('/') {
+            return None;
+        }
+        let known = self
+            .assets
+            .get(chain)
+            .into_iter()
+            .flat_map(|x| x.values())
+            .find(|x| x.ibc_path.as_deref() == Some(path) && x.origin_chain.is_some());
+        if let Some(asset) = known {
+            return asset.origin_chain.clone();
+        }
+        let chain_id = channel_chain_id(api, port, channel).await.ok()?;
+        self.chain_names.get(&chain_id).cloned()
+    }
+
+    /// Adds the cw20 token of a `token_info` query.
+    pub fn insert_cw20(&mut self, chain: &str, token: &JunoToken) -> Result<&Asset> {
+        let address = token
+            .address
+            .clone()
+            .ok_or_else(|| eyre!("Token has no address"))?;
+        let denom = format!("cw20:{}", address);
+        let asset = Asset {
+            denom: denom.clone(),
+            chain: chain.to_owned(),
+            symbol: token.symbol.clone().unwrap_or_else(|| denom.clone()),
+            display: None,
+            name: token.name.clone(),
+            exponent: token.decimals.map(|x| x as u32),
+            origin_chain: Some(chain.to_owned()),
+            base_denom: denom.clone(),
+            ibc_path: None,
+            cw20_address: Some(address),
+        };
+        // the asset list may know more than the contract
+        if self.get(chain, &denom).is_none() {
+            self.insert(asset);
+        }
+        Ok(self.get(chain, &denom).unwrap())
+    }
+
+    /// Asset of an on-chain denom of `chain`, unknown denoms are looked up on the chain:
+    /// `cw20:<address>` with `token_info`, `ibc/<hash>` with its denom trace.
+    pub async fn resolve(&mut self, api: &EndpointPool, chain: &str, denom: &str) -> Result<Asset> {
+        if let Some(asset) = self.get(chain, denom) {
+            return Ok(asset.clone());
+        }
+        if let Some(address) = denom.strip_prefix("cw20:") {
+            let token = get_token_info(api, address).await?;
+            return Ok(self.insert_cw20(chain, &token)?.clone());
+        }
+        let asset = match denom.strip_prefix("ibc/") {
+            Some(hash) => {
+                let trace = denom_trace(api, hash).await?;
+                // the path of a trace does not contain the base denom
+                let origin_chain = self.trace_origin(api, chain, &trace.path).await;
+                let origin = self.origin_asset(&trace.base_denom);
+                Asset {
+                    denom: denom.to_owned(),
+                    chain: chain.to_owned(),
+                    symbol: origin
+                        .map(|x| x.symbol.clone())
+                        .unwrap_or_else(|| trace.base_denom.clone()),
+                    display: origin.and_then(|x| x.display.clone()),
+                    name: origin.and_then(|x| x.name.clone()),
+                    exponent: origin.and_then(|x| x.exponent),
+                    origin_chain,
+                    base_denom: trace.base_denom,
+                    ibc_path: Some(trace.path),
+                    cw20_address: None,
+                }
+            }
+            None => Asset::unknown(chain, denom),
+        };
+        self.insert(asset.clone());
+        Ok(asset)
+    }
+
+    /// Asset of every token of a pool, tokens missing from the registry are taken as native
+    /// to the chain of the pool.
+    pub fn pool_assets(&self, pool: &dyn Pool) -> Vec<Asset> {
+        let chain = pool.chain();
+        pool.asset_denoms()
+            .iter()
+            .map(|denom| {
+                self.get(&chain, denom)
+                    .cloned()
+                    .unwrap_or_else(|| Asset::unknown(&chain, denom))
+            })
+            .collect()
+    }
+
+    /// On-chain denom of the asset in a pool, `None` if the pool does not hold it.
+    pub fn pool_denom(&self, pool: &dyn Pool, id: &AssetId) -> Option<String> {
+        self.pool_assets(pool)
+            .into_iter()
+            .find(|x| x.id() == *id)
+            .map(|x| x.denom)
+    }
+
+    // symbol and exponent of an id, preferably from the origin chain
+    fn display_asset(&self, id: &AssetId) -> Option<&Asset> {
+        self.assets()
+            .into_iter()
+            .filter(|x| x.id() == *id)
+            .min_by_key(|x| (x.exponent.is_none(), x.ibc_path.is_some()))
+    }
+
+    /// Raw amount formatted with the symbol and exponent of the asset, in base units if
+    /// the exponent is not known.
+    pub fn amount(&self, raw: u128, id: &AssetId) -> Amount {
+        match self.display_asset(id) {
+            Some(asset) => Amount::new(raw, id, &asset.symbol, asset.exponent),
+            None => Amount::new(raw, id, &id.base_denom, None),
+        }
+    }
+
+    /// Raw amount of `id` from user input: `1.5 ATOM` or `1.5atom` in the display unit,
+    /// `250000uosmo` or `250000 ibc/27394...` in a denom of the asset and `250000` in base units.
+    pub fn parse_amount(&self, amount: &str, id: &AssetId) -> Result<u128> {
+        let (number, unit) = split_amount(amount)?;
+        let unit = match unit {
+            Some(x) => x,
+            None if number.contains('.') => {
+                return Err(eyre!(
+                    "Amount {} has no unit, amounts without unit are in base units",
+                    amount
+                ))
+            }
+            None => return parse_units(number, 0),
+        };
+        let assets: Vec<&Asset> = self
+            .assets()
+            .into_iter()
+            .filter(|x| x.id() == *id)
+            .collect();
+        if unit == id.base_denom || assets.iter().any(|x| x.denom == unit) {
+            return parse_units(number, 0);
+        }
+        let display_unit = assets.iter().any(|x| {
+            x.symbol.eq_ignore_ascii_case(unit)
+                || x.display
+                    .as_ref()
+                    .is_some_and(|x| x.eq_ignore_ascii_case(unit))
+        });
+        if !display_unit {
+            return Err(eyre!("{} is not a unit of {}", unit, id));
+        }
+        match self.display_asset(id).and_then(|x| x.exponent) {
+            Some(exponent) => parse_units(number, exponent),
+            None => Err(eyre!(
+                "Exponent of {} is not known, give the amount in {}",
+                id,
+                id.base_denom
+            )),
+        }
+    }
+
+    /// Id of an asset given by a user as id (`cosmoshub:uatom`), on-chain denom
+    /// (`ibc/27394...`, `cw20:juno1...`), symbol (`ATOM`) or base denom (`uatom`), tried in
+    /// this order. The tokens of `pools` count as known assets too.
+    ///
+    /// Fails if the asset is unknown or if several assets match, e.g. `uatom` over two
+    /// paths whose origin is not known.
+    pub fn find_id(&self, asset: &str, pools: &[Box<dyn Pool>]) -> Result<AssetId> {
+        let mut known: Vec<Asset> = self.assets().into_iter().cloned().collect();
+        for pool in pools {
+            known.extend(self.pool_assets(pool.as_ref()));
+        }
+        if let Ok(id) = asset.parse::<AssetId>() {
+            if known.iter().any(|x| x.id() == id) {
+                return Ok(id);
+            }
+        }
+        let rules: [fn(&Asset, &str) -> bool; 3] = [
+            |x, asset| x.denom == asset,
+            // unknown assets use their denom as symbol, that is no symbol to match
+            |x, asset| x.symbol != x.base_denom && x.symbol.eq_ignore_ascii_case(asset),
+            |x, asset| x.base_denom == asset,
+        ];
+        for rule in rules {
+            let mut ids: Vec<AssetId> = known
+                .iter()
+                .filter(|x| rule(x, asset))
+                .map(|x| x.id())
+                .collect();
+            ids.sort();
+            ids.dedup();
+            match ids.len() {
+                0 => continue,
+                1 => return Ok(ids.remove(0)),
+                _ => {
+                    let ids: Vec<String> = ids.iter().map(|x| x.to_string()).collect();
+                    return Err(eyre!(
+                        "Asset {} is ambiguous, use one of: {}",
+                        asset,
+                        ids.join(", ")
+                    ));
+                }
+            }
+        }
+        Err(eyre!("Unknown asset {}", asset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::mock_pool::MockPool;
+
+    const ATOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    // OSMO, ATOM and USDC of the osmosis asset list, a SHARE token without exponent and an
+    // ATOM on juno over a channel whose origin is not known
+    fn registry() -> AssetRegistry {
+        let path = format!(
+            "{}/tests/fixtures/osmosis_assetlist.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut registry = AssetRegistry::default();
+        registry.add_asset_list(Path::new(&path)).unwrap();
+        registry.insert(Asset {
+            symbol: "ATOM".to_owned(),
+            exponent: Some(6),
+            base_denom: "uatom".to_owned(),
+            ibc_path: Some("transfer/channel-1".to_owned()),
+            ..Asset::unknown(
+                "juno",
+                "ibc/C4CFF46FD6DE35CA4CF4CE031E643C8FDC9BA4B99AE598E9B0ED98FE3A2319F9",
+            )
+        });
+        registry
+    }
+
+    #[test]
+    fn ids_by_denom_symbol_and_base_denom() {
+        let registry = registry();
+        let find = |x| registry.find_id(x, &[]).unwrap().to_string();
+        assert_eq!(find(ATOM), "cosmoshub:uatom");
+        assert_eq!(find("osmo"), "osmosis:uosmo");
+        assert_eq!(find("USDC"), "noble:uusdc");
+        assert_eq!(find("usdc"), "noble:uusdc");
+        assert_eq!(find("uusdc"), "noble:uusdc");
+        assert_eq!(find("uosmo"), "osmosis:uosmo");
+        assert_eq!(find("cosmoshub:uatom"), "cosmoshub:uatom");
+        assert!(registry.find_id("cosmoshub:uosmo", &[]).is_err());
+        let err = registry.find_id("uion", &[]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown asset uion");
+    }
+
+    #[test]
+    fn ambiguous_symbol_is_an_error() {
+        let registry = registry();
+        let err = registry.find_id("ATOM", &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Asset ATOM is ambiguous, use one of: cosmoshub:uatom, juno/transfer/channel-1:uatom"
+        );
+        // the denom of each of them is not
+        assert_eq!(
+            registry.find_id(ATOM, &[]).unwrap().to_string(),
+            "cosmoshub:uatom"
+        );
+    }
+
+    #[test]
+    fn unknown_denoms_are_native_to_their_chain() {
+        let registry = registry();
+        let pools = vec![MockPool::pair("osmosis", "pool", ("uosmo", 1), ("uion", 1)).boxed()];
+        let assets = registry.pool_assets(pools[0].as_ref());
+        assert_eq!(assets[0].symbol, "OSMO");
+        assert_eq!(assets[1], Asset::unknown("osmosis", "uion"));
+        // unknown denoms have no symbol, only their denom finds them
+        assert_eq!(
+            registry.find_id("uion", &pools).unwrap().to_string(),
+            "osmosis:uion"
+        );
+        // and an unknown ibc denom has no origin
+        let unknown = Asset::unknown("osmosis", "ibc/ABCD");
+        assert_eq!(unknown.origin_chain, None);
+        assert_eq!(unknown.id().to_string(), "osmosis:ibc/ABCD");
+    }
+
+    #[tokio::test]
+    async fn resolve_takes_known_and_unknown_native_denoms() {
+        let mut registry = registry();
+        // neither needs a request
+        let api = EndpointPool::default();
+        let osmo = registry.resolve(&api, "osmosis", "uosmo").await.unwrap();
+        assert_eq!(osmo.exponent, Some(6));
+        let ion = registry.resolve(&api, "osmosis", "uion").await.unwrap();
+        assert_eq!(ion, Asset::unknown("osmosis", "uion"));
+        assert_eq!(registry.get("osmosis", "uion"), Some(&ion));
+    }
+
+    #[test]
+    fn amounts_in_display_and_base_units() {
+        let registry = registry();
+        let atom = AssetId::new("cosmoshub", "uatom");
+        let parse = |x| registry.parse_amount(x, &atom);
+        assert_eq!(parse("1.5 ATOM").unwrap(), 1_500_000);
+        assert_eq!(parse("1.5atom").unwrap(), 1_500_000);
+        assert_eq!(parse("250000uatom").unwrap(), 250_000);
+        let ibc_amount = format!("250000 {}", ATOM);
+        assert_eq!(parse(&ibc_amount).unwrap(), 250_000);
+        assert_eq!(parse("250000").unwrap(), 250_000);
+        assert!(parse("1.5").is_err());
+        assert!(parse("1.5 OSMO").is_err());
+        assert!(parse("1.0000001 ATOM").is_err());
+
+        let share = registry.find_id("SHARE", &[]).unwrap();
+        assert!(registry.parse_amount("10ushare", &share).is_err());
+        assert_eq!(
+            registry
+                .parse_amount("10 factory/osmo1pool/ushare", &share)
+                .unwrap(),
+            10
+        );
+        let err = registry.parse_amount("1.5 SHARE", &share).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Exponent of osmosis:factory/osmo1pool/ushare is not known, give the amount in factory/osmo1pool/ushare"
+        );
+    }
+
+    // denom traces by hash and the chain ids of the channels of a node
+    fn serve_traces() -> EndpointPool {
+        use warp::Filter;
+        let traces = warp::path!("ibc" / "apps" / "transfer" / "v1" / "denom_traces" / String).map(
+            |hash: String| {
+                let path = match hash.as_str() {
+                    "KNOWNPATH" => "transfer/channel-0",
+                    "OTHERPATH" => "transfer/channel-141",
+                    "UNKNOWNCHAIN" => "transfer/channel-7",
+                    _ => "transfer/channel-1/transfer/channel-2",
+                };
+                warp::reply::json(&serde_json::json!({
+                    "denom_trace": { "path": path, "base_denom": "uatom" }
+                }))
+            },
+        );
+        let channels = warp::path!(
+            "ibc"
+                / "core"
+                / "channel"
+                / "v1"
+                / "channels"
+                / String
+                / "ports"
+                / String
+                / "client_state"
+        )
+        .map(|channel: String, _port: String| {
+            let chain_id = match channel.as_str() {
+                "channel-141" => "cosmoshub-4",
+                _ => "unknown-1",
+            };
+            warp::reply::json(&serde_json::json!({
+                "identified_client_state": { "client_state": { "chain_id": chain_id } }
+            }))
+        });
+        let (address, server) =
+            warp::serve(traces.or(channels)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        EndpointPool::new(vec![format!("http://{}", address)])
+    }
+
+    #[tokio::test]
+    async fn same_base_denom_over_different_paths() {
+        let mut registry = registry();
+        registry
+            .chain_names
+            .insert("cosmoshub-4".to_owned(), "cosmoshub".to_owned());
+        let api = serve_traces();
+        for (hash, id) in [
+            // over the channel of the ATOM of the asset list
+            ("KNOWNPATH", "cosmoshub:uatom"),
+            // over another channel to the hub, found by its chain id
+            ("OTHERPATH", "cosmoshub:uatom"),
+            // the origin of the others is not known, they get an id per path
+            ("UNKNOWNCHAIN", "osmosis/transfer/channel-7:uatom"),
+            (
+                "MULTIHOP",
+                "osmosis/transfer/channel-1/transfer/channel-2:uatom",
+            ),
+        ] {
+            let denom = format!("ibc/{}", hash);
+            let asset = registry.resolve(&api, "osmosis", &denom).await.unwrap();
+            assert_eq!(asset.id().to_string(), id, "{}", hash);
+            // symbol and exponent come from the ATOM of the asset list either way
+            assert_eq!(asset.symbol, "ATOM");
+            assert_eq!(asset.exponent, Some(6));
+        }
+        // one id per origin, the juno ATOM over a channel of unknown origin keeps its own
+        let atoms = registry.by_symbol("ATOM");
+        let mut ids: Vec<String> = atoms.iter().map(|x| x.id().to_string()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(
+            ids,
+            [
+                "cosmoshub:uatom",
+                "juno/transfer/channel-1:uatom",
+                "osmosis/transfer/channel-1/transfer/channel-2:uatom",
+                "osmosis/transfer/channel-7:uatom",
+            ]
+        );
+    }
+
+    #[test]
+    fn asset_id_round_trip() {
+        for text in [
+            "cosmoshub:uatom",
+            "osmosis/transfer/channel-7:uatom",
+            "juno:cw20:juno1token",
+        ] {
+            let id: AssetId = text.parse().unwrap();
+            assert_eq!(id.to_string(), text);
+            let json = serde_json::to_string(&id).unwrap();
+            assert_eq!(json, format!("\"{}\"", text));
+            assert_eq!(serde_json::from_str::<AssetId>(&json).unwrap(), id);
+        }
+        // the origin ends at the first colon, cw20 denoms keep theirs
+        let cw20: AssetId = "juno:cw20:juno1token".parse().unwrap();
+        assert_eq!(cw20.origin, "juno");
+        assert_eq!(cw20.base_denom, "cw20:juno1token");
+        for text in ["uatom", ":uatom", "cosmoshub:", ""] {
+            assert!(text.parse::<AssetId>().is_err(), "{}", text);
+        }
+        assert!(serde_json::from_str::<AssetId>("\"uatom\"").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::util::connection::{ConnectionManager, ConnectionOptions};
+use crate::util::endpoint::EndpointPool;
+use crate::PoolConfig;
+
+/// Pool implementation used to load a snapshot file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolAdapter {
+    // balancer pools of osmosis
+    Osmosis,
+    // stableswap pools of osmosis, loaded from the same pool list as the balancer pools
+    Stableswap,
+    // concentrated liquidity pools of osmosis with their tick liquidity
+    Concentrated,
+    // wasmswap pools, found through `code_ids` like the terraswap pairs
+    Wasmswap,
+    // cosmwasm pairs with the terraswap interface, found through `factories` and `code_ids`
+    Terraswap,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdapterConfig {
+    pub adapter: PoolAdapter,
+    pub snapshot: String,
+    // factory contracts whose pairs are loaded, cosmwasm adapters only
+    #[serde(default)]
+    pub factories: Vec<String>,
+    // code ids of the pool contracts, cosmwasm adapters only
+    #[serde(default)]
+    pub code_ids: Vec<u64>,
+}
+
+/// Everything tracy needs to know about a chain, the first url of every list is
+/// the primary endpoint, the others are fallbacks.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChainConfig {
+    pub name: String,
+    pub chain_id: String,
+    pub bech32_prefix: String,
+    #[serde(default)]
+    pub grpc_urls: Vec<String>,
+    #[serde(default)]
+    pub rest_urls: Vec<String>,
+    #[serde(default)]
+    pub rpc_urls: Vec<String>,
+    #[serde(default)]
+    pub pools: Vec<AdapterConfig>,
+    #[serde(default = "default_estimate_quote")]
+    pub estimate_quote: bool,
+    #[serde(default)]
+    pub connection: ConnectionOptions,
+    // chain-registry style assetlist.json with symbols and exponents
+    #[serde(default)]
+    pub asset_list: Option<String>,
+}
+
+fn default_estimate_quote() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TracyConfig {
+    pub chains: Vec<ChainConfig>,
+    // snapshot of the asset registry, written by `load`
+    #[serde(default = "default_assets")]
+    pub assets: String,
+}
+
+fn default_assets() -> String {
+    "./assets.json".to_owned()
+}
+
+impl TracyConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut text: String = "".to_string();
+        file.read_to_string(&mut text)?;
+        let config: TracyConfig = serde_json::from_str(&text)?;
+        Ok(config)
+    }
+
+    pub fn chain(&self, name: &str) -> Option<&ChainConfig> {
+        self.chains.iter().find(|x| x.name == name)
+    }
+}
+
+// public nodes we used during the hackathon
+impl Default for TracyConfig {
+    fn default() -> Self {
+        TracyConfig {
+            chains: vec![
+                ChainConfig {
+                    name: "osmosis".to_owned(),
+                    chain_id: "osmosis-1".to_owned(),
+                    bech32_prefix: "osmo".to_owned(),
+                    grpc_urls: vec!["https://osmosis-grpc.polkachu.com:12590".to_owned()],
+                    rest_urls: vec!["https://lcd.osmosis.zone".to_owned()],
+                    rpc_urls: vec![],
+                    pools: vec![AdapterConfig {
+                        adapter: PoolAdapter::Osmosis,
+                        snapshot: "./osmosis_pools_hackathon.json".to_owned(),
+                        factories: vec![],
+                        code_ids: vec![],
+                    }],
+                    estimate_quote: true,
+                    connection: ConnectionOptions::default(),
+                    asset_list: Some("./osmosis_assetlist.json".to_owned()),
+                },
+                ChainConfig {
+                    name: "juno".to_owned(),
+                    chain_id: "juno-1".to_owned(),
+                    bech32_prefix: "juno".to_owned(),
+                    grpc_urls: vec![],
+                    rest_urls: vec!["https://lcd-juno.itastakers.com".to_owned()],
+                    rpc_urls: vec![],
+                    pools: vec![AdapterConfig {
+                        adapter: PoolAdapter::Wasmswap,
+                        snapshot: "./juno_pools.json".to_owned(),
+                        factories: vec![],
+                        code_ids: vec![16],
+                    }],
+                    estimate_quote: true,
+                    connection: ConnectionOptions::default(),
+                    asset_list: Some("./juno_assetlist.json".to_owned()),
+                },
+            ],
+            assets: default_assets(),
+        }
+    }
+}
+
+impl TryFrom<&ChainConfig> for PoolConfig {
+    type Error = eyre::Report;
+
+    fn try_from(chain: &ChainConfig) -> Result<Self> {
+        // one connection manager per chain, shared by all of its endpoints
+        let connections = ConnectionManager::new(chain.connection)?;
+        let endpoints =
+            |urls: &Vec<String>| EndpointPool::with_connections(urls.clone(), connections.clone());
+        Ok(PoolConfig {
+            grpc: endpoints(&chain.grpc_urls),
+            rest: endpoints(&chain.rest_urls),
+            rpc: endpoints(&chain.rpc_urls),
+            chain_id: chain.chain_id.clone(),
+            bech32_prefix: chain.bech32_prefix.clone(),
+            estimate_quote: chain.estimate_quote,
+        })
+    }
+}
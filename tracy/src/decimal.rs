@@ -0,0 +1,654 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ethnum::{I256, U256};
+use eyre::{eyre, Result};
+
+use crate::util::proto::cosmos_base_v1beta1::{Coin, DecCoin, DecProto, IntProto};
+
+// sdk.Dec has 18 decimals
+pub const DEC_DECIMALS: u32 = 18;
+
+const PRECISION: I256 = I256::new(1_000_000_000_000_000_000);
+
+// osmomath.Pow approximates the fractional power until a term is below 10^-8
+const POW_PRECISION: Dec = Dec(I256::new(10_000_000_000));
+const POW_ITERATION_LIMIT: u64 = 150_000;
+
+#[derive(Debug, Clone, Copy)]
+enum Rounding {
+    Down,
+    Up,
+    HalfEven,
+}
+
+fn overflow() -> eyre::Report {
+    eyre!("Overflow while calculating quote")
+}
+
+// rounds the absolute value `quotient + remainder / d` of a division
+fn round_quotient(
+    quotient: U256,
+    remainder: U256,
+    d: U256,
+    negative: bool,
+    rounding: Rounding,
+) -> Result<I256> {
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        // the remainder is below d, twice of it fits
+        Rounding::HalfEven => {
+            let twice = remainder * 2;
+            twice > d || (twice == d && quotient % 2 == 1)
+        }
+    };
+    let quotient = if round_up {
+        quotient.checked_add(U256::ONE).ok_or_else(overflow)?
+    } else {
+        quotient
+    };
+    let quotient = I256::try_from(quotient).map_err(|_| overflow())?;
+    Ok(if negative { -quotient } else { quotient })
+}
+
+// `n / d` rounded away from zero for `Up` and to the even neighbour on ties for `HalfEven`
+fn div_round(n: I256, d: I256, rounding: Rounding) -> Result<I256> {
+    if d == I256::ZERO {
+        return Err(eyre!("Division by zero"));
+    }
+    let negative = n.is_negative() != d.is_negative();
+    let (n, d) = (n.unsigned_abs(), d.unsigned_abs());
+    round_quotient(n / d, n % d, d, negative, rounding)
+}
+
+/// Fixed-point decimal with 18 decimals and the rounding of the cosmos sdk `Dec`.
+///
+/// All operations are checked, intermediates have 256 bits. Serialized like the sdk
+/// formats it, e.g. `"0.003000000000000000"`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(into = "String", try_from = "String")]
+pub struct Dec(I256);
+
+impl Dec {
+    pub const ZERO: Dec = Dec(I256::ZERO);
+    pub const ONE: Dec = Dec(PRECISION);
+
+    /// Decimal from its integer representation with 18 decimals, `1` is `10^-18`.
+    pub fn from_atomics(atomics: I256) -> Self {
+        Dec(atomics)
+    }
+
+    pub fn atomics(&self) -> I256 {
+        self.0
+    }
+
+    /// Parses a decimal of a protobuf message, sdk decimals are sent as integers with 18
+    /// decimals there but some chains and the REST api use the decimal form.
+    pub fn from_proto(value: &str) -> Result<Self> {
+        if value.contains('.') {
+            return value.parse();
+        }
+        let atomics = value
+            .parse::<I256>()
+            .map_err(|_| eyre!("Invalid decimal: {}", value))?;
+        Ok(Dec(atomics))
+    }
+
+    /// `10^exponent`
+    pub fn pow10(exponent: u32) -> Result<Self> {
+        I256::new(10)
+            .checked_pow(exponent)
+            .and_then(|x| x.checked_mul(PRECISION))
+            .map(Dec)
+            .ok_or_else(overflow)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == I256::ZERO
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn checked_add(self, other: Dec) -> Result<Dec> {
+        self.0.checked_add(other.0).map(Dec).ok_or_else(overflow)
+    }
+
+    pub fn checked_sub(self, other: Dec) -> Result<Dec> {
+        self.0.checked_sub(other.0).map(Dec).ok_or_else(overflow)
+    }
+
+    // with a = a1 * 10^18 + a0 and b likewise, a * b / 10^18 is
+    // a1 * b1 * 10^18 + a1 * b0 + a0 * b1 + a0 * b0 / 10^18. Only the last term is
+    // divided, so the product of the atomics does not have to fit into 256 bits.
+    fn mul_rounded(self, other: Dec, rounding: Rounding) -> Result<Dec> {
+        let negative = self.is_negative() != other.is_negative();
+        let precision = PRECISION.unsigned_abs();
+        let (a, b) = (self.0.unsigned_abs(), other.0.unsigned_abs());
+        let (a1, a0) = (a / precision, a % precision);
+        let (b1, b0) = (b / precision, b % precision);
+        let low = a0 * b0;
+        let quotient = a1
+            .checked_mul(b1)
+            .and_then(|x| x.checked_mul(precision))
+            .and_then(|x| x.checked_add(a1.checked_mul(b0)?))
+            .and_then(|x| x.checked_add(a0.checked_mul(b1)?))
+            .and_then(|x| x.checked_add(low / precision))
+            .ok_or_else(overflow)?;
+        round_quotient(quotient, low % precision, precision, negative, rounding).map(Dec)
+    }
+
+    /// Product rounded half to even like `Dec.Mul`.
+    pub fn checked_mul(self, other: Dec) -> Result<Dec> {
+        self.mul_rounded(other, Rounding::HalfEven)
+    }
+
+    pub fn mul_truncate(self, other: Dec) -> Result<Dec> {
+        self.mul_rounded(other, Rounding::Down)
+    }
+
+    pub fn mul_round_up(self, other: Dec) -> Result<Dec> {
+        self.mul_rounded(other, Rounding::Up)
+    }
+
+    fn quo_rounded(self, other: Dec, rounding: Rounding) -> Result<Dec> {
+        let numerator = self.0.checked_mul(PRECISION).ok_or_else(overflow)?;
+        div_round(numerator, other.0, rounding).map(Dec)
+    }
+
+    /// Quotient rounded half to even like `Dec.Quo`.
+    pub fn checked_quo(self, other: Dec) -> Result<Dec> {
+        self.quo_rounded(other, Rounding::HalfEven)
+    }
+
+    pub fn quo_truncate(self, other: Dec) -> Result<Dec> {
+        self.quo_rounded(other, Rounding::Down)
+    }
+
+    pub fn quo_round_up(self, other: Dec) -> Result<Dec> {
+        self.quo_rounded(other, Rounding::Up)
+    }
+
+    /// Integer power by squaring with the rounding of `Dec.Power`.
+    pub fn checked_pow(self, exponent: u64) -> Result<Dec> {
+        if exponent == 0 {
+            return Ok(Dec::ONE);
+        }
+        let mut result = Dec::ONE;
+        let mut base = self;
+        let mut exponent = exponent;
+        while exponent > 1 {
+            if exponent % 2 == 1 {
+                result = result.checked_mul(base)?;
+            }
+            exponent /= 2;
+            base = base.checked_mul(base)?;
+        }
+        base.checked_mul(result)
+    }
+
+    /// `self ^ exponent` for a base between 0 and 2 as osmomath.Pow calculates it, the
+    /// fractional part of the exponent is approximated with a binomial series.
+    pub fn pow(self, exponent: Dec) -> Result<Dec> {
+        if self <= Dec::ZERO || self >= Dec(PRECISION * 2) {
+            return Err(eyre!("Power base {} is not between 0 and 2", self));
+        }
+        if exponent.is_negative() {
+            return Err(eyre!("Negative exponent {}", exponent));
+        }
+        let integer = exponent.truncate_int();
+        let fractional = exponent.checked_sub(integer.to_dec()?)?;
+        let integer = u64::try_from(integer.0).map_err(|_| overflow())?;
+        let integer_pow = self.checked_pow(integer)?;
+        if fractional.is_zero() {
+            return Ok(integer_pow);
+        }
+        integer_pow.checked_mul(self.pow_approx(fractional)?)
+    }
+
+    // osmomath.PowApprox, sums the terms of (1 + (base - 1))^exponent until they get
+    // smaller than POW_PRECISION
+    fn pow_approx(self, exponent: Dec) -> Result<Dec> {
+        if exponent.is_zero() {
+            return Ok(Dec::ONE);
+        }
+        let x = self.checked_sub(Dec::ONE)?;
+        let (x, x_negative) = (Dec(x.0.abs()), x.is_negative());
+        let mut term = Dec::ONE;
+        let mut sum = Dec::ONE;
+        let mut negative = false;
+        let mut i = 1;
+        while term >= POW_PRECISION {
+            // exponent - (i - 1)
+            let c = exponent.checked_sub(Dec::from(i - 1))?;
+            let (c, c_negative) = (Dec(c.0.abs()), c.is_negative());
+            // associated like osmomath, term * (c * x)
+            term = term
+                .checked_mul(c.checked_mul(x)?)?
+                .checked_quo(Dec::from(i))?;
+            if term.is_zero() {
+                break;
+            }
+            if x_negative {
+                negative = !negative;
+            }
+            if c_negative {
+                negative = !negative;
+            }
+            sum = if negative {
+                sum.checked_sub(term)?
+            } else {
+                sum.checked_add(term)?
+            };
+            if i == POW_ITERATION_LIMIT {
+                return Err(eyre!("Power of {} did not converge", self));
+            }
+            i += 1;
+        }
+        Ok(sum)
+    }
+
+    /// Square root rounded up like `osmomath.MonotonicSqrt`.
+    pub fn sqrt(self) -> Result<Dec> {
+        if self.is_negative() {
+            return Err(eyre!("Square root of negative {}", self));
+        }
+        let n = self
+            .0
+            .unsigned_abs()
+            .checked_mul(PRECISION.unsigned_abs())
+            .ok_or_else(overflow)?;
+        if n < 2 {
+            return Ok(self);
+        }
+        // newton iteration from a start above the root decreases to its floor
+        let bits = 256 - n.leading_zeros();
+        let mut x = U256::ONE << bits.div_ceil(2);
+        loop {
+            let y = (x + n / x) >> 1;
+            if y >= x {
+                break;
+            }
+            x = y;
+        }
+        if x * x < n {
+            x += 1;
+        }
+        I256::try_from(x).map(Dec).map_err(|_| overflow())
+    }
+
+    pub fn truncate_int(&self) -> Int {
+        Int(self.0 / PRECISION)
+    }
+
+    pub fn ceil_int(&self) -> Int {
+        let int = self.0 / PRECISION;
+        if self.0 % PRECISION > 0 {
+            Int(int + 1)
+        } else {
+            Int(int)
+        }
+    }
+}
+
+impl From<u128> for Dec {
+    fn from(value: u128) -> Self {
+        // 2^128 * 10^18 fits into 255 bits
+        Dec(I256::from(value) * PRECISION)
+    }
+}
+
+impl From<u64> for Dec {
+    fn from(value: u64) -> Self {
+        Dec::from(value as u128)
+    }
+}
+
+impl From<i64> for Dec {
+    fn from(value: i64) -> Self {
+        Dec(I256::from(value) * PRECISION)
+    }
+}
+
+impl FromStr for Dec {
+    type Err = eyre::Report;
+
+    /// Parses `1`, `-0.5` or `0.003000000000000000`, at most 18 decimals.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || eyre!("Invalid decimal: {}", value);
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(x) => (true, x),
+            None => (false, value),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_number = |x: &str| x.bytes().all(|x| x.is_ascii_digit());
+        if int.is_empty() || !is_number(int) || !is_number(frac) {
+            return Err(invalid());
+        }
+        if frac.len() > DEC_DECIMALS as usize {
+            return Err(eyre!("{} has more than {} decimals", value, DEC_DECIMALS));
+        }
+        let atomics = format!("{}{:0<width$}", int, frac, width = DEC_DECIMALS as usize)
+            .parse::<I256>()
+            .map_err(|_| invalid())?;
+        Ok(Dec(if negative { -atomics } else { atomics }))
+    }
+}
+
+impl fmt::Display for Dec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let atomics = self.0.unsigned_abs();
+        let precision = PRECISION.unsigned_abs();
+        write!(
+            f,
+            "{}{}.{:0>width$}",
+            sign,
+            atomics / precision,
+            atomics % precision,
+            width = DEC_DECIMALS as usize
+        )
+    }
+}
+
+impl From<Dec> for String {
+    fn from(value: Dec) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Dec {
+    type Error = eyre::Report;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&DecProto> for Dec {
+    type Error = eyre::Report;
+
+    fn try_from(value: &DecProto) -> Result<Self> {
+        Dec::from_proto(&value.dec)
+    }
+}
+
+/// Signed 256 bit integer with checked operations like the cosmos sdk `Int`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Int(I256);
+
+impl Int {
+    pub const ZERO: Int = Int(I256::ZERO);
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == I256::ZERO
+    }
+
+    pub fn checked_add(self, other: Int) -> Result<Int> {
+        self.0.checked_add(other.0).map(Int).ok_or_else(overflow)
+    }
+
+    pub fn checked_sub(self, other: Int) -> Result<Int> {
+        self.0.checked_sub(other.0).map(Int).ok_or_else(overflow)
+    }
+
+    pub fn checked_mul(self, other: Int) -> Result<Int> {
+        self.0.checked_mul(other.0).map(Int).ok_or_else(overflow)
+    }
+
+    /// Quotient rounded towards zero.
+    pub fn checked_quo(self, other: Int) -> Result<Int> {
+        div_round(self.0, other.0, Rounding::Down).map(Int)
+    }
+
+    pub fn quo_round_up(self, other: Int) -> Result<Int> {
+        div_round(self.0, other.0, Rounding::Up).map(Int)
+    }
+
+    pub fn to_dec(&self) -> Result<Dec> {
+        self.0.checked_mul(PRECISION).map(Dec).ok_or_else(overflow)
+    }
+
+    /// Amount of a coin, errors for negative values or values above 128 bits.
+    pub fn to_u128(&self) -> Result<u128> {
+        u128::try_from(self.0).map_err(|_| overflow())
+    }
+}
+
+impl From<u128> for Int {
+    fn from(value: u128) -> Self {
+        Int(I256::from(value))
+    }
+}
+
+impl FromStr for Int {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let int = value
+            .parse::<I256>()
+            .map_err(|_| eyre!("Invalid integer: {}", value))?;
+        Ok(Int(int))
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&IntProto> for Int {
+    type Error = eyre::Report;
+
+    fn try_from(value: &IntProto) -> Result<Self> {
+        value.int.parse()
+    }
+}
+
+impl TryFrom<&Coin> for Int {
+    type Error = eyre::Report;
+
+    fn try_from(value: &Coin) -> Result<Self> {
+        value.amount.parse()
+    }
+}
+
+/// Denom and amount of a `DecCoin`, e.g. rewards or community pool balances.
+pub fn parse_dec_coin(coin: &DecCoin) -> Result<(String, Dec)> {
+    Ok((coin.denom.clone(), Dec::from_proto(&coin.amount)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Dec {
+        value.parse().unwrap()
+    }
+
+    fn assert_close(value: Dec, expected: &str, tolerance: &str) {
+        let difference = Dec(value.checked_sub(dec(expected)).unwrap().0.abs());
+        assert!(
+            difference <= dec(tolerance),
+            "{} is not within {} of {}",
+            value,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn mul_rounds_half_to_even() {
+        let atomics = |x: i64| Dec(I256::from(x));
+        let tenth = dec("0.1");
+        assert_eq!(atomics(5).checked_mul(tenth).unwrap(), Dec::ZERO);
+        assert_eq!(atomics(15).checked_mul(tenth).unwrap(), atomics(2));
+        assert_eq!(atomics(25).checked_mul(tenth).unwrap(), atomics(2));
+        assert_eq!(atomics(26).checked_mul(tenth).unwrap(), atomics(3));
+        assert_eq!(atomics(-15).checked_mul(tenth).unwrap(), atomics(-2));
+        assert_eq!(atomics(15).mul_truncate(tenth).unwrap(), atomics(1));
+        assert_eq!(atomics(5).mul_round_up(tenth).unwrap(), atomics(1));
+        assert_eq!(dec("1.5").checked_mul(dec("-2.25")).unwrap(), dec("-3.375"));
+    }
+
+    #[test]
+    fn quo_rounds_half_to_even() {
+        assert_eq!(
+            Dec::ONE.checked_quo(Dec::from(3u64)).unwrap(),
+            dec("0.333333333333333333")
+        );
+        assert_eq!(
+            Dec::from(2u64).checked_quo(Dec::from(3u64)).unwrap(),
+            dec("0.666666666666666667")
+        );
+        // vectors of the sdk Dec tests
+        assert_eq!(
+            dec("0.3333").checked_quo(dec("0.0333")).unwrap(),
+            dec("10.009009009009009009")
+        );
+        assert_eq!(
+            dec("0.3333").quo_round_up(dec("0.0333")).unwrap(),
+            dec("10.009009009009009010")
+        );
+        assert_eq!(
+            Dec::from(2u64).quo_truncate(Dec::from(3u64)).unwrap(),
+            dec("0.666666666666666666")
+        );
+        assert!(Dec::ONE.checked_quo(Dec::ZERO).is_err());
+    }
+
+    #[test]
+    fn mul_of_large_operands() {
+        // the product of the atomics has more than 256 bits, the result does not
+        assert_eq!(
+            Dec::pow10(30)
+                .unwrap()
+                .checked_mul(Dec::pow10(20).unwrap())
+                .unwrap(),
+            Dec::pow10(50).unwrap()
+        );
+        let a = Dec::pow10(30).unwrap().checked_add(dec("0.5")).unwrap();
+        let b = Dec::pow10(20).unwrap().checked_add(dec("0.25")).unwrap();
+        assert_eq!(
+            a.checked_mul(b).unwrap(),
+            dec("100000000000000000000250000000050000000000000000000.125")
+        );
+        assert!(Dec::pow10(40)
+            .unwrap()
+            .checked_mul(Dec::pow10(40).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn pow_like_osmomath() {
+        // computed with the association of osmomath, term * (c * x)
+        assert_eq!(
+            dec("1.68").pow(dec("0.32")).unwrap(),
+            dec("1.180589649075586119")
+        );
+        assert_eq!(
+            dec("1.777").pow(Dec::from(20u64)).unwrap(),
+            dec("98570.862372081602208445")
+        );
+        assert_eq!(dec("0.0000123").pow(Dec::from(123u64)).unwrap(), Dec::ZERO);
+        assert_eq!(Dec::ONE.pow(Dec::from(123u64)).unwrap(), Dec::ONE);
+        // vectors of the osmomath tests, within the precision of the approximation
+        assert_close(
+            dec("0.8").pow(dec("0.32")).unwrap(),
+            "0.93108385",
+            "0.00000001",
+        );
+        assert_close(
+            dec("1.68").pow(dec("0.32")).unwrap(),
+            "1.18058965",
+            "0.00000001",
+        );
+        assert_close(
+            dec("1.9999").pow(dec("0.23")).unwrap(),
+            "1.172821461",
+            "0.00000001",
+        );
+        assert!(Dec::from(2u64).pow(dec("0.5")).is_err());
+        assert!(Dec::ZERO.pow(dec("0.5")).is_err());
+    }
+
+    #[test]
+    fn pow_approx_like_osmomath() {
+        assert_eq!(dec("0.5").pow_approx(Dec::ZERO).unwrap(), Dec::ONE);
+        assert_close(
+            dec("0.8").pow_approx(dec("0.32")).unwrap(),
+            "0.93108385",
+            "0.00000001",
+        );
+        assert_close(
+            dec("1.5").pow_approx(dec("0.5")).unwrap(),
+            "1.224744871",
+            "0.00000001",
+        );
+    }
+
+    #[test]
+    fn sqrt_like_osmomath() {
+        assert_eq!(Dec::ZERO.sqrt().unwrap(), Dec::ZERO);
+        assert_eq!(Dec::ONE.sqrt().unwrap(), Dec::ONE);
+        assert_eq!(dec("0.25").sqrt().unwrap(), dec("0.5"));
+        assert_eq!(dec("0.09").sqrt().unwrap(), dec("0.3"));
+        assert_eq!(Dec::from(9u64).sqrt().unwrap(), Dec::from(3u64));
+        assert_eq!(Dec::from(2u64).sqrt().unwrap(), dec("1.414213562373095049"));
+        assert_eq!(
+            dec("0.000000000000000001").sqrt().unwrap(),
+            dec("0.000000001")
+        );
+        assert_eq!(
+            dec("0.000000000000000003").sqrt().unwrap(),
+            dec("0.000000001732050808")
+        );
+        assert!(dec("-1").sqrt().is_err());
+    }
+
+    #[test]
+    fn parse_protos() {
+        // sdk decimals are integers with 18 decimals in protobuf
+        assert_eq!(Dec::from_proto("3000000000000000").unwrap(), dec("0.003"));
+        assert_eq!(
+            Dec::from_proto("0.003000000000000000").unwrap(),
+            dec("0.003")
+        );
+        let proto = DecProto {
+            dec: "1500000000000000000".to_owned(),
+        };
+        assert_eq!(Dec::try_from(&proto).unwrap(), dec("1.5"));
+        assert!(Dec::from_proto("0.3.0").is_err());
+        assert!(Dec::from_proto("abc").is_err());
+        assert_eq!(dec("0.003").to_string(), "0.003000000000000000");
+
+        let proto = IntProto {
+            // 2^128
+            int: "340282366920938463463374607431768211456".to_owned(),
+        };
+        assert!(Int::try_from(&proto).unwrap().to_u128().is_err());
+        let proto = IntProto {
+            int: "2500000".to_owned(),
+        };
+        assert_eq!(Int::try_from(&proto).unwrap(), Int::from(2_500_000u128));
+        let proto = IntProto {
+            int: "2.5".to_owned(),
+        };
+        assert!(Int::try_from(&proto).is_err());
+    }
+}
@@ -1,92 +1,195 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use crate::{
-    pools::{juno_pool::load_juno_pools_from_file, osmosis_pool::load_osmo_pools_from_file_boxed},
-    Pool, PoolConfig,
+    assets::{AssetId, AssetRegistry},
+    config::{PoolAdapter, TracyConfig},
+    pools::{
+        concentrated_pool::load_concentrated_pools_from_file_boxed,
+        juno_pool::load_juno_pools_from_file, osmosis_pool::load_osmo_pools_from_file_boxed,
+        stableswap_pool::load_stableswap_pools_from_file_boxed,
+        terraswap_pool::load_terraswap_pools_from_file,
+    },
+    router::{Route, Router},
+    split::{SplitOptions, SplitQuote},
+    util::{
+        block::{latest_block, BlockInfo},
+        endpoint::{probe_grpc, probe_rest, EndpointStats},
+    },
+    Pool, PoolConfig, Quote, QuoteSource,
 };
-use eyre::Result;
-use tokio::sync::Mutex;
+use eyre::{eyre, Result};
+use futures::{future::join_all, stream, StreamExt};
+use log::warn;
+use tokio::{sync::Mutex, task::JoinHandle};
+
+pub const DEFAULT_QUOTE_CONCURRENCY: usize = 16;
+pub const DEFAULT_QUOTE_TIMEOUT: Duration = Duration::from_secs(15);
+pub const DEFAULT_REFRESH_CONCURRENCY: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteOptions {
+    // quotes in flight at once
+    pub concurrency: usize,
+    // per pool, including failover to other endpoints
+    pub timeout: Duration,
+    // amount is the token_out amount and the quotes contain the needed token_in amount
+    pub exact_out: bool,
+}
+
+impl Default for QuoteOptions {
+    fn default() -> Self {
+        QuoteOptions {
+            concurrency: DEFAULT_QUOTE_CONCURRENCY,
+            timeout: DEFAULT_QUOTE_TIMEOUT,
+            exact_out: false,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct DexAgg {
     pub pools: Arc<Mutex<Vec<Box<dyn Pool>>>>,
     pub config: HashMap<String, PoolConfig>,
+    // symbols and exponents of the pool denoms
+    pub assets: Arc<AssetRegistry>,
+    // when the reserves of a pool were last fetched from the chain, by pool address
+    pub last_updated: Arc<Mutex<HashMap<String, SystemTime>>>,
+    // block of the chain when the reserves of a pool were last fetched, by pool address
+    pub last_blocks: Arc<Mutex<HashMap<String, BlockInfo>>>,
+    refresher: Arc<std::sync::Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl DexAgg {
     pub fn new(extra_pools: Option<&mut Vec<Box<dyn Pool>>>) -> Result<Self> {
-        let mut osmo_pools =
-            load_osmo_pools_from_file_boxed(Path::new("./osmosis_pools_hackathon.json"))?;
-        let mut juno_pools = load_juno_pools_from_file(Path::new("./juno_pools.json"))?;
-        let mut pools: Vec<Box<dyn Pool>> = osmo_pools
-            .drain(..)
-            .map(|x| Box::<dyn Pool>::from(x))
-            .collect();
-        pools.append(
-            &mut juno_pools
-                .drain(..)
-                .map(|x| Box::<dyn Pool>::from(x))
-                .collect::<Vec<Box<dyn Pool>>>(),
-        );
-        if extra_pools.is_some() {
-            pools.append(extra_pools.unwrap());
-        }
+        DexAgg::from_config(&TracyConfig::default(), extra_pools)
+    }
+
+    pub fn from_config(
+        tracy_config: &TracyConfig,
+        extra_pools: Option<&mut Vec<Box<dyn Pool>>>,
+    ) -> Result<Self> {
+        let mut pools: Vec<Box<dyn Pool>> = vec![];
         let mut config = HashMap::new();
-        config.insert(
-            "osmosis".to_owned(),
-            PoolConfig {
-                grpc_url: Some("https://osmosis-grpc.polkachu.com:12590".to_owned()),
-                rest_url: None,
-                rpc_url: None,
-                estimate_quote: true,
-            },
-        );
-        config.insert(
-            "juno".to_owned(),
-            PoolConfig {
-                grpc_url: None,
-                rest_url: Some("https://lcd-juno.itastakers.com".to_owned()),
-                rpc_url: None,
-                estimate_quote: true,
-            },
-        );
+        for chain in &tracy_config.chains {
+            for adapter in &chain.pools {
+                let path = Path::new(&adapter.snapshot);
+                match adapter.adapter {
+                    PoolAdapter::Osmosis => {
+                        for mut pool in load_osmo_pools_from_file_boxed(path)? {
+                            pool.chain = Some(chain.name.clone());
+                            pools.push(pool);
+                        }
+                    }
+                    PoolAdapter::Stableswap => {
+                        for mut pool in load_stableswap_pools_from_file_boxed(path)? {
+                            pool.chain = Some(chain.name.clone());
+                            pools.push(pool);
+                        }
+                    }
+                    PoolAdapter::Concentrated => {
+                        for mut pool in load_concentrated_pools_from_file_boxed(path)? {
+                            pool.chain = Some(chain.name.clone());
+                            pools.push(pool);
+                        }
+                    }
+                    PoolAdapter::Wasmswap => {
+                        for mut pool in load_juno_pools_from_file(path)? {
+                            pool.chain = Some(chain.name.clone());
+                            pools.push(pool);
+                        }
+                    }
+                    PoolAdapter::Terraswap => {
+                        for mut pool in load_terraswap_pools_from_file(path)? {
+                            pool.chain = Some(chain.name.clone());
+                            pools.push(pool);
+                        }
+                    }
+                }
+            }
+            config.insert(chain.name.clone(), PoolConfig::try_from(chain)?);
+        }
+        if let Some(extra_pools) = extra_pools {
+            pools.append(extra_pools);
+        }
         Ok(DexAgg {
             pools: Arc::new(Mutex::new(pools)),
-            config: config,
+            config,
+            assets: Arc::new(AssetRegistry::from_config(tracy_config)?),
+            last_updated: Arc::new(Mutex::new(HashMap::new())),
+            last_blocks: Arc::new(Mutex::new(HashMap::new())),
+            refresher: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
-    pub async fn with_denom(&self, denom: &String) -> Vec<Box<dyn Pool>> {
-        self.pools
-            .lock()
-            .await
-            .clone()
-            .into_iter()
-            .filter(|x| x.token_denoms().contains(denom))
-            .collect()
+    pub fn from_config_file(
+        path: &Path,
+        extra_pools: Option<&mut Vec<Box<dyn Pool>>>,
+    ) -> Result<Self> {
+        DexAgg::from_config(&TracyConfig::load(path)?, extra_pools)
     }
 
-    // TODO make &str
-    pub async fn with_denoms(&self, denoms: Vec<String>) -> Vec<Box<dyn Pool>> {
+    /// Id of an asset given as id, on-chain denom, symbol or base denom,
+    /// see [`AssetRegistry::find_id`].
+    pub async fn asset_id(&self, asset: &str) -> Result<AssetId> {
+        let pools = self.pools.lock().await;
+        self.assets.find_id(asset, &pools)
+    }
+
+    /// Raw amount of an asset from user input like `1.5 ATOM` or `250000uosmo`,
+    /// see [`AssetRegistry::parse_amount`].
+    pub async fn parse_amount(&self, amount: &str, asset: &str) -> Result<u128> {
+        let id = self.asset_id(asset).await?;
+        self.assets.parse_amount(amount, &id)
+    }
+
+    // pools holding every one of the assets
+    pub async fn with_assets(&self, ids: &[AssetId]) -> Vec<Box<dyn Pool>> {
         self.pools
             .lock()
             .await
             .clone()
             .into_iter()
             .filter(|x| {
-                let token_denoms = x.token_denoms();
-                denoms.iter().all(|x| token_denoms.contains(x))
+                let assets: Vec<AssetId> = self
+                    .assets
+                    .pool_assets(x.as_ref())
+                    .iter()
+                    .map(|x| x.id())
+                    .collect();
+                ids.iter().all(|x| assets.contains(x))
             })
             .collect()
     }
 
+    pub async fn with_denom(&self, denom: &str) -> Vec<Box<dyn Pool>> {
+        self.with_denoms(vec![denom.to_owned()]).await
+    }
+
+    // like `with_assets`, unknown or ambiguous assets match no pool
+    pub async fn with_denoms(&self, denoms: Vec<String>) -> Vec<Box<dyn Pool>> {
+        let mut ids = vec![];
+        for denom in &denoms {
+            match self.asset_id(denom).await {
+                Ok(id) => ids.push(id),
+                Err(_) => return vec![],
+            }
+        }
+        self.with_assets(&ids).await
+    }
+
     pub async fn with_address(&self, addr: &str) -> Result<Box<dyn Pool>> {
         let pools = self.pools.lock().await;
-        let index = pools
+        pools
             .iter()
-            .position(|x| x.address().unwrap() == addr)
-            .unwrap();
-        Ok(pools[index].clone())
+            .find(|x| x.address().is_ok_and(|x| x == addr))
+            .cloned()
+            .ok_or_else(|| eyre!("No pool with address {}", addr))
     }
 
     pub async fn with_chain(&self, chain: &str) -> Vec<Box<dyn Pool>> {
@@ -98,4 +201,393 @@ impl DexAgg {
             .filter(|x| x.chain() == chain)
             .collect()
     }
+
+    pub async fn router(&self) -> Router {
+        Router::new(self.pools.lock().await.clone(), &self.assets)
+    }
+
+    // also finds routes over several pools if there is no pool with both assets
+    pub async fn best_route(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        max_hops: usize,
+    ) -> Result<Route> {
+        let token_in = self.asset_id(token_in_denom).await?;
+        let token_out = self.asset_id(token_out_denom).await?;
+        self.router()
+            .await
+            .best_route(amount, &token_in, &token_out, max_hops, &self.config)
+            .await
+    }
+
+    // splits the amount over several pools/routes, see [`Router::best_split`]
+    pub async fn best_split(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        options: &SplitOptions,
+    ) -> Result<SplitQuote> {
+        let token_in = self.asset_id(token_in_denom).await?;
+        let token_out = self.asset_id(token_out_denom).await?;
+        self.router()
+            .await
+            .best_split(amount, &token_in, &token_out, options)
+    }
+
+    /// Quotes every pool containing both assets concurrently, the assets are given as id,
+    /// on-chain denom, symbol or base denom.
+    ///
+    /// Failed or timed out pools are returned as quotes with `error` set instead of failing
+    /// the batch. Quotes are sorted best first, most token_out (least token_in for
+    /// `exact_out`), errors last. Fails if an asset is unknown or ambiguous.
+    pub async fn quote_all(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        options: &QuoteOptions,
+    ) -> Result<Vec<Quote>> {
+        let token_in = self.asset_id(token_in_denom).await?;
+        let token_out = self.asset_id(token_out_denom).await?;
+        if token_in == token_out {
+            return Err(eyre!("Cannot swap {} to itself", token_in));
+        }
+        let pools = self
+            .with_assets(&[token_in.clone(), token_out.clone()])
+            .await;
+        // simulations run against the latest block of their chain
+        let simulated = pools
+            .iter()
+            .map(|x| x.chain())
+            .filter(|x| self.config.get(x).is_some_and(|x| x.estimate_quote));
+        let blocks = self.latest_blocks(simulated).await;
+        // collected first, streaming the lazy map trips up the Send check of the server handlers
+        let quotes: Vec<_> = pools
+            .into_iter()
+            .map(|pool| self.quote_pool(pool, amount, &token_in, &token_out, &blocks, options))
+            .collect();
+        let mut quotes: Vec<Quote> = stream::iter(quotes)
+            .buffer_unordered(options.concurrency.max(1))
+            .collect()
+            .await;
+        quotes.sort_by(|a, b| compare_quotes(a, b, options.exact_out));
+        Ok(quotes)
+    }
+
+    async fn quote_pool(
+        &self,
+        pool: Box<dyn Pool>,
+        amount: u128,
+        token_in: &AssetId,
+        token_out: &AssetId,
+        blocks: &HashMap<String, BlockInfo>,
+        options: &QuoteOptions,
+    ) -> Quote {
+        // the pool is quoted with its own denoms of the assets
+        let denoms = (
+            self.assets.pool_denom(pool.as_ref(), token_in),
+            self.assets.pool_denom(pool.as_ref(), token_out),
+        );
+        let quote = match (self.config.get(&pool.chain()), &denoms) {
+            (Some(config), (Some(token_in_denom), Some(token_out_denom))) => {
+                let quote = if options.exact_out {
+                    pool.get_quote_exact_out(amount, token_in_denom, token_out_denom, config)
+                } else {
+                    pool.get_quote(amount, token_in_denom, token_out_denom, config)
+                };
+                match tokio::time::timeout(options.timeout, quote).await {
+                    Ok(x) => x,
+                    Err(_) => Err(eyre!("Timed out after {:?}", options.timeout)),
+                }
+            }
+            (None, _) => Err(eyre!("No config for chain {}", pool.chain())),
+            (_, _) => Err(eyre!(
+                "Pool does not contain {} and {}",
+                token_in,
+                token_out
+            )),
+        };
+        match quote {
+            Ok(mut quote) => {
+                if let (Some(token_in_denom), Some(token_out_denom)) = &denoms {
+                    quote.add_details(pool.as_ref(), token_in_denom, token_out_denom);
+                }
+                quote.amount_in = quote.token_in.map(|x| self.assets.amount(x, token_in));
+                quote.amount_out = quote.token_out.map(|x| self.assets.amount(x, token_out));
+                quote.fee_amount = quote.fee.as_ref().map(|fee| {
+                    let asset = if quote.token_out_denom.as_ref() == Some(&fee.denom) {
+                        token_out
+                    } else {
+                        token_in
+                    };
+                    self.assets.amount(fee.amount, asset)
+                });
+                quote.block = match quote.source {
+                    Some(QuoteSource::Simulation) => blocks.get(&pool.chain()).cloned(),
+                    _ => match pool.address() {
+                        Ok(address) => self.last_blocks.lock().await.get(&address).cloned(),
+                        Err(_) => None,
+                    },
+                };
+                quote
+            }
+            Err(e) => Quote {
+                pool_address: pool.address().ok(),
+                chain: Some(pool.chain()),
+                error: Some(format!("{:#}", e)),
+                ..Default::default()
+            },
+        }
+    }
+
+    // latest block of every chain, chains whose block cannot be fetched are left out
+    async fn latest_blocks(
+        &self,
+        chains: impl Iterator<Item = String>,
+    ) -> HashMap<String, BlockInfo> {
+        let mut chains: Vec<String> = chains.collect();
+        chains.sort();
+        chains.dedup();
+        let blocks = join_all(chains.iter().map(|chain| async move {
+            let config = self.config.get(chain)?;
+            latest_block(&config.rest).await.ok()
+        }))
+        .await;
+        chains
+            .into_iter()
+            .zip(blocks)
+            .filter_map(|(chain, block)| Some((chain, block?)))
+            .collect()
+    }
+
+    async fn refresh_pool(&self, pool: Box<dyn Pool>) -> Result<(String, Box<dyn Pool>)> {
+        let address = pool.address()?;
+        let config = self
+            .config
+            .get(&pool.chain())
+            .ok_or_else(|| eyre!("No config for chain {}", pool.chain()))?;
+        let refreshed = pool
+            .refresh(config)
+            .await
+            .map_err(|e| eyre!("Cannot refresh pool {}: {:#}", address, e))?;
+        Ok((address, refreshed))
+    }
+
+    /// Fetches the current reserves of every pool and swaps them into `pools`.
+    ///
+    /// All refreshed pools are replaced under one lock, pools which failed keep their
+    /// previous state. A pool is not replaced when it was fetched at a lower height than
+    /// its current state. Returns the errors of the failed pools.
+    pub async fn refresh_pools(&self, concurrency: usize) -> Vec<String> {
+        self.refresh_pools_where(|_| true, concurrency).await
+    }
+
+    // like `refresh_pools` but only for the pools matching `filter`
+    pub async fn refresh_pools_where<F>(&self, filter: F, concurrency: usize) -> Vec<String>
+    where
+        F: Fn(&dyn Pool) -> bool,
+    {
+        let pools: Vec<Box<dyn Pool>> = self
+            .pools
+            .lock()
+            .await
+            .iter()
+            .filter(|x| filter(x.as_ref()))
+            .cloned()
+            .collect();
+        // the block is fetched before the reserves, so they are at least as new as it
+        let blocks = self.latest_blocks(pools.iter().map(|x| x.chain())).await;
+        // collected first for the same reason as in quote_all
+        let refreshes: Vec<_> = pools
+            .into_iter()
+            .map(|pool| self.refresh_pool(pool))
+            .collect();
+        let results: Vec<Result<(String, Box<dyn Pool>)>> = stream::iter(refreshes)
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut errors = vec![];
+        let mut refreshed = HashMap::new();
+        for result in results {
+            match result {
+                Ok((address, pool)) => {
+                    let block = blocks.get(&pool.chain()).cloned();
+                    refreshed.insert(address, (pool, block));
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        let now = SystemTime::now();
+        let mut pools = self.pools.lock().await;
+        let mut last_updated = self.last_updated.lock().await;
+        let mut last_blocks = self.last_blocks.lock().await;
+        for pool in pools.iter_mut() {
+            let address = match pool.address() {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            if let Some((new_pool, block)) = refreshed.remove(&address) {
+                // the subscriber refreshes pools concurrently to the refresher, a slower
+                // refresh must not replace reserves of a later block, neither may reserves
+                // of an unknown block
+                let stored = last_blocks.get(&address).map(|x| x.height);
+                match (stored, &block) {
+                    (Some(stored), Some(block)) if block.height < stored => continue,
+                    (Some(stored), None) => {
+                        errors.push(format!(
+                            "Kept reserves of pool {} from block {}, the block of the refresh is unknown",
+                            address, stored
+                        ));
+                        continue;
+                    }
+                    _ => {}
+                }
+                *pool = new_pool;
+                if let Some(block) = block {
+                    last_blocks.insert(address.clone(), block);
+                }
+                last_updated.insert(address, now);
+            }
+        }
+        errors
+    }
+
+    /// Starts refreshing all pools every `interval` in the background, replaces a running refresher.
+    pub fn start_refresher(&self, interval: Duration) {
+        let dex = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let errors = dex.refresh_pools(DEFAULT_REFRESH_CONCURRENCY).await;
+                if !errors.is_empty() {
+                    warn!("Could not refresh {} pools: {}", errors.len(), errors[0]);
+                }
+            }
+        });
+        if let Some(old) = self.refresher.lock().unwrap().replace(handle) {
+            old.abort();
+        }
+    }
+
+    pub fn stop_refresher(&self) {
+        if let Some(handle) = self.refresher.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+
+    /// Re-probes endpoints with an open circuit of every chain each `interval`.
+    pub fn spawn_health_checks(&self, interval: Duration) -> Vec<JoinHandle<()>> {
+        self.config
+            .values()
+            .flat_map(|config| {
+                let http = config.rest.connections().http().clone();
+                vec![
+                    config.grpc.spawn_prober(interval, probe_grpc),
+                    config
+                        .rest
+                        .spawn_prober(interval, move |url| probe_rest(http.clone(), url)),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn endpoint_stats(&self) -> HashMap<String, HashMap<&'static str, Vec<EndpointStats>>> {
+        self.config
+            .iter()
+            .map(|(chain, config)| {
+                let stats = HashMap::from([
+                    ("grpc", config.grpc.stats()),
+                    ("rest", config.rest.stats()),
+                    ("rpc", config.rpc.stats()),
+                ]);
+                (chain.clone(), stats)
+            })
+            .collect()
+    }
+}
+
+// best quote first, quotes without amount last
+fn compare_quotes(a: &Quote, b: &Quote, exact_out: bool) -> Ordering {
+    if exact_out {
+        match (a.token_in, b.token_in) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        }
+    } else {
+        match (a.token_out, b.token_out) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChainConfig;
+    use crate::pools::mock_pool::MockPool;
+
+    // osmosis dex without nodes, so the block of a refresh is unknown
+    fn mock_dex() -> DexAgg {
+        let mut pools = vec![
+            MockPool::pair("osmosis", "pool-ab", ("a", 1_000), ("b", 1_000)).boxed(),
+            MockPool::pair("osmosis", "pool-bc", ("b", 1_000), ("c", 1_000)).boxed(),
+        ];
+        let config = TracyConfig {
+            chains: vec![ChainConfig {
+                name: "osmosis".to_owned(),
+                chain_id: "osmosis-1".to_owned(),
+                bech32_prefix: "osmo".to_owned(),
+                grpc_urls: vec![],
+                rest_urls: vec![],
+                rpc_urls: vec![],
+                pools: vec![],
+                estimate_quote: false,
+                connection: Default::default(),
+                asset_list: None,
+            }],
+            assets: format!(
+                "{}/tests/fixtures/missing_assets.json",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+        };
+        DexAgg::from_config(&config, Some(&mut pools)).unwrap()
+    }
+
+    async fn refreshes(dex: &DexAgg, address: &str) -> u32 {
+        let pool = dex.with_address(address).await.unwrap();
+        pool.as_any().downcast_ref::<MockPool>().unwrap().refreshes
+    }
+
+    #[tokio::test]
+    async fn refresh_of_an_unknown_block_keeps_reserves_of_a_known_block() {
+        let dex = mock_dex();
+        let block = BlockInfo {
+            height: 100,
+            time: "2024-03-01T12:00:00Z".to_owned(),
+        };
+        dex.last_blocks
+            .lock()
+            .await
+            .insert("pool-ab".to_owned(), block.clone());
+
+        let errors = dex.refresh_pools(DEFAULT_REFRESH_CONCURRENCY).await;
+        assert_eq!(
+            errors,
+            vec![
+                "Kept reserves of pool pool-ab from block 100, the block of the refresh is unknown"
+            ]
+        );
+        assert_eq!(refreshes(&dex, "pool-ab").await, 0);
+        assert_eq!(dex.last_blocks.lock().await.get("pool-ab"), Some(&block));
+        // pools without a known block are replaced
+        assert_eq!(refreshes(&dex, "pool-bc").await, 1);
+        assert!(dex.last_updated.lock().await.contains_key("pool-bc"));
+        assert!(!dex.last_updated.lock().await.contains_key("pool-ab"));
+    }
 }
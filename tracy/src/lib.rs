@@ -1,27 +1,178 @@
+use std::fmt;
+
+use amount::Amount;
 use async_trait::async_trait;
+use decimal::Dec;
 use dyn_clone::DynClone;
-use eyre::Result;
+use eyre::{eyre, Result};
+use util::block::BlockInfo;
+use util::endpoint::EndpointPool;
 
+pub mod amount;
+pub mod assets;
+pub mod config;
+pub mod decimal;
 pub mod dex;
 pub mod pools;
+pub mod router;
+pub mod split;
+pub mod subscriber;
+pub mod tx;
 pub mod util;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// How the amounts of a quote were computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteSource {
+    // swap math on the stored state of the pool
+    Offline,
+    // swap simulated by a node of the chain
+    Simulation,
+}
+
+/// Fee of a swap in the token it is taken from, most pools take it from the input.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Fee {
+    pub rate: Dec,
+    pub amount: u128,
+    pub denom: String,
+}
+
+impl Fee {
+    // fee taken from `amount_in` before the swap
+    pub fn from_input(rate: Dec, amount_in: u128, denom: &str) -> Result<Self> {
+        let amount = Dec::from(amount_in).mul_truncate(rate)?.truncate_int();
+        Ok(Fee {
+            rate,
+            amount: amount.to_u128()?,
+            denom: denom.to_owned(),
+        })
+    }
+
+    // commission taken from the output, `amount_out` is what is left of it
+    pub fn from_output(rate: Dec, amount_out: u128, denom: &str) -> Result<Self> {
+        let amount = Dec::from(amount_out)
+            .checked_mul(rate)?
+            .quo_truncate(Dec::ONE.checked_sub(rate)?)?
+            .truncate_int();
+        Ok(Fee {
+            rate,
+            amount: amount.to_u128()?,
+            denom: denom.to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Quote {
     pub token_in: Option<u128>,
     pub token_out: Option<u128>,
     pub pool_address: Option<String>,
     pub error: Option<String>,
+    // token_in and token_out with their asset, set by `DexAgg`
+    pub amount_in: Option<Amount>,
+    pub amount_out: Option<Amount>,
+    // on-chain denoms the pool was quoted with
+    pub token_in_denom: Option<String>,
+    pub token_out_denom: Option<String>,
+    pub chain: Option<String>,
+    pub source: Option<QuoteSource>,
+    pub fee: Option<Fee>,
+    // fee with its asset, set by `DexAgg`
+    pub fee_amount: Option<Amount>,
+    // token_out per token_in in base units without fees, before and after the swap
+    pub spot_price_before: Option<Dec>,
+    pub spot_price_after: Option<Dec>,
+    // token_out per token_in in base units the swap gives, fees included
+    pub effective_rate: Option<Dec>,
+    // percent the rate without fees is below the spot price before the swap
+    pub price_impact: Option<Dec>,
+    // block the pool state was fetched at, the latest block for simulations
+    pub block: Option<BlockInfo>,
 }
 
-#[derive(Debug, Clone)]
+impl Quote {
+    /// Fills in denoms, chain, fee, spot prices, effective rate and price impact of a
+    /// successful quote of `pool`. Whatever the pool cannot tell stays `None`.
+    pub fn add_details(&mut self, pool: &dyn Pool, token_in_denom: &str, token_out_denom: &str) {
+        self.token_in_denom = Some(token_in_denom.to_owned());
+        self.token_out_denom = Some(token_out_denom.to_owned());
+        self.chain = Some(pool.chain());
+        let (amount_in, amount_out) = match (self.token_in, self.token_out) {
+            (Some(amount_in), Some(amount_out)) => (amount_in, amount_out),
+            _ => return,
+        };
+        self.fee = pool
+            .fee(amount_in, amount_out, token_in_denom, token_out_denom)
+            .ok();
+        self.spot_price_before = pool.spot_price(token_in_denom, token_out_denom).ok();
+        self.spot_price_after = pool
+            .spot_price_after(amount_in, amount_out, token_in_denom, token_out_denom)
+            .ok();
+        self.effective_rate = Dec::from(amount_out).checked_quo(Dec::from(amount_in)).ok();
+        self.price_impact = self.price_impact(amount_in, amount_out).ok();
+    }
+
+    // 100 * (1 - rate without fees / spot price before)
+    fn price_impact(&self, amount_in: u128, amount_out: u128) -> Result<Dec> {
+        let spot_price = self
+            .spot_price_before
+            .ok_or_else(|| eyre!("No spot price"))?;
+        let (amount_in, amount_out) = match &self.fee {
+            Some(fee) if self.token_out_denom.as_ref() == Some(&fee.denom) => {
+                (amount_in, amount_out.saturating_add(fee.amount))
+            }
+            Some(fee) => (amount_in.saturating_sub(fee.amount), amount_out),
+            None => (amount_in, amount_out),
+        };
+        let rate = Dec::from(amount_out).checked_quo(Dec::from(amount_in))?;
+        Dec::ONE
+            .checked_sub(rate.checked_quo(spot_price)?)?
+            .checked_mul(Dec::from(100u64))
+    }
+}
+
+impl fmt::Display for Quote {
+    // `1.5 ATOM -> 12.25 OSMO`, raw amounts if the assets are not known
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(error) = &self.error {
+            return write!(f, "Error: {}", error);
+        }
+        let amount = |amount: &Option<Amount>, raw: Option<u128>| match (amount, raw) {
+            (Some(x), _) => x.to_string(),
+            (None, Some(x)) => x.to_string(),
+            (None, None) => "?".to_owned(),
+        };
+        write!(
+            f,
+            "{} -> {}",
+            amount(&self.amount_in, self.token_in),
+            amount(&self.amount_out, self.token_out)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PoolConfig {
-    pub grpc_url: Option<String>,
-    pub rest_url: Option<String>,
-    pub rpc_url: Option<String>,
+    pub grpc: EndpointPool,
+    pub rest: EndpointPool,
+    pub rpc: EndpointPool,
+    pub chain_id: String,
+    pub bech32_prefix: String,
     pub estimate_quote: bool,
 }
 
+impl PoolConfig {
+    // source of the quotes made with this config
+    pub fn quote_source(&self) -> QuoteSource {
+        if self.estimate_quote {
+            QuoteSource::Simulation
+        } else {
+            QuoteSource::Offline
+        }
+    }
+}
+
 // Send + Static may be unsafe(probably is) but we  use it in DexAgg behind a mutex
 #[async_trait]
 pub trait Pool: DynClone + Send + Sync {
@@ -33,9 +184,85 @@ pub trait Pool: DynClone + Send + Sync {
         config: &PoolConfig,
     ) -> Result<Quote>;
 
+    // token_in amount needed to receive exactly `amount` of token_out
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote>;
+
+    // token_out amount calculated from the stored reserves, no network access
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128>;
+
+    // token_in amount for exactly `amount` of token_out, no network access
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128>;
+
+    // copy of the pool with the current reserves from the chain
+    async fn refresh(&self, _config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        Err(eyre!("Pool {} cannot be refreshed", self.address()?))
+    }
+
+    // token_out per token_in in base units without fees, from the stored state
+    fn spot_price(&self, token_in_denom: &str, token_out_denom: &str) -> Result<Dec> {
+        self.spot_price_after(0, 0, token_in_denom, token_out_denom)
+    }
+
+    // spot price once `amount_in` went into and `amount_out` left the stored state
+    fn spot_price_after(
+        &self,
+        _amount_in: u128,
+        _amount_out: u128,
+        _token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Dec> {
+        Err(eyre!("Pool {} has no spot price", self.address()?))
+    }
+
+    // fee paid for swapping `amount_in` to `amount_out`
+    fn fee(
+        &self,
+        _amount_in: u128,
+        _amount_out: u128,
+        _token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Fee> {
+        Err(eyre!("Pool {} has no fee", self.address()?))
+    }
+
     fn token_denoms(&self) -> Vec<String>;
+    // on-chain denom of every token, `cw20:<address>` for cw20 tokens
+    fn asset_denoms(&self) -> Vec<String> {
+        self.token_denoms()
+    }
+    // every (token_in, token_out) combination the pool can be quoted for
+    fn token_pairs(&self) -> Vec<(String, String)> {
+        let denoms = self.token_denoms();
+        let mut pairs = vec![];
+        for token_in in &denoms {
+            for token_out in &denoms {
+                if token_in != token_out {
+                    pairs.push((token_in.clone(), token_out.clone()));
+                }
+            }
+        }
+        pairs
+    }
     fn to_json(&self) -> String;
     fn chain(&self) -> String;
+    // gives access to the concrete pool type, e.g. to build transactions
+    fn as_any(&self) -> &dyn std::any::Any;
     // unique identifer
     fn address(&self) -> Result<String>;
 }
@@ -4,23 +4,25 @@ use tracy::dex::DexAgg;
 async fn main() {
     let dexes = DexAgg::new(None).unwrap();
     let amount = "1000000";
-    let denom1 = "ujuno".to_string();
-    let denom2 = "uatom".to_string();
+    let token_in = dexes.asset_id("ujuno").await.unwrap();
+    let token_out = dexes.asset_id("uatom").await.unwrap();
     let pools = dexes
-        .with_denoms(vec![denom1.clone(), denom2.clone()])
-        .await
-        .clone();
+        .with_assets(&[token_in.clone(), token_out.clone()])
+        .await;
     let mut quotes = vec![];
     for pool in pools {
+        // every pool is quoted with its own denoms of the assets
+        let denom1 = dexes.assets.pool_denom(pool.as_ref(), &token_in).unwrap();
+        let denom2 = dexes.assets.pool_denom(pool.as_ref(), &token_out).unwrap();
         let quote = pool
             .get_quote(
-                u128::from_str_radix(&amount, 10).unwrap(),
+                amount.parse::<u128>().unwrap(),
                 &denom1,
                 &denom2,
                 dexes
                     .config
                     .get(&pool.chain())
-                    .expect(&format!("No config for chain {}", pool.chain())),
+                    .unwrap_or_else(|| panic!("No config for chain {}", pool.chain())),
             )
             .await;
         quotes.push(quote);
@@ -0,0 +1,751 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+
+use super::math::{concentrated_swap_step, concentrated_swap_step_exact_out, tick_to_price};
+use super::osmosis_pool::{
+    big_dec_from_proto, dec_from_proto, decode_pool, decode_pool_json, DecodedPool, GammPool,
+};
+use super::{load_pools_from_file, save_pools_to_file};
+use crate::decimal::{Dec, DEC_DECIMALS};
+use crate::util::proto::osmosis_concentratedliquidity_v1beta1::query_client::QueryClient;
+use crate::util::proto::osmosis_concentratedliquidity_v1beta1::{
+    LiquidityDepthWithRange, LiquidityPerTickRangeRequest, Pool as ConcentratedProtoPool,
+};
+use crate::util::proto::osmosis_poolmanager_v1beta1::query_client::QueryClient as PoolManagerClient;
+use crate::util::proto::osmosis_poolmanager_v1beta1::{
+    EstimateSwapExactAmountInRequest, EstimateSwapExactAmountOutRequest, PoolRequest,
+    SwapAmountInRoute, SwapAmountOutRoute,
+};
+use crate::{Fee, Pool, PoolConfig, Quote};
+
+pub const CONCENTRATED_POOL_TYPE_URL: &str = "/osmosis.concentratedliquidity.v1beta1.Pool";
+
+/// Liquidity of all positions between two initialized ticks.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct TickRange {
+    pub liquidity_amount: String,
+    pub lower_tick: String,
+    pub upper_tick: String,
+}
+
+impl From<LiquidityDepthWithRange> for TickRange {
+    fn from(range: LiquidityDepthWithRange) -> Self {
+        TickRange {
+            liquidity_amount: dec_from_proto(&range.liquidity_amount),
+            lower_tick: range.lower_tick.to_string(),
+            upper_tick: range.upper_tick.to_string(),
+        }
+    }
+}
+
+/// Osmosis concentrated liquidity pool, liquidity is provided for price ranges so a swap
+/// crosses ticks with different liquidity. `liquidity` holds the ranges of the whole pool.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ConcentratedPool {
+    #[serde(alias = "address")]
+    pub pool_address: String,
+    pub id: String,
+    pub token0: String,
+    pub token1: String,
+    #[serde(default)]
+    pub token0_native_name: Option<String>,
+    #[serde(default)]
+    pub token1_native_name: Option<String>,
+    pub current_tick_liquidity: String,
+    pub current_sqrt_price: String,
+    // int64 and uint64 are strings in the REST api
+    pub current_tick: String,
+    pub tick_spacing: String,
+    pub exponent_at_price_one: String,
+    pub spread_factor: String,
+    #[serde(default)]
+    pub liquidity: Vec<TickRange>,
+    pub chain: Option<String>,
+}
+
+impl From<ConcentratedProtoPool> for ConcentratedPool {
+    fn from(pool: ConcentratedProtoPool) -> Self {
+        ConcentratedPool {
+            pool_address: pool.address,
+            id: pool.id.to_string(),
+            token0: pool.token0,
+            token1: pool.token1,
+            token0_native_name: None,
+            token1_native_name: None,
+            current_tick_liquidity: dec_from_proto(&pool.current_tick_liquidity),
+            current_sqrt_price: big_dec_from_proto(&pool.current_sqrt_price),
+            current_tick: pool.current_tick.to_string(),
+            tick_spacing: pool.tick_spacing.to_string(),
+            exponent_at_price_one: pool.exponent_at_price_one.to_string(),
+            spread_factor: dec_from_proto(&pool.spread_factor),
+            liquidity: vec![],
+            chain: Some("osmosis".to_owned()),
+        }
+    }
+}
+
+struct Range {
+    lower: i64,
+    upper: i64,
+    liquidity: Dec,
+}
+
+// token_out per token_in at a square root price, the price is token1 per token0
+fn spot_price_at(sqrt_price: Dec, zero_for_one: bool) -> Result<Dec> {
+    let price = sqrt_price.checked_mul(sqrt_price)?;
+    if zero_for_one {
+        Ok(price)
+    } else {
+        Dec::ONE.checked_quo(price)
+    }
+}
+
+// the square root price is an osmomath.BigDec, decimals beyond the 18 of a Dec are cut off.
+// The swap math is done with Dec anyway, the cut off moves an output by less than
+// liquidity * 10^-18 of token1 or liquidity * 10^-18 / price of token0.
+fn parse_big_dec(value: &str) -> Result<Dec> {
+    match value.split_once('.') {
+        Some((int, frac)) if frac.len() > DEC_DECIMALS as usize => {
+            format!("{}.{}", int, &frac[..DEC_DECIMALS as usize]).parse()
+        }
+        _ => value.parse(),
+    }
+}
+
+impl ConcentratedPool {
+    // takes ibc or native denom, 0 for token0 and 1 for token1
+    fn token_index(&self, denom: &str) -> Result<usize> {
+        if self.token0 == denom || self.token0_native_name.as_deref() == Some(denom) {
+            Ok(0)
+        } else if self.token1 == denom || self.token1_native_name.as_deref() == Some(denom) {
+            Ok(1)
+        } else {
+            Err(eyre!("Pool does not contain {}", denom))
+        }
+    }
+
+    // token0 goes in and the price falls
+    fn zero_for_one(&self, token_in_denom: &str, token_out_denom: &str) -> Result<bool> {
+        let token_in = self.token_index(token_in_denom)?;
+        if token_in == self.token_index(token_out_denom)? {
+            return Err(eyre!("Cannot swap {} to itself", token_in_denom));
+        }
+        Ok(token_in == 0)
+    }
+
+    fn chain_denoms(&self, zero_for_one: bool) -> (String, String) {
+        if zero_for_one {
+            (self.token0.clone(), self.token1.clone())
+        } else {
+            (self.token1.clone(), self.token0.clone())
+        }
+    }
+
+    fn ranges(&self) -> Result<Vec<Range>> {
+        let mut ranges = vec![];
+        for range in &self.liquidity {
+            ranges.push(Range {
+                lower: range.lower_tick.parse::<i64>()?,
+                upper: range.upper_tick.parse::<i64>()?,
+                liquidity: range.liquidity_amount.parse::<Dec>()?,
+            });
+        }
+        ranges.sort_by_key(|x| x.lower);
+        Ok(ranges)
+    }
+
+    // walks the tick ranges in the direction of the swap until `amount` is used up, `amount`
+    // is the input after fees or the output. Returns the other side of the swap and the
+    // square root price after it.
+    fn swap(&self, amount: Dec, zero_for_one: bool, exact_out: bool) -> Result<(Dec, Dec)> {
+        let exponent = self.exponent_at_price_one.parse::<i64>()?;
+        let current_tick = self.current_tick.parse::<i64>()?;
+        let sqrt_price_at = |tick: i64| tick_to_price(tick, exponent)?.sqrt();
+        let mut sqrt_price = parse_big_dec(&self.current_sqrt_price)?;
+        let mut ranges = self.ranges()?;
+        if ranges.is_empty() {
+            return Err(eyre!("Pool {} has no tick liquidity", self.id));
+        }
+        if zero_for_one {
+            ranges.retain(|x| x.lower <= current_tick);
+            ranges.reverse();
+        } else {
+            ranges.retain(|x| x.upper > current_tick);
+        }
+
+        let mut remaining = amount;
+        let mut total = Dec::ZERO;
+        for range in ranges {
+            // the price jumps over ticks without liquidity
+            let (start, target) = if zero_for_one {
+                (
+                    sqrt_price.min(sqrt_price_at(range.upper)?),
+                    sqrt_price_at(range.lower)?,
+                )
+            } else {
+                (
+                    sqrt_price.max(sqrt_price_at(range.lower)?),
+                    sqrt_price_at(range.upper)?,
+                )
+            };
+            if exact_out {
+                let (next, amount_in, received) = concentrated_swap_step_exact_out(
+                    start,
+                    target,
+                    range.liquidity,
+                    remaining,
+                    zero_for_one,
+                )?;
+                sqrt_price = next;
+                remaining = remaining.checked_sub(received)?;
+                total = total.checked_add(amount_in)?;
+            } else {
+                let (next, used, amount_out) = concentrated_swap_step(
+                    start,
+                    target,
+                    range.liquidity,
+                    remaining,
+                    zero_for_one,
+                )?;
+                sqrt_price = next;
+                remaining = remaining.checked_sub(used)?;
+                total = total.checked_add(amount_out)?;
+            }
+            if remaining <= Dec::ZERO {
+                return Ok((total, sqrt_price));
+            }
+        }
+        Err(eyre!("Not enough liquidity in pool {}", self.id))
+    }
+
+    fn calculate_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let zero_for_one = self.zero_for_one(token_in_denom, token_out_denom)?;
+        let spread_factor = self.spread_factor.parse::<Dec>()?;
+        let amount = Dec::from(amount).checked_mul(Dec::ONE.checked_sub(spread_factor)?)?;
+        let (amount_out, _) = self.swap(amount, zero_for_one, false)?;
+        // never round in favour of the trader
+        amount_out.truncate_int().to_u128()
+    }
+
+    fn calculate_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let zero_for_one = self.zero_for_one(token_in_denom, token_out_denom)?;
+        let spread_factor = self.spread_factor.parse::<Dec>()?;
+        let (amount_in, _) = self.swap(Dec::from(amount), zero_for_one, true)?;
+        let amount_in = amount_in.checked_quo(Dec::ONE.checked_sub(spread_factor)?)?;
+        amount_in.ceil_int().to_u128()
+    }
+
+    async fn estimate_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<u128> {
+        let pool_id = self.pool_id()?;
+        let (token_in, token_out) =
+            self.chain_denoms(self.zero_for_one(token_in_denom, token_out_denom)?);
+        let request = EstimateSwapExactAmountInRequest {
+            pool_id,
+            token_in: format!("{}{}", amount, token_in),
+            routes: vec![SwapAmountInRoute {
+                pool_id,
+                token_out_denom: token_out,
+            }],
+            ..Default::default()
+        };
+        let response = config
+            .grpc
+            .run(|url| {
+                let request = request.clone();
+                async move {
+                    let mut client =
+                        PoolManagerClient::new(config.grpc.connections().channel(&url)?);
+                    Ok(client.estimate_swap_exact_amount_in(request).await?)
+                }
+            })
+            .await?;
+        Ok(response.into_inner().token_out_amount.parse::<u128>()?)
+    }
+
+    async fn estimate_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<u128> {
+        let pool_id = self.pool_id()?;
+        let (token_in, token_out) =
+            self.chain_denoms(self.zero_for_one(token_in_denom, token_out_denom)?);
+        let request = EstimateSwapExactAmountOutRequest {
+            pool_id,
+            routes: vec![SwapAmountOutRoute {
+                pool_id,
+                token_in_denom: token_in,
+            }],
+            token_out: format!("{}{}", amount, token_out),
+            ..Default::default()
+        };
+        let response = config
+            .grpc
+            .run(|url| {
+                let request = request.clone();
+                async move {
+                    let mut client =
+                        PoolManagerClient::new(config.grpc.connections().channel(&url)?);
+                    Ok(client.estimate_swap_exact_amount_out(request).await?)
+                }
+            })
+            .await?;
+        Ok(response.into_inner().token_in_amount.parse::<u128>()?)
+    }
+}
+
+impl GammPool for ConcentratedPool {
+    fn pool_id(&self) -> Result<u64> {
+        Ok(self.id.parse::<u64>()?)
+    }
+
+    fn chain_denom(&self, denom: &str) -> Option<String> {
+        match self.token_index(denom) {
+            Ok(0) => Some(self.token0.clone()),
+            Ok(_) => Some(self.token1.clone()),
+            Err(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RestTickRanges {
+    liquidity: Vec<TickRange>,
+}
+
+/// Liquidity per tick range of a pool, over gRPC if the config has gRPC urls and over REST otherwise.
+pub async fn fetch_tick_ranges(config: &PoolConfig, pool_id: u64) -> Result<Vec<TickRange>> {
+    if config.grpc.is_empty() {
+        let response: RestTickRanges = config
+            .rest
+            .run(|url| async move {
+                let url = format!(
+                    "{}/osmosis/concentratedliquidity/v1beta1/liquidity_per_tick_range",
+                    url
+                );
+                let http = config.rest.connections().http();
+                let query = [("pool_id", pool_id.to_string())];
+                Ok(http.get(url).query(&query).send().await?.json().await?)
+            })
+            .await?;
+        return Ok(response.liquidity);
+    }
+    let response = config
+        .grpc
+        .run(|url| async move {
+            let mut client = QueryClient::new(config.grpc.connections().channel(&url)?);
+            let request = LiquidityPerTickRangeRequest { pool_id };
+            Ok(client.liquidity_per_tick_range(request).await?)
+        })
+        .await?;
+    Ok(response
+        .into_inner()
+        .liquidity
+        .into_iter()
+        .map(TickRange::from)
+        .collect())
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RestPool {
+    pool: serde_json::Value,
+}
+
+// current price and tick of a pool, over gRPC if the config has gRPC urls and over REST otherwise
+async fn fetch_pool(config: &PoolConfig, pool_id: u64) -> Result<ConcentratedPool> {
+    let decoded = if config.grpc.is_empty() {
+        let response: RestPool = config
+            .rest
+            .run(|url| async move {
+                let url = format!("{}/osmosis/poolmanager/v1beta1/pools/{}", url, pool_id);
+                let http = config.rest.connections().http();
+                Ok(http
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?)
+            })
+            .await?;
+        let type_url = response.pool["@type"]
+            .as_str()
+            .unwrap_or_default()
+            .to_owned();
+        decode_pool_json(&type_url, response.pool)?
+    } else {
+        let response = config
+            .grpc
+            .run(|url| async move {
+                let mut client = PoolManagerClient::new(config.grpc.connections().channel(&url)?);
+                Ok(client.pool(PoolRequest { pool_id }).await?)
+            })
+            .await?;
+        let any = response
+            .into_inner()
+            .pool
+            .ok_or_else(|| eyre!("Pool {} not found", pool_id))?;
+        decode_pool(&any)?
+    };
+    match decoded {
+        DecodedPool::Concentrated(x) => Ok(x),
+        _ => Err(eyre!("Pool {} is not a concentrated pool", pool_id)),
+    }
+}
+
+#[async_trait]
+impl Pool for ConcentratedPool {
+    async fn get_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let token_out = if config.estimate_quote {
+            self.estimate_quote(amount, token_in_denom, token_out_denom, config)
+                .await?
+        } else {
+            self.calculate_quote(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount),
+            token_out: Some(token_out),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let token_in = if config.estimate_quote {
+            self.estimate_quote_exact_out(amount, token_in_denom, token_out_denom, config)
+                .await?
+        } else {
+            self.calculate_quote_exact_out(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(token_in),
+            token_out: Some(amount),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    // the current price and tick change with every swap, the ranges with every position change
+    async fn refresh(&self, config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        let pool_id = self.pool_id()?;
+        let current = fetch_pool(config, pool_id).await?;
+        let mut pool = self.clone();
+        pool.current_tick_liquidity = current.current_tick_liquidity;
+        pool.current_sqrt_price = current.current_sqrt_price;
+        pool.current_tick = current.current_tick;
+        pool.spread_factor = current.spread_factor;
+        pool.liquidity = fetch_tick_ranges(config, pool_id).await?;
+        Ok(Box::new(pool))
+    }
+
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote(amount, token_in_denom, token_out_denom)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote_exact_out(amount, token_in_denom, token_out_denom)
+    }
+
+    fn spot_price(&self, token_in_denom: &str, token_out_denom: &str) -> Result<Dec> {
+        let zero_for_one = self.zero_for_one(token_in_denom, token_out_denom)?;
+        spot_price_at(parse_big_dec(&self.current_sqrt_price)?, zero_for_one)
+    }
+
+    fn spot_price_after(
+        &self,
+        amount_in: u128,
+        _amount_out: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Dec> {
+        let zero_for_one = self.zero_for_one(token_in_denom, token_out_denom)?;
+        let spread_factor = self.spread_factor.parse::<Dec>()?;
+        let amount = Dec::from(amount_in).checked_mul(Dec::ONE.checked_sub(spread_factor)?)?;
+        let (_, sqrt_price) = self.swap(amount, zero_for_one, false)?;
+        spot_price_at(sqrt_price, zero_for_one)
+    }
+
+    fn fee(
+        &self,
+        amount_in: u128,
+        _amount_out: u128,
+        token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Fee> {
+        Fee::from_input(
+            self.spread_factor.parse::<Dec>()?,
+            amount_in,
+            token_in_denom,
+        )
+    }
+
+    fn token_denoms(&self) -> Vec<String> {
+        let mut denoms = vec![self.token0.clone(), self.token1.clone()];
+        denoms.extend(self.token0_native_name.clone());
+        denoms.extend(self.token1_native_name.clone());
+        denoms
+    }
+
+    fn asset_denoms(&self) -> Vec<String> {
+        vec![self.token0.clone(), self.token1.clone()]
+    }
+
+    fn token_pairs(&self) -> Vec<(String, String)> {
+        // ibc and native denom of an asset are the same token
+        let token0 = self
+            .token0_native_name
+            .clone()
+            .unwrap_or(self.token0.clone());
+        let token1 = self
+            .token1_native_name
+            .clone()
+            .unwrap_or(self.token1.clone());
+        vec![(token0.clone(), token1.clone()), (token1, token0)]
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    fn chain(&self) -> String {
+        self.chain
+            .clone()
+            .unwrap_or_else(|| String::from("osmosis"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn address(&self) -> Result<String> {
+        Ok(self.pool_address.clone())
+    }
+}
+
+/// Writes a concentrated liquidity snapshot, see [`crate::pools::osmosis_pool::fetch_osmosis_pool_list`].
+pub fn save_concentrated_pools_to_file(path: &Path, pools: &[ConcentratedPool]) -> Result<()> {
+    save_pools_to_file(path, pools)
+}
+
+pub fn load_concentrated_pools_from_file_boxed(path: &Path) -> Result<Vec<Box<ConcentratedPool>>> {
+    load_pools_from_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::endpoint::EndpointPool;
+    use warp::Filter;
+
+    const USDC: &str = "ibc/498A0751C798A0D9A389AA3691123DADA57DAA4FE165D5C75894505B876BA6E4";
+
+    // OSMO/USDC at a price of 1.5 with 0.2% spread factor, the ranges end at the prices
+    // 1.3, 1.45, 1.55 and 1.7 with a gap up to 1.8 and a last range up to 2. The expected
+    // amounts are the swap solved with exact fractions.
+    fn fixture_pool() -> ConcentratedPool {
+        let path = format!(
+            "{}/tests/fixtures/osmosis_concentrated_pools.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let text = std::fs::read_to_string(path).unwrap();
+        serde_json::from_str::<Vec<ConcentratedPool>>(&text)
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn exact_in_of_fixture() {
+        let pool = fixture_pool();
+        // inside the current range
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000, "uosmo", "uusdc").unwrap(),
+            1_495_172_457
+        );
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000, "uusdc", "uosmo").unwrap(),
+            664_791_618
+        );
+        // crosses the tick at 1.45 downwards, and the gap above 1.7 upwards
+        assert_eq!(
+            pool.simulate_swap(30_000_000_000, "uosmo", USDC).unwrap(),
+            42_900_122_054
+        );
+        assert_eq!(
+            pool.simulate_swap(50_000_000_000, "uusdc", "uosmo")
+                .unwrap(),
+            31_100_357_448
+        );
+    }
+
+    #[test]
+    fn exact_out_of_fixture() {
+        let pool = fixture_pool();
+        let quote =
+            |amount, token_in, token_out| pool.simulate_swap_exact_out(amount, token_in, token_out);
+        assert_eq!(quote(1_000_000_000, "uosmo", "uusdc").unwrap(), 668_548_540);
+        assert_eq!(
+            quote(1_000_000_000, "uusdc", "uosmo").unwrap(),
+            1_504_849_069
+        );
+        assert_eq!(
+            quote(30_000_000_000, "uosmo", "uusdc").unwrap(),
+            20_595_366_131
+        );
+        assert_eq!(
+            quote(30_000_000_000, "uusdc", "uosmo").unwrap(),
+            47_939_461_749
+        );
+    }
+
+    #[test]
+    fn exact_out_inverts_exact_in() {
+        let pool = fixture_pool();
+        for (amount_out, token_in, token_out) in [
+            (1_000_000_000, "uosmo", "uusdc"),
+            (30_000_000_000, "uosmo", "uusdc"),
+            (1_000_000_000, "uusdc", "uosmo"),
+            (30_000_000_000, "uusdc", "uosmo"),
+        ] {
+            let amount_in = pool
+                .simulate_swap_exact_out(amount_out, token_in, token_out)
+                .unwrap();
+            let received = pool.simulate_swap(amount_in, token_in, token_out).unwrap();
+            assert!(received >= amount_out);
+            // a unit of input is worth less than two units of output in this pool
+            assert!(received - amount_out <= 2, "{} {}", received, amount_out);
+        }
+    }
+
+    #[test]
+    fn swaps_beyond_the_last_range_fail() {
+        let pool = fixture_pool();
+        for (amount, token_in, token_out) in [
+            (100_000_000_000, "uosmo", "uusdc"),
+            (150_000_000_000, "uusdc", "uosmo"),
+        ] {
+            let err = pool.simulate_swap(amount, token_in, token_out).unwrap_err();
+            assert_eq!(err.to_string(), "Not enough liquidity in pool 9002");
+        }
+        assert!(pool
+            .simulate_swap_exact_out(100_000_000_000, "uosmo", "uusdc")
+            .is_err());
+        assert!(pool.simulate_swap(1_000, "uosmo", "uatom").is_err());
+    }
+
+    #[test]
+    fn cut_off_sqrt_price_stays_within_bound() {
+        let pool = fixture_pool();
+        let sqrt_price = parse_big_dec(&pool.current_sqrt_price).unwrap();
+        assert_eq!(sqrt_price.to_string(), "1.224744871391589049");
+        // the exact price is below the next Dec, the outputs of a liquidity of 10^12 may
+        // only move by the rounding of the result
+        let mut above = pool.clone();
+        above.current_sqrt_price = sqrt_price
+            .checked_add(Dec::from_atomics(1.into()))
+            .unwrap()
+            .to_string();
+        for (token_in, token_out) in [("uosmo", "uusdc"), ("uusdc", "uosmo")] {
+            let low = pool
+                .simulate_swap(1_000_000_000, token_in, token_out)
+                .unwrap();
+            let high = above
+                .simulate_swap(1_000_000_000, token_in, token_out)
+                .unwrap();
+            assert!(low.abs_diff(high) <= 1, "{} {}", low, high);
+        }
+    }
+
+    #[test]
+    fn invalid_exponent_is_an_error() {
+        let mut pool = fixture_pool();
+        pool.exponent_at_price_one = "-19".to_owned();
+        let err = pool.simulate_swap(1_000, "uosmo", "uusdc").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid exponent at price one -19");
+        assert!(tick_to_price(1, i64::MIN).is_err());
+    }
+
+    #[tokio::test]
+    async fn refresh_over_rest() {
+        let pool = fixture_pool();
+        let mut current = serde_json::to_value(&pool).unwrap();
+        current["@type"] = CONCENTRATED_POOL_TYPE_URL.into();
+        current["current_tick"] = "450000".into();
+        current["current_sqrt_price"] = "1.204159457879229548012824103037860805".into();
+        let ranges = serde_json::json!({ "liquidity": [pool.liquidity[0], pool.liquidity[1]] });
+        let routes = warp::path!("osmosis" / "poolmanager" / "v1beta1" / "pools" / u64)
+            .map(move |id| {
+                assert_eq!(id, 9002);
+                warp::reply::json(&serde_json::json!({ "pool": current }))
+            })
+            .or(warp::path!(
+                "osmosis" / "concentratedliquidity" / "v1beta1" / "liquidity_per_tick_range"
+            )
+            .map(move || warp::reply::json(&ranges)));
+        let (address, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let config = PoolConfig {
+            rest: EndpointPool::new(vec![format!("http://{}", address)]),
+            ..Default::default()
+        };
+        let refreshed = pool.refresh(&config).await.unwrap();
+        let refreshed = refreshed
+            .as_any()
+            .downcast_ref::<ConcentratedPool>()
+            .unwrap();
+        assert_eq!(refreshed.current_tick, "450000");
+        assert_eq!(refreshed.liquidity.len(), 2);
+        // the names of the snapshot are kept
+        assert_eq!(refreshed.token1_native_name.as_deref(), Some("uusdc"));
+        assert_eq!(
+            refreshed.spot_price("uosmo", "uusdc").unwrap().to_string(),
+            "1.450000000000000000"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use eyre::{eyre, Result, WrapErr};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+
+use super::juno_pool::{query_contract, WasmErrorResponse, WasmPool};
+use super::terraswap_pool::{get_factory_pairs, PairInfo};
+use crate::config::{AdapterConfig, PoolAdapter};
+use crate::util::endpoint::EndpointPool;
+
+const PAGE_LIMIT: u64 = 100;
+// contracts probed at the same time
+const PROBE_CONCURRENCY: usize = 8;
+
+#[derive(Debug, serde::Deserialize)]
+struct RestPagination {
+    next_key: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CodeContractsPage {
+    contracts: Vec<String>,
+    pagination: Option<RestPagination>,
+}
+
+/// Every contract instantiated from `code_id`, loaded page by page.
+pub async fn get_contracts(api: &EndpointPool, code_id: u64) -> Result<Vec<String>> {
+    let mut contracts = vec![];
+    let mut key: Option<String> = None;
+    loop {
+        let page: CodeContractsPage = api
+            .run(|url| {
+                let key = key.clone();
+                async move {
+                    let url = format!("{}/cosmwasm/wasm/v1/code/{}/contracts", url, code_id);
+                    let mut query = vec![("pagination.limit".to_owned(), PAGE_LIMIT.to_string())];
+                    if let Some(key) = key {
+                        query.push(("pagination.key".to_owned(), key));
+                    }
+                    let http = api.connections().http();
+                    Ok(http.get(url).query(&query).send().await?.json().await?)
+                }
+            })
+            .await?;
+        let page_size = page.contracts.len();
+        contracts.extend(page.contracts);
+        let next_key = page
+            .pagination
+            .and_then(|x| x.next_key)
+            .filter(|x| !x.is_empty());
+        match next_key {
+            None => break,
+            Some(x) if Some(&x) == key.as_ref() || page_size == 0 => {
+                return Err(eyre!("Contract list pagination did not advance"))
+            }
+            Some(x) => key = Some(x),
+        }
+    }
+    Ok(contracts)
+}
+
+// answer of the query `msg`, None if the contract has no such query or answers it with
+// another type. Errors of the nodes are returned.
+async fn probe_query<T: DeserializeOwned>(
+    api: &EndpointPool,
+    contract_address: &str,
+    msg: serde_json::Value,
+) -> Result<Option<T>> {
+    let msg = base64::encode(msg.to_string());
+    let answer = match query_contract(api, contract_address, &msg).await {
+        Ok(x) => x,
+        Err(e) if e.downcast_ref::<WasmErrorResponse>().is_some() => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let decoded = base64::decode_config(answer, base64::STANDARD)?;
+    Ok(serde_json::from_slice::<T>(&decoded).ok())
+}
+
+/// Finds the pool adapter of a contract by probing its queries, `None` if no adapter fits.
+pub async fn detect_adapter(
+    api: &EndpointPool,
+    contract_address: &str,
+) -> Result<Option<PoolAdapter>> {
+    let pair = serde_json::json!({ "pair": {} });
+    if probe_query::<PairInfo>(api, contract_address, pair)
+        .await?
+        .is_some()
+    {
+        return Ok(Some(PoolAdapter::Terraswap));
+    }
+    let info = serde_json::json!({ "info": {} });
+    if probe_query::<WasmPool>(api, contract_address, info)
+        .await?
+        .is_some()
+    {
+        return Ok(Some(PoolAdapter::Wasmswap));
+    }
+    Ok(None)
+}
+
+/// Pool contracts of a chain by the adapter which can quote them.
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveredContracts {
+    pub contracts: HashMap<PoolAdapter, Vec<String>>,
+    // contracts which answered none of the pool queries
+    pub unknown: Vec<String>,
+}
+
+impl DiscoveredContracts {
+    pub fn get(&self, adapter: PoolAdapter) -> &[String] {
+        self.contracts
+            .get(&adapter)
+            .map(|x| x.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Collects the pairs of the factories and the contracts of the code ids of every
+/// adapter and sorts them by the adapter they answer to.
+///
+/// A contract found through the config of one adapter may end up at another one,
+/// e.g. when a code id list contains WasmSwap and Terraswap pairs.
+pub async fn discover_contracts(
+    api: &EndpointPool,
+    adapters: &[AdapterConfig],
+) -> Result<DiscoveredContracts> {
+    let mut addresses: Vec<String> = vec![];
+    for adapter in adapters {
+        for factory in &adapter.factories {
+            addresses.extend(
+                get_factory_pairs(api, factory)
+                    .await?
+                    .into_iter()
+                    .map(|x| x.contract_addr),
+            );
+        }
+        for code_id in &adapter.code_ids {
+            addresses.extend(get_contracts(api, *code_id).await?);
+        }
+    }
+    addresses.sort();
+    addresses.dedup();
+
+    // collected first like in `DexAgg::quote_all`, the connections of `api` limit the requests
+    let probes: Vec<_> = addresses
+        .into_iter()
+        .map(|address| async move {
+            let adapter = detect_adapter(api, &address)
+                .await
+                .wrap_err(format!("Could not probe contract {}", address))?;
+            Ok::<_, eyre::Report>((address, adapter))
+        })
+        .collect();
+    let mut detected: Vec<(String, Option<PoolAdapter>)> = stream::iter(probes)
+        .buffer_unordered(PROBE_CONCURRENCY)
+        .try_collect()
+        .await?;
+    detected.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut discovered = DiscoveredContracts::default();
+    for (address, adapter) in detected {
+        match adapter {
+            Some(adapter) => discovered
+                .contracts
+                .entry(adapter)
+                .or_default()
+                .push(address),
+            None => discovered.unknown.push(address),
+        }
+    }
+    Ok(discovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::Filter;
+
+    // answers of the legacy smart query route of the node, by contract and query
+    fn answer(contract: &str, query: &serde_json::Value) -> warp::reply::Response {
+        use warp::Reply;
+        let smart = |x: serde_json::Value| {
+            warp::reply::json(&serde_json::json!({
+                "result": { "smart": base64::encode(x.to_string()) }
+            }))
+            .into_response()
+        };
+        let unknown = warp::reply::json(&serde_json::json!({
+            "error": "Error parsing into type: unknown variant"
+        }))
+        .into_response();
+        match (contract, query.get("pair").is_some()) {
+            ("pair", true) => smart(serde_json::json!({
+                "asset_infos": [
+                    { "native_token": { "denom": "ujuno" } },
+                    { "token": { "contract_addr": "juno1token" } }
+                ],
+                "contract_addr": "pair",
+                "liquidity_token": "juno1lp"
+            })),
+            ("wasmswap", false) => smart(serde_json::json!({
+                "token1_reserve": "100",
+                "token1_denom": { "native": "ujuno" },
+                "token2_reserve": "200",
+                "token2_denom": { "cw20": "juno1token" },
+                "lp_token_supply": "150",
+                "lp_token_address": "juno1lp"
+            })),
+            // answers with something else than a pair
+            ("other", true) => smart(serde_json::json!({ "owner": "juno1owner" })),
+            ("broken", _) => warp::reply::with_status(
+                "upstream connect error",
+                warp::http::StatusCode::BAD_GATEWAY,
+            )
+            .into_response(),
+            _ => unknown,
+        }
+    }
+
+    async fn serve(contracts: &'static [&'static str]) -> EndpointPool {
+        let smart = warp::path!("wasm" / "contract" / String / "smart" / String).map(
+            |contract: String, msg: String| {
+                let query = base64::decode(msg).unwrap();
+                answer(&contract, &serde_json::from_slice(&query).unwrap())
+            },
+        );
+        let code = warp::path!("cosmwasm" / "wasm" / "v1" / "code" / u64 / "contracts")
+            .map(move |_| warp::reply::json(&serde_json::json!({ "contracts": contracts })));
+        let (address, server) = warp::serve(smart.or(code)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        EndpointPool::new(vec![format!("http://{}", address)])
+    }
+
+    fn code_id_adapter() -> AdapterConfig {
+        AdapterConfig {
+            adapter: PoolAdapter::Terraswap,
+            snapshot: String::new(),
+            factories: vec![],
+            code_ids: vec![1],
+        }
+    }
+
+    #[tokio::test]
+    async fn adapters_of_contracts() {
+        let api = serve(&[]).await;
+        let detect = |x| detect_adapter(&api, x);
+        assert_eq!(detect("pair").await.unwrap(), Some(PoolAdapter::Terraswap));
+        assert_eq!(
+            detect("wasmswap").await.unwrap(),
+            Some(PoolAdapter::Wasmswap)
+        );
+        assert_eq!(detect("other").await.unwrap(), None);
+        assert_eq!(detect("cw20").await.unwrap(), None);
+        // a node error is no answer of the contract
+        assert!(detect("broken").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn discovered_contracts_by_adapter() {
+        let api = serve(&["wasmswap", "pair", "other", "cw20", "pair"]).await;
+        let discovered = discover_contracts(&api, &[code_id_adapter()])
+            .await
+            .unwrap();
+        assert_eq!(discovered.get(PoolAdapter::Terraswap), ["pair"]);
+        assert_eq!(discovered.get(PoolAdapter::Wasmswap), ["wasmswap"]);
+        assert_eq!(discovered.unknown, ["cw20", "other"]);
+    }
+
+    #[tokio::test]
+    async fn node_errors_fail_the_discovery() {
+        let api = serve(&["pair", "broken"]).await;
+        let err = discover_contracts(&api, &[code_id_adapter()])
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Could not probe contract broken");
+    }
+}
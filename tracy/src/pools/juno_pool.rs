@@ -2,15 +2,22 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use eyre::{eyre, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::{self, from_utf8};
 
-use crate::util::denom_trace::denom_trace;
-use crate::{Pool, PoolConfig, Quote};
+use super::load_pools_from_file;
+use super::math::{
+    constant_product_in_given_out, constant_product_out_given_in, constant_product_spot_price,
+    reserves_after_swap, WASMSWAP_FEE_DENOMINATOR, WASMSWAP_FEE_NUMERATOR,
+};
+use crate::assets::AssetRegistry;
+use crate::decimal::Dec;
+use crate::util::endpoint::EndpointPool;
+use crate::{Fee, Pool, PoolConfig, Quote, QuoteSource};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WasmContractResponse {
@@ -42,18 +49,34 @@ pub struct WasmPool {
     pub token2: Option<JunoToken>,
     pub token2_denom: JunoDenom,
     pub token2_reserve: String,
-    chain: Option<String>,
+    // name of the chain in the config, set when the pool is fetched or loaded by `DexAgg`
+    pub chain: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JunoDenom {
     pub native: Option<String>,
     pub cw20: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct WasmCodeContracts {
-    result: Vec<String>,
+impl JunoDenom {
+    /// Address of the token contract of cw20 tokens.
+    pub fn cw20_address(&self) -> Option<&str> {
+        match (&self.cw20, &self.native) {
+            (Some(address), _) => Some(address),
+            // older contracts list cw20 tokens as native
+            (None, Some(address)) if address.starts_with("juno") => Some(address),
+            _ => None,
+        }
+    }
+
+    /// On-chain denom of the token, `cw20:<address>` for cw20 tokens.
+    pub fn chain_denom(&self) -> Option<String> {
+        match self.cw20_address() {
+            Some(address) => Some(format!("cw20:{}", address)),
+            None => self.native.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,60 +85,81 @@ pub struct WasmPoolPriceResponse {
     token2_amount: Option<String>,
 }
 
+/// Error answer of a contract query, e.g. for a query the contract does not have.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WasmErrorResponse {
     error: String,
 }
 
-async fn get_query(url: &str, query: &Vec<(&str, &str)>) -> Result<String> {
-    let client = reqwest::Client::new();
-    let resp = client.get(url).query(query).send().await?.text().await?;
-    Ok(resp)
+impl std::fmt::Display for WasmErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
 }
 
-pub async fn get_contracts(api: &str, code_id: u64) -> Result<Vec<String>> {
-    let url = format!("{}/wasm/code/{}/contracts", &api.to_string(), code_id);
-    let res = get_query(&url, &vec![]).await?;
-    let res = serde_json::from_str::<WasmCodeContracts>(res.as_str())?;
-    Ok(res.result)
+impl std::error::Error for WasmErrorResponse {}
+
+async fn get_query(
+    client: &reqwest::Client,
+    url: &str,
+    query: &Vec<(&str, &str)>,
+) -> Result<String> {
+    let resp = client.get(url).query(query).send().await?.text().await?;
+    Ok(resp)
 }
 
-pub async fn query_contract(api: &str, contract_address: &str, msg: &str) -> Result<String> {
-    let url = format!("{}/wasm/contract/{}/smart/{}", api, contract_address, msg);
-    let res = get_query(&url, &vec![("encoding", "base64")]).await?;
+pub async fn query_contract(
+    api: &EndpointPool,
+    contract_address: &str,
+    msg: &str,
+) -> Result<String> {
+    let res = api
+        .run(|url| async move {
+            let url = format!("{}/wasm/contract/{}/smart/{}", url, contract_address, msg);
+            let res = get_query(
+                api.connections().http(),
+                &url,
+                &vec![("encoding", "base64")],
+            )
+            .await?;
+            // only a node which does not answer with json is broken, contract errors are valid answers
+            serde_json::from_str::<serde_json::Value>(&res)?;
+            Ok(res)
+        })
+        .await?;
     let err = res.clone();
     if let Ok(res) = serde_json::from_str::<WasmErrorResponse>(&err) {
-        return Err(eyre!(res.error));
+        return Err(res.into());
     }
     let res = serde_json::from_str::<WasmContractResponse>(&res)?;
     Ok(res.result.smart)
 }
 
-pub async fn get_token_info(api: &str, contract_address: &str) -> Result<JunoToken> {
+pub async fn get_token_info(api: &EndpointPool, contract_address: &str) -> Result<JunoToken> {
     let msg = "{ \"token_info\" : {} }";
     let msg = base64::encode(msg);
     let res = query_contract(api, contract_address, msg.as_str()).await?;
     let decoded = base64::decode_config(res, base64::STANDARD)?;
     let decoded = from_utf8(&decoded)?;
-    let mut token = serde_json::from_str::<JunoToken>(&decoded)?;
+    let mut token = serde_json::from_str::<JunoToken>(decoded)?;
     token.address = Some(contract_address.to_string());
     Ok(token)
 }
 
-pub async fn get_pool_info(api: &str, contract_address: &str) -> Result<WasmPool> {
+pub async fn get_pool_info(api: &EndpointPool, contract_address: &str) -> Result<WasmPool> {
     let msg = "{ \"info\" : {} }";
     let msg = base64::encode(msg);
     let res = query_contract(api, contract_address, msg.as_str()).await?;
     let decoded = base64::decode_config(res, base64::STANDARD)?;
     let decoded = from_utf8(&decoded)?;
-    let pool = serde_json::from_str::<WasmPool>(&decoded)?;
+    let pool = serde_json::from_str::<WasmPool>(decoded)?;
     Ok(pool)
 }
 
 pub async fn get_price_for(
-    api: &str,
+    api: &EndpointPool,
     contract_address: &str,
-    amount: u64,
+    amount: u128,
     for2: bool,
 ) -> Result<String> {
     let (method, arg): (&str, &str) = if !for2 {
@@ -128,114 +172,294 @@ pub async fn get_price_for(
     let res = query_contract(api, contract_address, msg.as_str()).await?;
     let decoded = base64::decode_config(res, base64::STANDARD)?;
     let decoded = from_utf8(&decoded)?;
-    let res = serde_json::from_str::<WasmPoolPriceResponse>(&decoded)?;
+    let res = serde_json::from_str::<WasmPoolPriceResponse>(decoded)?;
     if let Some(amount) = res.token1_amount {
         return Ok(amount);
     }
     if let Some(amount) = res.token2_amount {
         return Ok(amount);
     }
-    return Err(eyre!("We should not be here"));
+    Err(eyre!("We should not be here"))
 }
 
-pub async fn fetch_juno_pools(api: &str) -> Result<Vec<WasmPool>> {
-    let contracts = get_contracts(api, 16).await?;
-    let mut res = Vec::new();
-    for contract in contracts {
-        let mut pool = get_pool_info(api, contract.as_str()).await?;
-        if pool.token1_reserve == "0" || pool.token2_reserve == "0" {
-            continue; // Empty pool, probably invalid
-        }
-        pool.pool_address = Some(contract);
-        pool.token1 = Some(extract_token(api, &pool.token1_denom).await?);
-        pool.token2 = Some(extract_token(api, &pool.token2_denom).await?);
-        pool.chain = Some("juno".to_owned());
-        res.push(pool);
-    }
-    let out = serde_json::to_string(&res)?;
-    let path = Path::new("juno_pools.json");
+// the snapshot and the checkpoint are written after this many contracts
+pub const CHECKPOINT_INTERVAL: usize = 10;
+
+/// How [`fetch_juno_pools`] treats an existing snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadMode {
+    // fetches every contract again
+    #[default]
+    Full,
+    // keeps the snapshot, new contracts are fetched and the others only if their info changed
+    Update,
+    // continues an interrupted load from its checkpoint, like `Update` for the remaining contracts
+    Resume,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailedContract {
+    pub address: String,
+    pub error: String,
+}
+
+/// What happened to every contract of a load, written next to the snapshot.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LoadReport {
+    pub loaded: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub empty: Vec<String>,
+    pub failed: Vec<FailedContract>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct LoadCheckpoint {
+    done: Vec<String>,
+    report: LoadReport,
+}
+
+enum ContractLoad {
+    Loaded(WasmPool),
+    Updated(WasmPool),
+    Unchanged,
+    Empty,
+}
+
+/// `juno_pools.json` -> `juno_pools.checkpoint.json`
+pub fn checkpoint_path(path: &Path) -> PathBuf {
+    path.with_extension("checkpoint.json")
+}
+
+/// `juno_pools.json` -> `juno_pools.errors.json`
+pub fn report_path(path: &Path) -> PathBuf {
+    path.with_extension("errors.json")
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let out = serde_json::to_string(value)?;
     let mut file = File::create(path)?;
-    file.write(out.as_bytes())?;
-    Ok(res)
+    file.write_all(out.as_bytes())?;
+    Ok(())
 }
 
-pub async fn fetch_juno_pool(api: &str, contract_address: &str) -> Result<WasmPool> {
-    let mut pool = get_pool_info(api, contract_address)
-        .await
-        .expect("Cannot fetch pool info");
+// pool info with its tokens, None if a reserve is empty
+async fn fetch_wasm_pool(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    contract_address: &str,
+) -> Result<Option<WasmPool>> {
+    let mut pool = get_pool_info(api, contract_address).await?;
     if pool.token1_reserve == "0" || pool.token2_reserve == "0" {
-        return Err(eyre!("Cannot fetch empty pool")); // Empty pool, probably invalid
+        return Ok(None); // Empty pool, probably invalid
     }
     pool.pool_address = Some(contract_address.to_string());
-    pool.token1 = Some(
-        extract_token(api, &pool.token1_denom)
-            .await
-            .expect("Cannot extract token"),
-    );
-    pool.token2 = Some(
-        extract_token(api, &pool.token2_denom)
-            .await
-            .expect("Cannot extract token"),
-    );
-    Ok(pool)
+    pool.token1 = Some(extract_token(api, registry, chain, &pool.token1_denom).await?);
+    pool.token2 = Some(extract_token(api, registry, chain, &pool.token2_denom).await?);
+    pool.chain = Some(chain.to_owned());
+    Ok(Some(pool))
 }
 
-pub async fn update_juno_pool(api: &str, path: &Path, contract_addresses: Vec<&str>) -> Result<()> {
+// only queries the tokens again if the denoms of the pool changed
+async fn update_wasm_pool(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    contract_address: &str,
+    old: Option<&WasmPool>,
+) -> Result<ContractLoad> {
+    let old = match old {
+        Some(x) => x,
+        None => {
+            return Ok(
+                match fetch_wasm_pool(api, registry, chain, contract_address).await? {
+                    Some(pool) => ContractLoad::Loaded(pool),
+                    None => ContractLoad::Empty,
+                },
+            )
+        }
+    };
+    let info = get_pool_info(api, contract_address).await?;
+    if info.token1_reserve == "0" || info.token2_reserve == "0" {
+        return Ok(ContractLoad::Empty);
+    }
+    if info.token1_denom != old.token1_denom || info.token2_denom != old.token2_denom {
+        return Ok(
+            match fetch_wasm_pool(api, registry, chain, contract_address).await? {
+                Some(pool) => ContractLoad::Updated(pool),
+                None => ContractLoad::Empty,
+            },
+        );
+    }
+    if info.token1_reserve == old.token1_reserve
+        && info.token2_reserve == old.token2_reserve
+        && info.lp_token_supply == old.lp_token_supply
+    {
+        return Ok(ContractLoad::Unchanged);
+    }
+    let mut pool = old.clone();
+    pool.lp_token_supply = info.lp_token_supply;
+    pool.token1_reserve = info.token1_reserve;
+    pool.token2_reserve = info.token2_reserve;
+    Ok(ContractLoad::Updated(pool))
+}
+
+/// Loads the given WasmSwap contracts into the snapshot at `path`, see
+/// [`crate::pools::discovery::discover_contracts`] to find them.
+///
+/// A contract which cannot be loaded does not stop the load, it is recorded in the report
+/// which is written to [`report_path`]. Every [`CHECKPOINT_INTERVAL`] contracts the snapshot
+/// and the progress are written to disk, so an interrupted load can continue with
+/// [`LoadMode::Resume`]. Pools of contracts which are not in `contracts` are dropped.
+pub async fn fetch_juno_pools(
+    api: &EndpointPool,
+    contracts: &[String],
+    path: &Path,
+    mode: LoadMode,
+    registry: &mut AssetRegistry,
+    chain: &str,
+) -> Result<(Vec<WasmPool>, LoadReport)> {
+    let mut pools: Vec<WasmPool> = match mode {
+        LoadMode::Full => vec![],
+        _ if path.exists() => load_juno_pools_from_file(path)?
+            .into_iter()
+            .map(|x| *x)
+            .collect(),
+        _ => vec![],
+    };
+    let checkpoint = checkpoint_path(path);
+    let mut progress = match mode {
+        LoadMode::Resume if checkpoint.exists() => {
+            serde_json::from_str::<LoadCheckpoint>(&fs::read_to_string(&checkpoint)?)?
+        }
+        _ => LoadCheckpoint::default(),
+    };
+    let done: HashSet<String> = progress.done.iter().cloned().collect();
+
+    for contract in contracts.iter().filter(|x| !done.contains(*x)) {
+        let index = pools
+            .iter()
+            .position(|x| x.pool_address.as_ref() == Some(contract));
+        let report = &mut progress.report;
+        let old = index.map(|x| &pools[x]);
+        match update_wasm_pool(api, registry, chain, contract, old).await {
+            Ok(ContractLoad::Loaded(pool)) => {
+                report.loaded.push(contract.clone());
+                pools.push(pool);
+            }
+            Ok(ContractLoad::Updated(pool)) => {
+                report.updated.push(contract.clone());
+                pools[index.unwrap()] = pool;
+            }
+            Ok(ContractLoad::Unchanged) => report.unchanged.push(contract.clone()),
+            Ok(ContractLoad::Empty) => {
+                report.empty.push(contract.clone());
+                if let Some(index) = index {
+                    pools.remove(index);
+                }
+            }
+            // the old pool stays in the snapshot
+            Err(e) => report.failed.push(FailedContract {
+                address: contract.clone(),
+                error: format!("{:#}", e),
+            }),
+        }
+        progress.done.push(contract.clone());
+        if progress.done.len() % CHECKPOINT_INTERVAL == 0 {
+            write_json(path, &pools)?;
+            write_json(&checkpoint, &progress)?;
+        }
+    }
+
+    pools.retain(|x| {
+        x.pool_address
+            .as_ref()
+            .is_some_and(|x| contracts.contains(x))
+    });
+    write_json(path, &pools)?;
+    write_json(&report_path(path), &progress.report)?;
+    if checkpoint.exists() {
+        fs::remove_file(&checkpoint)?;
+    }
+    Ok((pools, progress.report))
+}
+
+pub async fn fetch_juno_pool(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    contract_address: &str,
+) -> Result<WasmPool> {
+    fetch_wasm_pool(api, registry, chain, contract_address)
+        .await?
+        .ok_or_else(|| eyre!("Cannot fetch empty pool")) // Empty pool, probably invalid
+}
+
+// refetches the given pools of `chain` and writes them back into the snapshot
+pub async fn update_juno_pool(
+    api: &EndpointPool,
+    path: &Path,
+    registry: &mut AssetRegistry,
+    contract_addresses: Vec<&str>,
+    chain: &str,
+) -> Result<Vec<Box<WasmPool>>> {
     let mut pools = load_juno_pools_from_file(path)?;
     let mut new_pools = HashMap::new();
     for contract_address in contract_addresses {
-        let new_pool = fetch_juno_pool(api, contract_address).await?;
+        let new_pool = fetch_juno_pool(api, registry, chain, contract_address).await?;
         new_pools.insert(contract_address, new_pool);
     }
-    for i in 0..pools.len() {
-        let pool = &pools[i];
-        let wrapped_address = pool.address().unwrap();
-        let pool_address = &wrapped_address.as_str();
-        if new_pools.contains_key(pool_address) {
-            pools[i] = Box::new(new_pools.get(pool_address.clone()).unwrap().to_owned());
+    for pool in pools.iter_mut() {
+        let pool_address = pool.address().unwrap();
+        if let Some(new_pool) = new_pools.get(pool_address.as_str()) {
+            **pool = new_pool.to_owned();
         }
     }
-    Ok(())
+    let out = serde_json::to_string(&pools)?;
+    let mut file = File::create(path)?;
+    file.write_all(out.as_bytes())?;
+    Ok(pools)
 }
 
-async fn extract_token(api: &str, denom: &JunoDenom) -> Result<JunoToken> {
-    if let Some(address) = &denom.cw20 {
-        return Ok(get_token_info(api, &address).await?);
-    }
-    if let Some(address) = &denom.native {
-        if address == "ujuno" {
-            return Ok(JunoToken {
-                symbol: Some("ujuno".to_owned()),
-                name: Some("ujuno".to_owned()),
-                total_supply: None,
-                address: None,
-                decimals: Some(6),
-            });
-        } else if address.starts_with("ibc") {
-            let origin = address.clone();
-            let hash = address.split("/").collect::<Vec<&str>>()[1];
-            let denom = denom_trace(api, hash).await?;
+/// Token of a pool denom, the metadata is added to `registry`. Native tokens are named
+/// by their base denom, e.g. "uatom" for an ibc denom of ATOM.
+pub(crate) async fn extract_token(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    denom: &JunoDenom,
+) -> Result<JunoToken> {
+    let cw20 = match (&denom.cw20, &denom.native) {
+        (Some(address), _) => address,
+        (None, Some(address)) if address.starts_with("juno") => address,
+        (None, Some(native)) => {
+            let asset = registry.resolve(api, chain, native).await?;
             return Ok(JunoToken {
-                symbol: Some(denom.base_denom),
-                name: None,
+                symbol: Some(asset.base_denom),
+                name: asset.name,
                 total_supply: None,
-                address: Some(origin),
-                decimals: Some(6),
+                address: asset.ibc_path.map(|_| native.clone()),
+                decimals: asset.exponent.map(u64::from),
             });
-        } else if address.starts_with("juno") {
-            return Ok(get_token_info(api, &address).await?);
         }
-    }
-    return Err(eyre!("We should not be here"));
+        (None, None) => return Err(eyre!("Pool token has no denom")),
+    };
+    let token = get_token_info(api, cw20).await?;
+    registry.insert_cw20(chain, &token)?;
+    Ok(token)
 }
 
-pub async fn extract_assets(api: &str) -> Result<(), Box<dyn Error>> {
+pub async fn extract_assets(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+) -> Result<(), Box<dyn Error>> {
     let pools = fs::read_to_string(Path::new("juno_pools.json"))?;
     let pools = serde_json::from_str::<Vec<WasmPool>>(&pools)?;
     let mut assets = Vec::new();
     for pool in pools {
-        let token1 = extract_token(api, &pool.token1_denom).await?;
-        let token2 = extract_token(api, &pool.token2_denom).await?;
+        let token1 = extract_token(api, registry, &pool.chain(), &pool.token1_denom).await?;
+        let token2 = extract_token(api, registry, &pool.chain(), &pool.token2_denom).await?;
         if !assets.contains(&token1) {
             assets.push(token1);
         }
@@ -246,16 +470,12 @@ pub async fn extract_assets(api: &str) -> Result<(), Box<dyn Error>> {
     let out = serde_json::to_string(&assets)?;
     let path = Path::new("juno_assets.json");
     let mut file = File::create(path)?;
-    file.write(out.as_bytes())?;
+    file.write_all(out.as_bytes())?;
     Ok(())
 }
 
 pub fn load_juno_pools_from_file(path: &Path) -> Result<Vec<Box<WasmPool>>> {
-    let mut file = File::open(path)?;
-    let mut text: String = "".to_string();
-    file.read_to_string(&mut text)?;
-    let pools: Vec<Box<WasmPool>> = serde_json::from_str(&text)?;
-    Ok(pools)
+    load_pools_from_file(path)
 }
 
 pub fn load_juno_assets_from_file(path: &Path) -> Result<Vec<JunoToken>> {
@@ -266,6 +486,33 @@ pub fn load_juno_assets_from_file(path: &Path) -> Result<Vec<JunoToken>> {
     Ok(pools)
 }
 
+// takes the symbol of the token or its on-chain denom
+fn is_token(token: &Option<JunoToken>, token_denom: &JunoDenom, denom: &str) -> bool {
+    token.as_ref().and_then(|x| x.symbol.as_deref()) == Some(denom)
+        || token_denom.chain_denom().as_deref() == Some(denom)
+}
+
+impl WasmPool {
+    pub(crate) fn is_token1(&self, denom: &str) -> bool {
+        is_token(&self.token1, &self.token1_denom, denom)
+    }
+
+    pub(crate) fn is_token2(&self, denom: &str) -> bool {
+        is_token(&self.token2, &self.token2_denom, denom)
+    }
+
+    // reserves as (token_in, token_out), None if the pool does not contain the pair
+    fn reserves_for(&self, token_in_denom: &str, token_out_denom: &str) -> Option<(&str, &str)> {
+        if self.is_token1(token_in_denom) && self.is_token2(token_out_denom) {
+            Some((&self.token1_reserve, &self.token2_reserve))
+        } else if self.is_token2(token_in_denom) && self.is_token1(token_out_denom) {
+            Some((&self.token2_reserve, &self.token1_reserve))
+        } else {
+            None
+        }
+    }
+}
+
 #[async_trait]
 impl Pool for WasmPool {
     async fn get_quote(
@@ -275,33 +522,138 @@ impl Pool for WasmPool {
         token_out_denom: &str,
         config: &PoolConfig,
     ) -> Result<Quote> {
-        let token1_denom = &self.token1.clone().unwrap().symbol.unwrap();
-        let token2_denom = &self.token2.clone().unwrap().symbol.unwrap();
         let pool_address = self.pool_address.clone().unwrap();
-        if (token_in_denom == token1_denom && token_out_denom == token2_denom)
-            || (token_in_denom == token1_denom && token_out_denom == token2_denom)
-        {
-            let amount_out = get_price_for(
-                config.rest_url.clone().unwrap().as_str(),
+        if self.reserves_for(token_in_denom, token_out_denom).is_none() {
+            return Err(eyre!(
+                "Cannot find pair: {} | {}",
+                token_in_denom,
+                token_out_denom
+            ));
+        }
+        let amount_out = if config.estimate_quote {
+            get_price_for(
+                &config.rest,
                 &pool_address,
-                amount as u64,
-                token_in_denom == token1_denom,
+                amount,
+                self.is_token1(token_in_denom),
             )
-            .await
-            .unwrap();
-            let amount_out = amount_out.parse::<u128>()?;
-            return Ok(Quote {
-                token_in: Some(amount),
-                token_out: Some(amount_out),
-                pool_address: Some(pool_address),
-                error: None,
-            });
-        }
-        Err(eyre!(
-            "Cannot find pair: {} | {}",
-            token_in_denom,
-            token_out_denom
-        ))
+            .await?
+            .parse::<u128>()?
+        } else {
+            self.simulate_swap(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount),
+            token_out: Some(amount_out),
+            pool_address: Some(pool_address),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let pool_address = self.pool_address.clone().unwrap();
+        let amount_in = if config.estimate_quote {
+            // the contract only has exact in price queries, so use the current reserves instead
+            let mut pool = get_pool_info(&config.rest, &pool_address).await?;
+            pool.token1 = self.token1.clone();
+            pool.token2 = self.token2.clone();
+            pool.simulate_swap_exact_out(amount, token_in_denom, token_out_denom)?
+        } else {
+            self.simulate_swap_exact_out(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount_in),
+            token_out: Some(amount),
+            pool_address: Some(pool_address),
+            error: None,
+            // swap math in both cases, the node is only asked for the reserves
+            source: Some(QuoteSource::Offline),
+            ..Default::default()
+        })
+    }
+
+    async fn refresh(&self, config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        let info = get_pool_info(&config.rest, &self.address()?).await?;
+        let mut pool = self.clone();
+        pool.lp_token_supply = info.lp_token_supply;
+        pool.token1_reserve = info.token1_reserve;
+        pool.token2_reserve = info.token2_reserve;
+        Ok(Box::new(pool))
+    }
+
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserve_in, reserve_out) = self
+            .reserves_for(token_in_denom, token_out_denom)
+            .ok_or_else(|| eyre!("Cannot find pair: {} | {}", token_in_denom, token_out_denom))?;
+        constant_product_out_given_in(
+            reserve_in.parse::<u128>()?,
+            reserve_out.parse::<u128>()?,
+            amount,
+            WASMSWAP_FEE_NUMERATOR,
+            WASMSWAP_FEE_DENOMINATOR,
+        )
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserve_in, reserve_out) = self
+            .reserves_for(token_in_denom, token_out_denom)
+            .ok_or_else(|| eyre!("Cannot find pair: {} | {}", token_in_denom, token_out_denom))?;
+        constant_product_in_given_out(
+            reserve_in.parse::<u128>()?,
+            reserve_out.parse::<u128>()?,
+            amount,
+            WASMSWAP_FEE_NUMERATOR,
+            WASMSWAP_FEE_DENOMINATOR,
+        )
+    }
+
+    fn spot_price_after(
+        &self,
+        amount_in: u128,
+        amount_out: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Dec> {
+        let (reserve_in, reserve_out) = self
+            .reserves_for(token_in_denom, token_out_denom)
+            .ok_or_else(|| eyre!("Cannot find pair: {} | {}", token_in_denom, token_out_denom))?;
+        let (reserve_in, reserve_out) = reserves_after_swap(
+            reserve_in.parse::<u128>()?,
+            reserve_out.parse::<u128>()?,
+            amount_in,
+            amount_out,
+        )?;
+        constant_product_spot_price(reserve_in, reserve_out)
+    }
+
+    fn fee(
+        &self,
+        amount_in: u128,
+        _amount_out: u128,
+        token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Fee> {
+        let rate =
+            Dec::from(WASMSWAP_FEE_NUMERATOR).checked_quo(Dec::from(WASMSWAP_FEE_DENOMINATOR))?;
+        Fee::from_input(rate, amount_in, token_in_denom)
     }
 
     fn token_denoms(&self) -> Vec<String> {
@@ -310,15 +662,259 @@ impl Pool for WasmPool {
         vec![token1_denom, token2_denom]
     }
 
+    fn asset_denoms(&self) -> Vec<String> {
+        [&self.token1_denom, &self.token2_denom]
+            .iter()
+            .filter_map(|x| x.chain_denom())
+            .collect()
+    }
+
     fn to_json(&self) -> String {
         serde_json::to_string(&self).unwrap()
     }
 
     fn chain(&self) -> String {
-        String::from("juno")
+        self.chain.clone().unwrap_or_else(|| String::from("juno"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
     }
 
     fn address(&self) -> Result<String> {
         Ok(self.pool_address.clone().unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use warp::Filter;
+
+    // node with WasmSwap contracts, a contract without an info answer fails its queries
+    #[derive(Clone, Default)]
+    struct Node {
+        infos: Arc<Mutex<HashMap<String, serde_json::Value>>>,
+        queried: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Node {
+        fn set(&self, contract: &str, reserve1: &str, reserve2: &str) {
+            let info = serde_json::json!({
+                "token1_reserve": reserve1,
+                "token1_denom": { "native": "ujuno" },
+                "token2_reserve": reserve2,
+                "token2_denom": { "cw20": "juno1token" },
+                "lp_token_supply": "1000",
+                "lp_token_address": format!("{}-lp", contract)
+            });
+            self.infos.lock().unwrap().insert(contract.to_owned(), info);
+        }
+
+        fn fail(&self, contract: &str) {
+            self.infos.lock().unwrap().remove(contract);
+        }
+
+        fn take_queried(&self) -> Vec<String> {
+            std::mem::take(&mut *self.queried.lock().unwrap())
+        }
+
+        fn answer(&self, contract: String, query: serde_json::Value) -> serde_json::Value {
+            let smart = |x: serde_json::Value| serde_json::json!({ "result": { "smart": base64::encode(x.to_string()) } });
+            if query.get("token_info").is_some() {
+                return smart(serde_json::json!({
+                    "name": "Token", "symbol": "TKN", "decimals": 6, "total_supply": "1000000"
+                }));
+            }
+            self.queried.lock().unwrap().push(contract.clone());
+            match self.infos.lock().unwrap().get(&contract) {
+                Some(info) => smart(info.clone()),
+                None => serde_json::json!({ "error": "contract not found" }),
+            }
+        }
+
+        fn serve(&self) -> EndpointPool {
+            let node = self.clone();
+            let smart = warp::path!("wasm" / "contract" / String / "smart" / String).map(
+                move |contract: String, msg: String| {
+                    let query = serde_json::from_slice(&base64::decode(msg).unwrap()).unwrap();
+                    warp::reply::json(&node.answer(contract, query))
+                },
+            );
+            let (address, server) = warp::serve(smart).bind_ephemeral(([127, 0, 0, 1], 0));
+            tokio::spawn(server);
+            EndpointPool::new(vec![format!("http://{}", address)])
+        }
+    }
+
+    fn snapshot_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tracy-juno-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn contracts(count: usize) -> Vec<String> {
+        (0..count).map(|x| format!("juno1pool{:02}", x)).collect()
+    }
+
+    fn addresses(pools: &[WasmPool]) -> Vec<String> {
+        pools
+            .iter()
+            .map(|x| x.pool_address.clone().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_checkpoint() {
+        let node = Node::default();
+        let api = node.serve();
+        let contracts = contracts(12);
+        for contract in &contracts {
+            node.set(contract, "100", "200");
+        }
+        let path = snapshot_dir("resume").join("juno_pools.json");
+        let mut registry = AssetRegistry::default();
+
+        // a load interrupted after its first checkpoint
+        let (pools, _) = fetch_juno_pools(
+            &api,
+            &contracts[..CHECKPOINT_INTERVAL],
+            &path,
+            LoadMode::Full,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+        let checkpoint = LoadCheckpoint {
+            done: contracts[..CHECKPOINT_INTERVAL].to_vec(),
+            report: LoadReport {
+                loaded: contracts[..CHECKPOINT_INTERVAL].to_vec(),
+                ..Default::default()
+            },
+        };
+        write_json(&checkpoint_path(&path), &checkpoint).unwrap();
+        assert_eq!(pools.len(), CHECKPOINT_INTERVAL);
+        node.take_queried();
+
+        let (pools, report) = fetch_juno_pools(
+            &api,
+            &contracts,
+            &path,
+            LoadMode::Resume,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+        // only the contracts after the checkpoint are queried
+        assert_eq!(node.take_queried(), contracts[CHECKPOINT_INTERVAL..]);
+        assert_eq!(addresses(&pools), contracts);
+        assert_eq!(report.loaded, contracts);
+        assert!(!checkpoint_path(&path).exists());
+        let saved: LoadReport =
+            serde_json::from_str(&fs::read_to_string(report_path(&path)).unwrap()).unwrap();
+        assert_eq!(saved.loaded, contracts);
+        assert_eq!(load_juno_pools_from_file(&path).unwrap().len(), 12);
+
+        // without a checkpoint resuming is an update of every contract
+        fetch_juno_pools(
+            &api,
+            &contracts,
+            &path,
+            LoadMode::Resume,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+        assert_eq!(node.take_queried(), contracts);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_reports_every_contract() {
+        let node = Node::default();
+        let api = node.serve();
+        let contracts = contracts(5);
+        for contract in &contracts[..4] {
+            node.set(contract, "100", "200");
+        }
+        let path = snapshot_dir("update").join("juno_pools.json");
+        let mut registry = AssetRegistry::default();
+        fetch_juno_pools(
+            &api,
+            &contracts[..4],
+            &path,
+            LoadMode::Full,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+
+        node.set(&contracts[0], "150", "150");
+        node.set(&contracts[2], "0", "200");
+        node.fail(&contracts[3]);
+        node.set(&contracts[4], "100", "200");
+        let (pools, report) = fetch_juno_pools(
+            &api,
+            &contracts,
+            &path,
+            LoadMode::Update,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.updated, [contracts[0].clone()]);
+        assert_eq!(report.unchanged, [contracts[1].clone()]);
+        assert_eq!(report.empty, [contracts[2].clone()]);
+        assert_eq!(report.failed[0].address, contracts[3]);
+        assert_eq!(report.failed[0].error, "contract not found");
+        assert_eq!(report.loaded, [contracts[4].clone()]);
+        // the failed contract keeps its old pool, the empty one is dropped
+        assert_eq!(
+            addresses(&pools),
+            [0, 1, 3, 4].map(|x| contracts[x].clone())
+        );
+        assert_eq!(pools[0].token1_reserve, "150");
+        assert_eq!(
+            pools[0].token2.as_ref().unwrap().symbol.as_deref(),
+            Some("TKN")
+        );
+        let saved: LoadReport =
+            serde_json::from_str(&fs::read_to_string(report_path(&path)).unwrap()).unwrap();
+        assert_eq!(saved.failed.len(), 1);
+
+        // a full load starts over and drops the pools of removed contracts
+        let (pools, report) = fetch_juno_pools(
+            &api,
+            &contracts[..2],
+            &path,
+            LoadMode::Full,
+            &mut registry,
+            "juno",
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.loaded, contracts[..2]);
+        assert_eq!(addresses(&pools), contracts[..2]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn pools_of_old_snapshots_are_on_juno() {
+        let path = format!(
+            "{}/tests/fixtures/juno_pools.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut pool = load_juno_pools_from_file(Path::new(&path))
+            .unwrap()
+            .remove(0);
+        pool.chain = None;
+        assert_eq!(pool.chain(), "juno");
+    }
+}
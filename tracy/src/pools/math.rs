@@ -0,0 +1,990 @@
+use crypto_bigint::{CheckedAdd, CheckedMul, Limb, U512};
+use eyre::{eyre, Result};
+
+use crate::decimal::{Dec, Int};
+
+// WasmSwap takes 0.3% of the input amount as lp fee
+pub const WASMSWAP_FEE_NUMERATOR: u128 = 3;
+pub const WASMSWAP_FEE_DENOMINATOR: u128 = 1000;
+
+/// Balancer weighted pool swap, the same formula osmosis uses for `SwapExactAmountIn`:
+///
+/// `balance_out * (1 - (balance_in / (balance_in + amount_in * (1 - swap_fee))) ^ (weight_in / weight_out))`
+pub fn weighted_out_given_in(
+    balance_in: u128,
+    weight_in: u128,
+    balance_out: u128,
+    weight_out: u128,
+    amount_in: u128,
+    swap_fee: Dec,
+) -> Result<u128> {
+    check_weighted(balance_in, weight_in, balance_out, weight_out, swap_fee)?;
+    let amount_in = Dec::from(amount_in).checked_mul(Dec::ONE.checked_sub(swap_fee)?)?;
+    let balance_in = Dec::from(balance_in);
+    let ratio = balance_in.checked_quo(balance_in.checked_add(amount_in)?)?;
+    let weight_ratio = Dec::from(weight_in).checked_quo(Dec::from(weight_out))?;
+    let amount_out =
+        Dec::from(balance_out).checked_mul(Dec::ONE.checked_sub(ratio.pow(weight_ratio)?)?)?;
+    // never round in favour of the trader
+    amount_out.truncate_int().to_u128()
+}
+
+/// Inverse of [`weighted_out_given_in`], the amount needed to receive exactly `amount_out`:
+///
+/// `balance_in * ((balance_out / (balance_out - amount_out)) ^ (weight_out / weight_in) - 1) / (1 - swap_fee)`
+///
+/// Like osmosis it fails if `amount_out` is half of the balance or more, the power is only
+/// approximated for bases below two.
+pub fn weighted_in_given_out(
+    balance_in: u128,
+    weight_in: u128,
+    balance_out: u128,
+    weight_out: u128,
+    amount_out: u128,
+    swap_fee: Dec,
+) -> Result<u128> {
+    check_weighted(balance_in, weight_in, balance_out, weight_out, swap_fee)?;
+    if amount_out >= balance_out {
+        return Err(eyre!("Not enough liquidity for {}", amount_out));
+    }
+    let ratio = Dec::from(balance_out).checked_quo(Dec::from(balance_out - amount_out))?;
+    let weight_ratio = Dec::from(weight_out).checked_quo(Dec::from(weight_in))?;
+    let amount_in = Dec::from(balance_in)
+        .checked_mul(ratio.pow(weight_ratio)?.checked_sub(Dec::ONE)?)?
+        .checked_quo(Dec::ONE.checked_sub(swap_fee)?)?;
+    amount_in.ceil_int().to_u128()
+}
+
+/// Spot price of a weighted pool, token_out per token_in without fees:
+///
+/// `(balance_out / weight_out) / (balance_in / weight_in)`
+pub fn weighted_spot_price(
+    balance_in: u128,
+    weight_in: u128,
+    balance_out: u128,
+    weight_out: u128,
+) -> Result<Dec> {
+    check_weighted(balance_in, weight_in, balance_out, weight_out, Dec::ZERO)?;
+    Dec::from(balance_out)
+        .checked_quo(Dec::from(balance_in))?
+        .checked_mul(Dec::from(weight_in).checked_quo(Dec::from(weight_out))?)
+}
+
+/// Reserves once `amount_in` went into and `amount_out` left the pool, fees stay in the pool.
+pub fn reserves_after_swap(
+    reserve_in: u128,
+    reserve_out: u128,
+    amount_in: u128,
+    amount_out: u128,
+) -> Result<(u128, u128)> {
+    let reserve_in = reserve_in
+        .checked_add(amount_in)
+        .ok_or_else(|| eyre!("Overflow while calculating quote"))?;
+    let reserve_out = reserve_out
+        .checked_sub(amount_out)
+        .ok_or_else(|| eyre!("Not enough liquidity for {}", amount_out))?;
+    Ok((reserve_in, reserve_out))
+}
+
+fn check_weighted(
+    balance_in: u128,
+    weight_in: u128,
+    balance_out: u128,
+    weight_out: u128,
+    swap_fee: Dec,
+) -> Result<()> {
+    if balance_in == 0 || balance_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    if weight_in == 0 || weight_out == 0 {
+        return Err(eyre!("Pool asset has no weight"));
+    }
+    check_fee(swap_fee)
+}
+
+fn check_fee(fee: Dec) -> Result<()> {
+    if fee.is_negative() || fee >= Dec::ONE {
+        return Err(eyre!("Invalid swap fee: {}", fee));
+    }
+    Ok(())
+}
+
+/// x * y = k swap with the fee taken from the input, as implemented by the WasmSwap contract.
+pub fn constant_product_out_given_in(
+    reserve_in: u128,
+    reserve_out: u128,
+    amount_in: u128,
+    fee_numerator: u128,
+    fee_denominator: u128,
+) -> Result<u128> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    let amount_in_with_fee =
+        Int::from(amount_in).checked_mul(Int::from(fee_denominator - fee_numerator))?;
+    let numerator = amount_in_with_fee.checked_mul(Int::from(reserve_out))?;
+    let denominator = Int::from(reserve_in)
+        .checked_mul(Int::from(fee_denominator))?
+        .checked_add(amount_in_with_fee)?;
+    numerator.checked_quo(denominator)?.to_u128()
+}
+
+/// Inverse of [`constant_product_out_given_in`], the smallest input with an output of at
+/// least `amount_out`.
+pub fn constant_product_in_given_out(
+    reserve_in: u128,
+    reserve_out: u128,
+    amount_out: u128,
+    fee_numerator: u128,
+    fee_denominator: u128,
+) -> Result<u128> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    if amount_out >= reserve_out {
+        return Err(eyre!("Not enough liquidity for {}", amount_out));
+    }
+    let numerator = Int::from(reserve_in)
+        .checked_mul(Int::from(amount_out))?
+        .checked_mul(Int::from(fee_denominator))?;
+    let denominator = Int::from(reserve_out - amount_out)
+        .checked_mul(Int::from(fee_denominator - fee_numerator))?;
+    numerator.quo_round_up(denominator)?.to_u128()
+}
+
+/// Spot price of an x * y = k pool, token_out per token_in without fees.
+pub fn constant_product_spot_price(reserve_in: u128, reserve_out: u128) -> Result<Dec> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    Dec::from(reserve_out).checked_quo(Dec::from(reserve_in))
+}
+
+// Terraswap pairs take 0.3% of the output as commission, Astroport forks use the same default
+pub const TERRASWAP_COMMISSION_RATE: &str = "0.003";
+
+/// x * y = k swap with the commission taken from the output, as implemented by Terraswap
+/// style pairs.
+pub fn constant_product_commission_out_given_in(
+    reserve_in: u128,
+    reserve_out: u128,
+    amount_in: u128,
+    commission_rate: Dec,
+) -> Result<u128> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    check_commission(commission_rate)?;
+    let return_amount = Int::from(reserve_out)
+        .checked_mul(Int::from(amount_in))?
+        .checked_quo(Int::from(reserve_in).checked_add(Int::from(amount_in))?)?;
+    // Uint128 * Decimal truncates in cosmwasm
+    let commission = return_amount
+        .to_dec()?
+        .mul_truncate(commission_rate)?
+        .truncate_int();
+    return_amount.checked_sub(commission)?.to_u128()
+}
+
+/// Inverse of [`constant_product_commission_out_given_in`], the smallest input whose output
+/// is at least `amount_out`.
+pub fn constant_product_commission_in_given_out(
+    reserve_in: u128,
+    reserve_out: u128,
+    amount_out: u128,
+    commission_rate: Dec,
+) -> Result<u128> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    check_commission(commission_rate)?;
+    // output before the commission is taken
+    let mut return_amount = Dec::from(amount_out)
+        .quo_round_up(Dec::ONE.checked_sub(commission_rate)?)?
+        .ceil_int();
+    // the commission is truncated, so a unit less may still leave `amount_out`
+    let leaves_amount_out = |x: Int| -> Result<bool> {
+        let commission = x.to_dec()?.mul_truncate(commission_rate)?.truncate_int();
+        Ok(x.checked_sub(commission)? >= Int::from(amount_out))
+    };
+    while !return_amount.is_zero()
+        && leaves_amount_out(return_amount.checked_sub(Int::from(1u128))?)?
+    {
+        return_amount = return_amount.checked_sub(Int::from(1u128))?;
+    }
+    let reserve_out = Int::from(reserve_out);
+    if return_amount >= reserve_out {
+        return Err(eyre!("Not enough liquidity for {}", amount_out));
+    }
+    Int::from(reserve_in)
+        .checked_mul(return_amount)?
+        .quo_round_up(reserve_out.checked_sub(return_amount)?)?
+        .to_u128()
+}
+
+fn check_commission(commission_rate: Dec) -> Result<()> {
+    if commission_rate.is_negative() || commission_rate >= Dec::ONE {
+        return Err(eyre!("Invalid commission rate"));
+    }
+    Ok(())
+}
+
+// digits of the largest scaled reserve in the stableswap solver, like the 36 decimals of
+// osmomath BigDec this keeps fractions of the base unit of assets with a large scaling factor.
+// The invariant is of fourth degree, so it is calculated with 512 bits.
+const STABLESWAP_DIGITS: i32 = 36;
+
+fn pow10(exponent: i32) -> U512 {
+    let ten = U512::from_u8(10);
+    (0..exponent.unsigned_abs()).fold(U512::ONE, |x, _| x.wrapping_mul(&ten))
+}
+
+fn checked_mul(a: &U512, b: &U512) -> Option<U512> {
+    a.checked_mul(b).into()
+}
+
+fn checked_add(a: &U512, b: &U512) -> Option<U512> {
+    a.checked_add(b).into()
+}
+
+// `numerator / denominator`, rounded up if `round_up`
+fn div(numerator: &U512, denominator: &U512, round_up: bool) -> U512 {
+    let value = numerator.wrapping_div(denominator);
+    if round_up && value.wrapping_mul(denominator) != *numerator {
+        value.wrapping_add(&U512::ONE)
+    } else {
+        value
+    }
+}
+
+fn wide_to_u128(value: &U512) -> Result<u128> {
+    if value.bits() > 128 {
+        return Err(eyre!("Overflow while calculating quote"));
+    }
+    Ok(value
+        .as_words()
+        .iter()
+        .rev()
+        .fold(0u128, |x, word| (x << Limb::BITS) | *word as u128))
+}
+
+// `amount / scaling_factor * 10^exponent` as integer
+fn to_scaled(amount: u128, scaling_factor: u64, exponent: i32, round_up: bool) -> U512 {
+    let (amount, scaling_factor) = (U512::from_u128(amount), U512::from_u64(scaling_factor));
+    if exponent >= 0 {
+        div(
+            &amount.wrapping_mul(&pow10(exponent)),
+            &scaling_factor,
+            round_up,
+        )
+    } else {
+        div(
+            &amount,
+            &scaling_factor.wrapping_mul(&pow10(exponent)),
+            round_up,
+        )
+    }
+}
+
+// inverse of `to_scaled`
+fn from_scaled(value: U512, scaling_factor: u64, exponent: i32, round_up: bool) -> Result<u128> {
+    let amount = checked_mul(&value, &U512::from_u64(scaling_factor))
+        .ok_or_else(|| eyre!("Overflow while calculating quote"))?;
+    let amount = if exponent >= 0 {
+        div(&amount, &pow10(exponent), round_up)
+    } else {
+        checked_mul(&amount, &pow10(exponent))
+            .ok_or_else(|| eyre!("Overflow while calculating quote"))?
+    };
+    wide_to_u128(&amount)
+}
+
+// power of ten which gives the largest of `amounts` STABLESWAP_DIGITS digits once scaled
+fn stableswap_exponent(amounts: &[(u128, u64)]) -> i32 {
+    let largest = amounts
+        .iter()
+        .map(|(amount, scaling_factor)| amount / *scaling_factor as u128)
+        .max()
+        .unwrap_or(0);
+    let digits = if largest == 0 {
+        0
+    } else {
+        largest.ilog10() as i32 + 1
+    };
+    STABLESWAP_DIGITS - digits
+}
+
+// multi asset stableswap invariant of osmosis without the product of the other reserves,
+// which stays the same in a swap: x * y * (x^2 + y^2 + w), w is the sum of squares of the
+// other reserves. None if it does not fit into 512 bits.
+fn stableswap_cfmm(x: &U512, y: &U512, w: &U512) -> Option<U512> {
+    let squares = checked_add(&checked_add(&checked_mul(x, x)?, &checked_mul(y, y)?)?, w)?;
+    checked_mul(&checked_mul(x, y)?, &squares)
+}
+
+// smallest y which keeps the invariant at or above k, the invariant grows with y
+// so a bisection finds it. `high` has to reach k.
+fn stableswap_solve(x: &U512, w: &U512, k: &U512, high: U512) -> U512 {
+    // an overflowing invariant is above k
+    let reaches = |y: &U512| stableswap_cfmm(x, y, w).is_none_or(|x| x >= *k);
+    let mut low = U512::ZERO;
+    let mut high = high;
+    while high.wrapping_sub(&low) > U512::ONE {
+        let mid = low.wrapping_add(&high.wrapping_sub(&low).shr_vartime(1));
+        if reaches(&mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    high
+}
+
+// scaled reserves of the swapped assets and the sum of squares of the other ones
+fn stableswap_reserves(
+    reserves: &[u128],
+    scaling_factors: &[u64],
+    token_in: usize,
+    token_out: usize,
+    exponent: i32,
+) -> (U512, U512, U512) {
+    let mut w = U512::ZERO;
+    for (index, (reserve, scaling_factor)) in reserves.iter().zip(scaling_factors).enumerate() {
+        if index != token_in && index != token_out {
+            let scaled = to_scaled(*reserve, *scaling_factor, exponent, false);
+            // at most 36 digits, so the squares of a few assets fit
+            w = w.wrapping_add(&scaled.wrapping_mul(&scaled));
+        }
+    }
+    (
+        to_scaled(
+            reserves[token_in],
+            scaling_factors[token_in],
+            exponent,
+            false,
+        ),
+        to_scaled(
+            reserves[token_out],
+            scaling_factors[token_out],
+            exponent,
+            false,
+        ),
+        w,
+    )
+}
+
+fn check_stableswap(
+    reserves: &[u128],
+    scaling_factors: &[u64],
+    token_in: usize,
+    token_out: usize,
+    swap_fee: Dec,
+) -> Result<()> {
+    if reserves.len() != scaling_factors.len() {
+        return Err(eyre!(
+            "Pool has {} assets but {} scaling factors",
+            reserves.len(),
+            scaling_factors.len()
+        ));
+    }
+    if token_in == token_out || token_in >= reserves.len() || token_out >= reserves.len() {
+        return Err(eyre!("Invalid assets {} -> {}", token_in, token_out));
+    }
+    if reserves[token_in] == 0 || reserves[token_out] == 0 {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    if scaling_factors.contains(&0) {
+        return Err(eyre!("Pool asset has no scaling factor"));
+    }
+    check_fee(swap_fee)
+}
+
+// 1 - swap_fee and 1 in atomics of Dec, to take the fee from scaled amounts
+fn stableswap_fee_factor(swap_fee: Dec) -> Result<(U512, U512)> {
+    let atomics = |x: Dec| U512::from_u128(x.atomics().as_u128());
+    Ok((atomics(Dec::ONE.checked_sub(swap_fee)?), atomics(Dec::ONE)))
+}
+
+/// Stableswap swap as implemented by osmosis for any number of assets.
+///
+/// The reserves are divided by their scaling factors, then `x * y * (x^2 + y^2 + w) = k`
+/// is solved for the new output reserve. The fee is taken from the scaled input, the output
+/// is rounded down.
+pub fn stableswap_out_given_in(
+    reserves: &[u128],
+    scaling_factors: &[u64],
+    token_in: usize,
+    token_out: usize,
+    amount_in: u128,
+    swap_fee: Dec,
+) -> Result<u128> {
+    check_stableswap(reserves, scaling_factors, token_in, token_out, swap_fee)?;
+    let mut amounts: Vec<(u128, u64)> = reserves
+        .iter()
+        .copied()
+        .zip(scaling_factors.iter().copied())
+        .collect();
+    amounts.push((amount_in, scaling_factors[token_in]));
+    let exponent = stableswap_exponent(&amounts);
+
+    let (x, y, w) = stableswap_reserves(reserves, scaling_factors, token_in, token_out, exponent);
+    let k = stableswap_cfmm(&x, &y, &w).ok_or_else(|| eyre!("Overflow while calculating quote"))?;
+    if k == U512::ZERO {
+        return Err(eyre!("Pool has no liquidity"));
+    }
+    let (fee_factor, one) = stableswap_fee_factor(swap_fee)?;
+    let amount_in = to_scaled(amount_in, scaling_factors[token_in], exponent, false);
+    let amount_in = div(&amount_in.wrapping_mul(&fee_factor), &one, false);
+    let y_new = stableswap_solve(&x.wrapping_add(&amount_in), &w, &k, y);
+    // never round in favour of the trader
+    from_scaled(
+        y.wrapping_sub(&y_new),
+        scaling_factors[token_out],
+        exponent,
+        false,
+    )
+}
+
+/// Inverse of [`stableswap_out_given_in`], rounded up so the output is at least `amount_out`.
+pub fn stableswap_in_given_out(
+    reserves: &[u128],
+    scaling_factors: &[u64],
+    token_in: usize,
+    token_out: usize,
+    amount_out: u128,
+    swap_fee: Dec,
+) -> Result<u128> {
+    check_stableswap(reserves, scaling_factors, token_in, token_out, swap_fee)?;
+    if amount_out >= reserves[token_out] {
+        return Err(eyre!("Not enough liquidity for {}", amount_out));
+    }
+    let amounts: Vec<(u128, u64)> = reserves
+        .iter()
+        .copied()
+        .zip(scaling_factors.iter().copied())
+        .collect();
+    let exponent = stableswap_exponent(&amounts);
+
+    let (x, y, w) = stableswap_reserves(reserves, scaling_factors, token_in, token_out, exponent);
+    let k = stableswap_cfmm(&x, &y, &w).ok_or_else(|| eyre!("Overflow while calculating quote"))?;
+    let scaled_out = to_scaled(amount_out, scaling_factors[token_out], exponent, true);
+    if k == U512::ZERO || scaled_out >= y {
+        return Err(eyre!("Not enough liquidity for {}", amount_out));
+    }
+    let y_new = y.wrapping_sub(&scaled_out);
+    // the invariant is symmetric, so the same solver finds the new input reserve
+    let mut high = x.max(U512::ONE);
+    while stableswap_cfmm(&high, &y_new, &w).is_some_and(|x| x < k) {
+        high = high.shl_vartime(1);
+    }
+    let x_new = stableswap_solve(&y_new, &w, &k, high);
+    // the fee is added to the scaled input like in osmosis
+    let (fee_factor, one) = stableswap_fee_factor(swap_fee)?;
+    let amount_in = div(
+        &x_new.wrapping_sub(&x).wrapping_mul(&one),
+        &fee_factor,
+        true,
+    );
+    from_scaled(amount_in, scaling_factors[token_in], exponent, true)
+}
+
+/// Spot price of a stableswap pool, token_out per token_in without fees. It is the slope
+/// of the invariant on the scaled reserves, `y * (3x^2 + y^2 + w) / (x * (x^2 + 3y^2 + w))`,
+/// times the ratio of the scaling factors.
+pub fn stableswap_spot_price(
+    reserves: &[u128],
+    scaling_factors: &[u64],
+    token_in: usize,
+    token_out: usize,
+) -> Result<Dec> {
+    check_stableswap(reserves, scaling_factors, token_in, token_out, Dec::ZERO)?;
+    let scaled =
+        |index: usize| Dec::from(reserves[index]).checked_quo(Dec::from(scaling_factors[index]));
+    // relative to the larger reserve of the pair so the squares stay small
+    let unit = scaled(token_in)?.max(scaled(token_out)?);
+    let x = scaled(token_in)?.checked_quo(unit)?;
+    let y = scaled(token_out)?.checked_quo(unit)?;
+    let mut w = Dec::ZERO;
+    for index in 0..reserves.len() {
+        if index != token_in && index != token_out {
+            let z = scaled(index)?.checked_quo(unit)?;
+            w = w.checked_add(z.checked_mul(z)?)?;
+        }
+    }
+    let three = Dec::from(3u64);
+    let (x2, y2) = (x.checked_mul(x)?, y.checked_mul(y)?);
+    let numerator = y.checked_mul(three.checked_mul(x2)?.checked_add(y2)?.checked_add(w)?)?;
+    let denominator = x.checked_mul(x2.checked_add(three.checked_mul(y2)?)?.checked_add(w)?)?;
+    numerator
+        .checked_quo(denominator)?
+        .checked_mul(Dec::from(scaling_factors[token_out]))?
+        .checked_quo(Dec::from(scaling_factors[token_in]))
+}
+
+// a power of ten of the price spans 9 * 10^-exponent_at_price_one ticks in osmosis
+// concentrated liquidity pools
+const TICKS_PER_POWER_OF_TEN: i64 = 9;
+
+/// Price of a concentrated liquidity tick, the price grows by `10^exponent_at_price_one`
+/// per tick between 1 and 10 and the increment grows tenfold with every power of ten.
+pub fn tick_to_price(tick: i64, exponent_at_price_one: i64) -> Result<Dec> {
+    let ticks_per_power = u32::try_from(exponent_at_price_one.saturating_neg().max(0))
+        .ok()
+        .and_then(|x| 10i64.checked_pow(x))
+        .and_then(|x| x.checked_mul(TICKS_PER_POWER_OF_TEN))
+        .ok_or_else(|| eyre!("Invalid exponent at price one {}", exponent_at_price_one))?;
+    let power = tick / ticks_per_power;
+    let mut exponent = exponent_at_price_one.saturating_add(power);
+    // the increment gets finer below price one
+    if tick < 0 {
+        exponent -= 1;
+    }
+    let additive_ticks = tick - power * ticks_per_power;
+    // value * 10^exponent
+    let scale = |value: Dec, exponent: i64| -> Result<Dec> {
+        let power = Dec::pow10(u32::try_from(exponent.unsigned_abs())?)?;
+        if exponent >= 0 {
+            value.checked_mul(power)
+        } else {
+            value.checked_quo(power)
+        }
+    };
+    let price = scale(Dec::ONE, power)?.checked_add(scale(Dec::from(additive_ticks), exponent)?)?;
+    if price <= Dec::ZERO {
+        return Err(eyre!("Price of tick {} is out of range", tick));
+    }
+    Ok(price)
+}
+
+/// Swap of a concentrated liquidity pool inside a range of constant `liquidity`, moves the
+/// square root price from `sqrt_price` towards `sqrt_price_target` with at most `amount_in`
+/// (after fees). Token 0 goes in and the price falls if `zero_for_one`.
+///
+/// Returns the new square root price, the used input and the output.
+pub fn concentrated_swap_step(
+    sqrt_price: Dec,
+    sqrt_price_target: Dec,
+    liquidity: Dec,
+    amount_in: Dec,
+    zero_for_one: bool,
+) -> Result<(Dec, Dec, Dec)> {
+    if liquidity <= Dec::ZERO {
+        return Ok((sqrt_price_target, Dec::ZERO, Dec::ZERO));
+    }
+    if zero_for_one {
+        let max_in = liquidity
+            .checked_mul(sqrt_price.checked_sub(sqrt_price_target)?)?
+            .checked_quo(sqrt_price)?
+            .checked_quo(sqrt_price_target)?;
+        let (next, used) = if amount_in >= max_in {
+            (sqrt_price_target, max_in)
+        } else {
+            let denominator = liquidity.checked_add(amount_in.checked_mul(sqrt_price)?)?;
+            (
+                liquidity
+                    .checked_mul(sqrt_price)?
+                    .checked_quo(denominator)?,
+                amount_in,
+            )
+        };
+        let amount_out = liquidity.checked_mul(sqrt_price.checked_sub(next)?)?;
+        Ok((next, used, amount_out))
+    } else {
+        let max_in = liquidity.checked_mul(sqrt_price_target.checked_sub(sqrt_price)?)?;
+        let (next, used) = if amount_in >= max_in {
+            (sqrt_price_target, max_in)
+        } else {
+            (
+                sqrt_price.checked_add(amount_in.checked_quo(liquidity)?)?,
+                amount_in,
+            )
+        };
+        let amount_out = liquidity
+            .checked_mul(next.checked_sub(sqrt_price)?)?
+            .checked_quo(next)?
+            .checked_quo(sqrt_price)?;
+        Ok((next, used, amount_out))
+    }
+}
+
+/// Inverse of [`concentrated_swap_step`], receives at most `amount_out`.
+///
+/// Returns the new square root price, the needed input (before fees) and the received output.
+pub fn concentrated_swap_step_exact_out(
+    sqrt_price: Dec,
+    sqrt_price_target: Dec,
+    liquidity: Dec,
+    amount_out: Dec,
+    zero_for_one: bool,
+) -> Result<(Dec, Dec, Dec)> {
+    if liquidity <= Dec::ZERO {
+        return Ok((sqrt_price_target, Dec::ZERO, Dec::ZERO));
+    }
+    if zero_for_one {
+        let max_out = liquidity.checked_mul(sqrt_price.checked_sub(sqrt_price_target)?)?;
+        let (next, received) = if amount_out >= max_out {
+            (sqrt_price_target, max_out)
+        } else {
+            (
+                sqrt_price.checked_sub(amount_out.checked_quo(liquidity)?)?,
+                amount_out,
+            )
+        };
+        let amount_in = liquidity
+            .checked_mul(sqrt_price.checked_sub(next)?)?
+            .checked_quo(sqrt_price)?
+            .checked_quo(next)?;
+        Ok((next, amount_in, received))
+    } else {
+        let max_out = liquidity
+            .checked_mul(sqrt_price_target.checked_sub(sqrt_price)?)?
+            .checked_quo(sqrt_price)?
+            .checked_quo(sqrt_price_target)?;
+        let (next, received) = if amount_out >= max_out {
+            (sqrt_price_target, max_out)
+        } else {
+            let denominator = liquidity.checked_sub(amount_out.checked_mul(sqrt_price)?)?;
+            (
+                liquidity
+                    .checked_mul(sqrt_price)?
+                    .checked_quo(denominator)?,
+                amount_out,
+            )
+        };
+        let amount_in = liquidity.checked_mul(next.checked_sub(sqrt_price)?)?;
+        Ok((next, amount_in, received))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::juno_pool::WasmPool;
+    use crate::pools::osmosis_pool::OsmosisPool;
+    use crate::pools::stableswap_pool::StableswapPool;
+    use crate::pools::terraswap_pool::TerraswapPool;
+    use crate::Pool;
+
+    // pools of the snapshots, the expected amounts are the osmosis balancer math evaluated
+    // with sdk.Dec rounding, the osmosis stableswap math solved with exact fractions and the
+    // input price of the WasmSwap and Terraswap contracts
+    fn fixture<T: serde::de::DeserializeOwned>(name: &str) -> Vec<T> {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn osmosis_pool(id: &str) -> OsmosisPool {
+        fixture::<OsmosisPool>("osmosis_pools.json")
+            .into_iter()
+            .find(|x| x.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn weighted_out_given_in_equal_weights() {
+        // ATOM/OSMO 50/50, 0.2% swap fee
+        let pool = osmosis_pool("1");
+        let quote = |amount, token_in, token_out| pool.simulate_swap(amount, token_in, token_out);
+        assert_eq!(quote(1_000_000, "uatom", "uosmo").unwrap(), 11_696_984);
+        assert_eq!(
+            quote(250_000_000_000, "uatom", "uosmo").unwrap(),
+            2_703_080_149_541
+        );
+        assert_eq!(quote(1_000_000, "uosmo", "uatom").unwrap(), 85_150);
+    }
+
+    #[test]
+    fn weighted_out_given_in_unequal_weights() {
+        // ION/OSMO 80/20, 0.5% swap fee, the fractional weight ratio goes through pow_approx
+        let pool = osmosis_pool("2");
+        let quote = |amount, token_in, token_out| pool.simulate_swap(amount, token_in, token_out);
+        assert_eq!(quote(1_000_000, "uosmo", "uion").unwrap(), 954);
+        assert_eq!(quote(50_000_000_000, "uosmo", "uion").unwrap(), 42_897_547);
+        assert_eq!(quote(1_000_000, "uion", "uosmo").unwrap(), 1_035_019_743);
+    }
+
+    #[test]
+    fn weighted_in_given_out_of_fixtures() {
+        let pool = osmosis_pool("1");
+        let quote =
+            |amount, token_in, token_out| pool.simulate_swap_exact_out(amount, token_in, token_out);
+        assert_eq!(quote(1_000_000, "uatom", "uosmo").unwrap(), 85_493);
+        assert_eq!(
+            quote(250_000_000_000, "uatom", "uosmo").unwrap(),
+            21_523_580_565
+        );
+        assert_eq!(quote(1_000_000, "uosmo", "uatom").unwrap(), 11_743_921);
+
+        let pool = osmosis_pool("2");
+        let quote =
+            |amount, token_in, token_out| pool.simulate_swap_exact_out(amount, token_in, token_out);
+        assert_eq!(quote(500, "uosmo", "uion").unwrap(), 523_976);
+        assert_eq!(
+            quote(100_000_000, "uosmo", "uion").unwrap(),
+            135_777_669_645
+        );
+        assert_eq!(quote(1_000_000, "uion", "uosmo").unwrap(), 964);
+        // osmosis only approximates the power for bases below two
+        assert!(quote(600_000_000, "uosmo", "uion").is_err());
+    }
+
+    #[test]
+    fn weighted_spot_price_of_fixtures() {
+        let pool = osmosis_pool("1");
+        assert_eq!(
+            pool.spot_price("uatom", "uosmo").unwrap().to_string(),
+            "11.720428981155496804"
+        );
+        let pool = osmosis_pool("2");
+        assert_eq!(
+            pool.spot_price("uion", "uosmo").unwrap().to_string(),
+            "1042.709128724655049096"
+        );
+        assert_eq!(
+            pool.spot_price("uosmo", "uion").unwrap().to_string(),
+            "0.000959040227472744"
+        );
+    }
+
+    #[test]
+    fn weighted_rejects_invalid_pools() {
+        let fee = Dec::ZERO;
+        assert!(weighted_out_given_in(0, 1, 100, 1, 10, fee).is_err());
+        assert!(weighted_out_given_in(100, 0, 100, 1, 10, fee).is_err());
+        assert!(weighted_out_given_in(100, 1, 100, 1, 10, Dec::ONE).is_err());
+        assert!(weighted_in_given_out(100, 1, 100, 1, 100, fee).is_err());
+    }
+
+    #[test]
+    fn constant_product_out_given_in_of_fixture() {
+        // sFOT/uusd WasmSwap pool, 0.3% fee on the input
+        let pool = fixture::<WasmPool>("juno_pools.json").remove(0);
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000_000, "sFOT", "uusd")
+                .unwrap(),
+            33_536_441
+        );
+        assert_eq!(
+            pool.simulate_swap(1_000_000, "uusd", "sFOT").unwrap(),
+            29_631_261_800
+        );
+    }
+
+    #[test]
+    fn constant_product_in_given_out_of_fixture() {
+        let pool = fixture::<WasmPool>("juno_pools.json").remove(0);
+        assert_eq!(
+            pool.simulate_swap_exact_out(1_000_000, "sFOT", "uusd")
+                .unwrap(),
+            29_810_341_541
+        );
+        assert_eq!(
+            pool.simulate_swap_exact_out(10_000_000_000, "uusd", "sFOT")
+                .unwrap(),
+            337_480
+        );
+    }
+
+    #[test]
+    fn constant_product_in_given_out_is_smallest_input() {
+        let (fee_numerator, fee_denominator) = (WASMSWAP_FEE_NUMERATOR, WASMSWAP_FEE_DENOMINATOR);
+        let out = |reserve_in, reserve_out, amount_in| {
+            constant_product_out_given_in(
+                reserve_in,
+                reserve_out,
+                amount_in,
+                fee_numerator,
+                fee_denominator,
+            )
+            .unwrap()
+        };
+        // 997 * 1000 * 1000 / (1000 * 997) divides without remainder, 1000 in gives exactly
+        // 1000 out so no extra unit is needed
+        let amount_in =
+            constant_product_in_given_out(997, 2000, 1000, fee_numerator, fee_denominator).unwrap();
+        assert_eq!(amount_in, 1000);
+        assert_eq!(out(997, 2000, 1000), 1000);
+        assert_eq!(out(997, 2000, 999), 999);
+
+        for (reserve_in, reserve_out, amount_out) in [
+            (3_618_832_819_961_800, 121_761_496_470, 1_000_000),
+            (121_761_496_470, 3_618_832_819_961_800, 10_000_000_000),
+            (1_000_000, 1_000_000, 1),
+            (1_000_000, 1_000_000, 999_999),
+        ] {
+            let amount_in = constant_product_in_given_out(
+                reserve_in,
+                reserve_out,
+                amount_out,
+                fee_numerator,
+                fee_denominator,
+            )
+            .unwrap();
+            assert!(out(reserve_in, reserve_out, amount_in) >= amount_out);
+            assert!(out(reserve_in, reserve_out, amount_in - 1) < amount_out);
+        }
+        assert!(constant_product_in_given_out(10, 10, 10, 3, 1000).is_err());
+    }
+
+    #[test]
+    fn constant_product_spot_price_of_fixture() {
+        let pool = fixture::<WasmPool>("juno_pools.json").remove(0);
+        assert_eq!(
+            pool.spot_price("uusd", "sFOT").unwrap().to_string(),
+            "29720.666424738135447786"
+        );
+        assert!(constant_product_spot_price(0, 1).is_err());
+    }
+
+    #[test]
+    fn commission_out_given_in_of_fixture() {
+        // Terraswap pair with 0.3% commission on the output
+        let pool = fixture::<TerraswapPool>("terraswap_pools.json").remove(0);
+        assert_eq!(
+            pool.simulate_swap(1_000_000, "ujuno", "TKN").unwrap(),
+            7_975_354_069
+        );
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000, "TKN", "ujuno").unwrap(),
+            124_624
+        );
+        assert_eq!(
+            pool.spot_price("ujuno", "TKN").unwrap().to_string(),
+            "8000.000073051956658856"
+        );
+    }
+
+    #[test]
+    fn commission_in_given_out_reaches_output() {
+        let rate: Dec = TERRASWAP_COMMISSION_RATE.parse().unwrap();
+        for (reserve_in, reserve_out, amount_out) in [
+            (12_345_678_901, 98_765_432_109_876, 7_975_354_069),
+            (98_765_432_109_876, 12_345_678_901, 124_624),
+            (1_000_000, 1_000_000, 1),
+        ] {
+            let amount_in =
+                constant_product_commission_in_given_out(reserve_in, reserve_out, amount_out, rate)
+                    .unwrap();
+            let received =
+                constant_product_commission_out_given_in(reserve_in, reserve_out, amount_in, rate)
+                    .unwrap();
+            assert!(received >= amount_out);
+        }
+        assert!(constant_product_commission_out_given_in(1_000, 1_000, 10, Dec::ONE).is_err());
+    }
+
+    // USDC/DAI/USDT with 0.05% swap fee, DAI has 18 decimals and a scaling factor of 10^12
+    fn stableswap_pool() -> StableswapPool {
+        fixture::<StableswapPool>("osmosis_stableswap_pools.json").remove(0)
+    }
+
+    #[test]
+    fn stableswap_out_given_in_of_fixture() {
+        let pool = stableswap_pool();
+        let quote = |amount, token_in, token_out| pool.simulate_swap(amount, token_in, token_out);
+        assert_eq!(
+            quote(1_000_000, "uusdc", "dai-wei").unwrap(),
+            990_564_137_060_011_544
+        );
+        assert_eq!(
+            quote(250_000_000_000, "uusdc", "dai-wei").unwrap(),
+            242_056_936_824_742_378_188_166
+        );
+        assert_eq!(
+            quote(10u128.pow(18), "dai-wei", "uusdt").unwrap(),
+            1_015_724
+        );
+        assert_eq!(
+            quote(500_000 * 10u128.pow(18), "dai-wei", "uusdc").unwrap(),
+            481_644_110_398
+        );
+        assert_eq!(quote(123_456_789, "uusdt", "uusdc").unwrap(), 122_518_249);
+        // the fee is taken from the scaled input, so a single unit still buys dai
+        assert_eq!(quote(1, "uusdt", "dai-wei").unwrap(), 983_534_195_088);
+        // pool assets are found by ibc denom too
+        assert_eq!(
+            pool.simulate_swap(
+                1_000_000,
+                "ibc/498A0751C798A0D9A389AA3691123DADA57DAA4FE165D5C75894505B876BA6E4",
+                "ibc/0CD3A0285E1341859B5E86B6AB7682F023D03E97607CCC1DC95706411D866DF7"
+            )
+            .unwrap(),
+            990_564_137_060_011_544
+        );
+        assert!(quote(1_000_000, "uusdc", "uatom").is_err());
+    }
+
+    #[test]
+    fn stableswap_in_given_out_of_fixture() {
+        let pool = stableswap_pool();
+        let quote =
+            |amount, token_in, token_out| pool.simulate_swap_exact_out(amount, token_in, token_out);
+        assert_eq!(
+            quote(10u128.pow(18), "uusdc", "dai-wei").unwrap(),
+            1_009_526
+        );
+        assert_eq!(
+            quote(250_000 * 10u128.pow(18), "uusdc", "dai-wei").unwrap(),
+            258_408_607_183
+        );
+        assert_eq!(
+            quote(1_000_000, "dai-wei", "uusdt").unwrap(),
+            984_518_546_998_187_190
+        );
+        assert_eq!(
+            quote(500_000_000_000, "dai-wei", "uusdc").unwrap(),
+            520_087_000_001_389_897_219_356
+        );
+        assert_eq!(quote(123_456_789, "uusdt", "uusdc").unwrap(), 124_402_527);
+        assert!(quote(2_500_000_000_000, "dai-wei", "uusdc").is_err());
+    }
+
+    #[test]
+    fn stableswap_in_given_out_inverts_out_given_in() {
+        let pool = stableswap_pool();
+        for (amount_in, token_in, token_out) in [
+            (1_000_000, "uusdc", "dai-wei"),
+            (250_000_000_000, "uusdc", "dai-wei"),
+            (10u128.pow(18), "dai-wei", "uusdt"),
+            (500_000 * 10u128.pow(18), "dai-wei", "uusdc"),
+            (123_456_789, "uusdt", "uusdc"),
+        ] {
+            let amount_out = pool.simulate_swap(amount_in, token_in, token_out).unwrap();
+            let needed = pool
+                .simulate_swap_exact_out(amount_out, token_in, token_out)
+                .unwrap();
+            // the output is rounded down, so the same output never costs more
+            assert!(needed <= amount_in);
+            assert!(pool.simulate_swap(needed, token_in, token_out).unwrap() >= amount_out);
+            // and at most the rounding of the output in units of the input
+            let rounding = if token_in == "dai-wei" {
+                10u128.pow(12)
+            } else {
+                1
+            };
+            assert!(amount_in - needed <= rounding, "{} {}", amount_in, needed);
+        }
+    }
+
+    #[test]
+    fn stableswap_spot_price_of_fixture() {
+        // the exact price rounded to 18 decimals
+        let pool = stableswap_pool();
+        let price = |token_in, token_out| pool.spot_price(token_in, token_out).unwrap().to_string();
+        assert_eq!(price("uusdc", "dai-wei"), "991059755694.948828000000000000");
+        assert_eq!(price("dai-wei", "uusdc"), "0.000000000001009021");
+        assert_eq!(price("uusdc", "uusdt"), "1.007147723753484051");
+        assert_eq!(price("dai-wei", "uusdt"), "0.000000000001016233");
+    }
+
+    #[test]
+    fn stableswap_rejects_invalid_pools() {
+        let fee = Dec::ZERO;
+        assert!(stableswap_out_given_in(&[100, 100], &[1], 0, 1, 10, fee).is_err());
+        assert!(stableswap_out_given_in(&[100, 100], &[1, 0], 0, 1, 10, fee).is_err());
+        assert!(stableswap_out_given_in(&[0, 100], &[1, 1], 0, 1, 10, fee).is_err());
+        assert!(stableswap_out_given_in(&[100, 100], &[1, 1], 0, 0, 10, fee).is_err());
+        assert!(stableswap_out_given_in(&[100, 100], &[1, 1], 0, 1, 10, Dec::ONE).is_err());
+        assert!(stableswap_in_given_out(&[100, 100], &[1, 1], 0, 1, 100, fee).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+
+use super::math::{constant_product_in_given_out, constant_product_out_given_in};
+use crate::{Pool, PoolConfig, Quote, QuoteSource};
+
+/// Pool of the router tests, every pair is an own x * y = k curve with a 0.3% fee, so
+/// its prices do not have to be consistent like in a real pool.
+#[derive(Debug, Clone)]
+pub(crate) struct MockPool {
+    pub chain: String,
+    pub address: String,
+    // (denom, reserve) of both sides of a pair
+    pub pairs: Vec<((String, u128), (String, u128))>,
+    // number of refreshes which lead to this copy
+    pub refreshes: u32,
+}
+
+impl MockPool {
+    pub fn pair(chain: &str, address: &str, a: (&str, u128), b: (&str, u128)) -> Self {
+        MockPool {
+            chain: chain.to_owned(),
+            address: address.to_owned(),
+            pairs: vec![],
+            refreshes: 0,
+        }
+        .with_pair(a, b)
+    }
+
+    pub fn with_pair(mut self, a: (&str, u128), b: (&str, u128)) -> Self {
+        self.pairs
+            .push(((a.0.to_owned(), a.1), (b.0.to_owned(), b.1)));
+        self
+    }
+
+    pub fn boxed(self) -> Box<dyn Pool> {
+        Box::new(self)
+    }
+
+    fn reserves(&self, token_in: &str, token_out: &str) -> Result<(u128, u128)> {
+        for (a, b) in &self.pairs {
+            if a.0 == token_in && b.0 == token_out {
+                return Ok((a.1, b.1));
+            }
+            if b.0 == token_in && a.0 == token_out {
+                return Ok((b.1, a.1));
+            }
+        }
+        Err(eyre!("Cannot find pair: {} | {}", token_in, token_out))
+    }
+}
+
+#[async_trait]
+impl Pool for MockPool {
+    async fn get_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        _config: &PoolConfig,
+    ) -> Result<Quote> {
+        Ok(Quote {
+            token_in: Some(amount),
+            token_out: Some(self.simulate_swap(amount, token_in_denom, token_out_denom)?),
+            pool_address: Some(self.address.clone()),
+            source: Some(QuoteSource::Offline),
+            ..Default::default()
+        })
+    }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        _config: &PoolConfig,
+    ) -> Result<Quote> {
+        Ok(Quote {
+            token_in: Some(self.simulate_swap_exact_out(
+                amount,
+                token_in_denom,
+                token_out_denom,
+            )?),
+            token_out: Some(amount),
+            pool_address: Some(self.address.clone()),
+            source: Some(QuoteSource::Offline),
+            ..Default::default()
+        })
+    }
+
+    fn simulate_swap(&self, amount: u128, token_in: &str, token_out: &str) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves(token_in, token_out)?;
+        constant_product_out_given_in(reserve_in, reserve_out, amount, 3, 1000)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in: &str,
+        token_out: &str,
+    ) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves(token_in, token_out)?;
+        constant_product_in_given_out(reserve_in, reserve_out, amount, 3, 1000)
+    }
+
+    async fn refresh(&self, _config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        Ok(Box::new(MockPool {
+            refreshes: self.refreshes + 1,
+            ..self.clone()
+        }))
+    }
+
+    fn token_denoms(&self) -> Vec<String> {
+        let mut denoms = vec![];
+        for (a, b) in &self.pairs {
+            for denom in [&a.0, &b.0] {
+                if !denoms.contains(denom) {
+                    denoms.push(denom.clone());
+                }
+            }
+        }
+        denoms
+    }
+
+    fn to_json(&self) -> String {
+        String::new()
+    }
+
+    fn chain(&self) -> String {
+        self.chain.clone()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn address(&self) -> Result<String> {
+        Ok(self.address.clone())
+    }
+}
@@ -1,2 +1,27 @@
+pub mod concentrated_pool;
+pub mod discovery;
 pub mod juno_pool;
+pub mod math;
+#[cfg(test)]
+pub(crate) mod mock_pool;
 pub mod osmosis_pool;
+pub mod stableswap_pool;
+pub mod terraswap_pool;
+
+use std::fs;
+use std::path::Path;
+
+use eyre::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Writes the pools of an adapter as a JSON snapshot.
+pub fn save_pools_to_file<T: Serialize>(path: &Path, pools: &[T]) -> Result<()> {
+    fs::write(path, serde_json::to_string(pools)?)?;
+    Ok(())
+}
+
+/// Reads a snapshot written by [`save_pools_to_file`].
+pub fn load_pools_from_file<T: DeserializeOwned>(path: &Path) -> Result<Vec<Box<T>>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
@@ -1,17 +1,37 @@
-use std::fmt;
-use std::fs::File;
-use std::io::{Read, Write};
 use std::path::Path;
 
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{eyre, Result};
+use futures::future::join_all;
+use prost::Message;
+use prost_types::Any;
 
-use crate::util::denom_trace::{
-    load_denom_trace_cache_from_file, resolve_ibc, save_denom_trace_cache_to_file,
+use super::concentrated_pool::{
+    fetch_tick_ranges, save_concentrated_pools_to_file, ConcentratedPool,
+    CONCENTRATED_POOL_TYPE_URL,
 };
+use super::math::{
+    reserves_after_swap, weighted_in_given_out, weighted_out_given_in, weighted_spot_price,
+};
+use super::stableswap_pool::{save_stableswap_pools_to_file, StableswapPool};
+use super::{load_pools_from_file, save_pools_to_file};
+use crate::assets::AssetRegistry;
+use crate::config::{AdapterConfig, PoolAdapter};
+use crate::decimal::Dec;
+use crate::util::endpoint::EndpointPool;
+use crate::util::proto::cosmos_base_query_v1beta1::PageRequest;
+use crate::util::proto::cosmos_base_v1beta1::Coin;
+use crate::util::proto::osmosis_concentratedliquidity_v1beta1::{
+    query_client::QueryClient as ConcentratedClient, Pool as ConcentratedProtoPool, PoolsRequest,
+};
+use crate::util::proto::osmosis_gamm_poolmodels_stableswap_v1beta1::Pool as StableswapProtoPool;
 use crate::util::proto::osmosis_gamm_v1beta1::query_client::QueryClient;
-use crate::util::proto::osmosis_gamm_v1beta1::{QuerySwapExactAmountInRequest, SwapAmountInRoute};
-use crate::{Pool, PoolConfig, Quote};
+use crate::util::proto::osmosis_gamm_v1beta1::{
+    Pool as BalancerPool, QueryPoolsRequest, QuerySwapExactAmountInRequest,
+    QuerySwapExactAmountOutRequest, QueryTotalPoolLiquidityRequest, SwapAmountInRoute,
+    SwapAmountOutRoute,
+};
+use crate::{Fee, Pool, PoolConfig, Quote};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct OsmosisPool {
@@ -30,26 +50,6 @@ pub struct OsmosisPool {
     pub chain: Option<String>,
 }
 
-#[derive(Debug)]
-pub enum TracyError {
-    Only2AssersError,
-}
-
-impl std::error::Error for TracyError {}
-unsafe impl Sync for TracyError {}
-unsafe impl Send for TracyError {}
-
-impl fmt::Display for TracyError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            TracyError::Only2AssersError => write!(
-                f,
-                "Can only calculate quote for pools with 2 assets, estimate quote instead."
-            ),
-        }
-    }
-}
-
 impl OsmosisPool {
     // takes ibc or native denom and converts to correct type
     fn asset_for_denom(&self, denom: &str) -> Option<usize> {
@@ -62,23 +62,62 @@ impl OsmosisPool {
         })
     }
 
+    // a swap only touches the two assets of the pair, in pools with more assets the others
+    // neither change the reserves nor the weight ratio of the pair
+    fn assets_for_pair(
+        &self,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<(&OsmosisPoolAssets, &OsmosisPoolAssets)> {
+        let token_in_index = self
+            .asset_for_denom(token_in_denom)
+            .ok_or_else(|| eyre!("Pool does not contain {}", token_in_denom))?;
+        let token_out_index = self
+            .asset_for_denom(token_out_denom)
+            .ok_or_else(|| eyre!("Pool does not contain {}", token_out_denom))?;
+        if token_in_index == token_out_index {
+            return Err(eyre!("Cannot swap {} to itself", token_in_denom));
+        }
+        Ok((
+            &self.pool_assets[token_in_index],
+            &self.pool_assets[token_out_index],
+        ))
+    }
+
     fn calculate_quote(
         &self,
         amount: u128,
-        token_in_amount: u128,
-        token_out_amount: u128,
-        token_in_weight: u128,
-        token_out_weight: u128,
-        token_in_decimals: u32,
-        token_out_decimals: u32,
+        token_in_denom: &str,
+        token_out_denom: &str,
     ) -> Result<u128> {
-        if self.pool_assets.len() != 2 {
-            return Err(TracyError::Only2AssersError.into());
-        }
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+
         // only on block by block basis, no time weighted average
-        Ok(
-            (token_out_amount * token_out_weight * u128::from(token_out_decimals) * amount)
-                / (token_in_amount * token_in_weight * u128::from(token_in_decimals)),
+        weighted_out_given_in(
+            token_in.token.amount.parse::<u128>()?,
+            token_in.weight.parse::<u128>()?,
+            token_out.token.amount.parse::<u128>()?,
+            token_out.weight.parse::<u128>()?,
+            amount,
+            self.pool_params.swap_fee.parse::<Dec>()?,
+        )
+    }
+
+    fn calculate_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+
+        weighted_in_given_out(
+            token_in.token.amount.parse::<u128>()?,
+            token_in.weight.parse::<u128>()?,
+            token_out.token.amount.parse::<u128>()?,
+            token_out.weight.parse::<u128>()?,
+            amount,
+            self.pool_params.swap_fee.parse::<Dec>()?,
         )
     }
 
@@ -90,27 +129,148 @@ impl OsmosisPool {
         token_out_denom: &str,
         config: &PoolConfig,
     ) -> Result<u128> {
-        let mut client = QueryClient::connect(config.grpc_url.clone().unwrap()).await?;
-
-        let pool_id = u64::from_str_radix(&self.id, 10)?;
-        let token_in_index = self.asset_for_denom(token_in_denom).unwrap();
-
-        let token_out_index = self.asset_for_denom(token_out_denom).unwrap();
-        let request = QuerySwapExactAmountInRequest {
-            sender: self.pool_address.clone(), // small hack because it uses SwapExactAmountIn just without writing new state so we need a address with enought liquidity, we assume the pool has that
-            pool_id: pool_id,
-            token_in: format!("{}{}", amount, self.pool_assets[token_in_index].token.denom),
-            routes: vec![SwapAmountInRoute {
-                pool_id: pool_id,
-                token_out_denom: self.pool_assets[token_out_index].token.denom.clone(),
-            }],
-        };
-        let response = client.estimate_swap_exact_amount_in(request).await?;
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+        estimate_swap_exact_in(
+            config,
+            &self.pool_address,
+            self.pool_id()?,
+            &token_in.token.denom,
+            &token_out.token.denom,
+            amount,
+        )
+        .await
+    }
+
+    async fn estimate_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<u128> {
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+        estimate_swap_exact_out(
+            config,
+            &self.pool_address,
+            self.pool_id()?,
+            &token_in.token.denom,
+            &token_out.token.denom,
+            amount,
+        )
+        .await
+    }
+}
+
+/// Asks the node for the output of swapping `amount` of `token_in` in a gamm pool of any type,
+/// the denoms are the on chain denoms.
+pub(crate) async fn estimate_swap_exact_in(
+    config: &PoolConfig,
+    pool_address: &str,
+    pool_id: u64,
+    token_in: &str,
+    token_out: &str,
+    amount: u128,
+) -> Result<u128> {
+    let request = QuerySwapExactAmountInRequest {
+        sender: pool_address.to_owned(), // small hack because it uses SwapExactAmountIn just without writing new state so we need a address with enought liquidity, we assume the pool has that
+        pool_id,
+        token_in: format!("{}{}", amount, token_in),
+        routes: vec![SwapAmountInRoute {
+            pool_id,
+            token_out_denom: token_out.to_owned(),
+        }],
+    };
+    let response = config
+        .grpc
+        .run(|url| {
+            let request = request.clone();
+            async move {
+                let mut client = QueryClient::new(config.grpc.connections().channel(&url)?);
+                Ok(client.estimate_swap_exact_amount_in(request).await?)
+            }
+        })
+        .await?;
+
+    Ok(response.into_inner().token_out_amount.parse::<u128>()?)
+}
+
+/// Asks the node for the input needed to receive exactly `amount` of `token_out`.
+pub(crate) async fn estimate_swap_exact_out(
+    config: &PoolConfig,
+    pool_address: &str,
+    pool_id: u64,
+    token_in: &str,
+    token_out: &str,
+    amount: u128,
+) -> Result<u128> {
+    let request = QuerySwapExactAmountOutRequest {
+        sender: pool_address.to_owned(), // same hack as in estimate_swap_exact_in
+        pool_id,
+        routes: vec![SwapAmountOutRoute {
+            pool_id,
+            token_in_denom: token_in.to_owned(),
+        }],
+        token_out: format!("{}{}", amount, token_out),
+    };
+    let response = config
+        .grpc
+        .run(|url| {
+            let request = request.clone();
+            async move {
+                let mut client = QueryClient::new(config.grpc.connections().channel(&url)?);
+                Ok(client.estimate_swap_exact_amount_out(request).await?)
+            }
+        })
+        .await?;
+
+    Ok(response.into_inner().token_in_amount.parse::<u128>()?)
+}
+
+// parameters only change with governance, the reserves with every swap
+pub(crate) async fn fetch_liquidity(config: &PoolConfig, pool_id: u64) -> Result<Vec<Coin>> {
+    let request = QueryTotalPoolLiquidityRequest { pool_id };
+    let response = config
+        .grpc
+        .run(|url| {
+            let request = request.clone();
+            async move {
+                let mut client = QueryClient::new(config.grpc.connections().channel(&url)?);
+                Ok(client.total_pool_liquidity(request).await?)
+            }
+        })
+        .await?;
+    Ok(response.into_inner().liquidity)
+}
 
-        Ok(u128::from_str_radix(
-            &response.into_inner().token_out_amount,
-            10,
-        )?)
+/// Pool of osmosis, every pool type is swapped with the same gamm messages.
+pub trait GammPool {
+    fn pool_id(&self) -> Result<u64>;
+    // on chain denom, e.g. the ibc denom for a native name
+    fn chain_denom(&self, denom: &str) -> Option<String>;
+}
+
+impl GammPool for OsmosisPool {
+    fn pool_id(&self) -> Result<u64> {
+        Ok(self.id.parse::<u64>()?)
+    }
+
+    fn chain_denom(&self, denom: &str) -> Option<String> {
+        self.asset_for_denom(denom)
+            .map(|x| self.pool_assets[x].token.denom.clone())
+    }
+}
+
+/// The gamm pool behind `pool`, None for pools of other dexes.
+pub fn as_gamm_pool(pool: &dyn Pool) -> Option<&dyn GammPool> {
+    let any = pool.as_any();
+    if let Some(x) = any.downcast_ref::<OsmosisPool>() {
+        Some(x)
+    } else if let Some(x) = any.downcast_ref::<StableswapPool>() {
+        Some(x)
+    } else if let Some(x) = any.downcast_ref::<ConcentratedPool>() {
+        Some(x)
+    } else {
+        None
     }
 }
 
@@ -132,38 +292,111 @@ impl Pool for OsmosisPool {
                 ),
                 pool_address: Some(self.pool_address.clone()),
                 error: None,
+                source: Some(config.quote_source()),
+                ..Default::default()
             })
         } else {
-            let token_in_index = self.asset_for_denom(token_in_denom).unwrap();
-            let token_out_index = self.asset_for_denom(token_out_denom).unwrap();
-
-            let token_in_amount =
-                u128::from_str_radix(&self.pool_assets[token_in_index].token.amount, 10)?;
-            let token_out_amount =
-                u128::from_str_radix(&self.pool_assets[token_out_index].token.amount, 10)?;
-            let token_in_weight =
-                u128::from_str_radix(&self.pool_assets[token_in_index].weight, 10)?;
-            let token_out_weight =
-                u128::from_str_radix(&self.pool_assets[token_out_index].weight, 10)?;
-            let token_in_decimals = 6;
-            let token_out_decimals = 6;
-
             Ok(Quote {
                 token_in: Some(amount),
-                token_out: Some(self.calculate_quote(
-                    amount,
-                    token_in_amount,
-                    token_out_amount,
-                    token_in_weight,
-                    token_out_weight,
-                    token_in_decimals,
-                    token_out_decimals,
-                )?),
+                token_out: Some(self.simulate_swap(amount, token_in_denom, token_out_denom)?),
                 pool_address: Some(self.pool_address.clone()),
                 error: None,
+                source: Some(config.quote_source()),
+                ..Default::default()
             })
         }
     }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let token_in = if config.estimate_quote {
+            self.estimate_quote_exact_out(amount, token_in_denom, token_out_denom, config)
+                .await?
+        } else {
+            self.simulate_swap_exact_out(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(token_in),
+            token_out: Some(amount),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn refresh(&self, config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        let liquidity = fetch_liquidity(config, self.pool_id()?).await?;
+        let mut pool = self.clone();
+        for asset in pool.pool_assets.iter_mut() {
+            let coin = liquidity
+                .iter()
+                .find(|x| x.denom == asset.token.denom)
+                .ok_or_else(|| eyre!("Pool {} has no {} liquidity", self.id, asset.token.denom))?;
+            asset.token.amount = coin.amount.clone();
+        }
+        Ok(Box::new(pool))
+    }
+
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote(amount, token_in_denom, token_out_denom)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote_exact_out(amount, token_in_denom, token_out_denom)
+    }
+
+    fn spot_price_after(
+        &self,
+        amount_in: u128,
+        amount_out: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Dec> {
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+        let (balance_in, balance_out) = reserves_after_swap(
+            token_in.token.amount.parse::<u128>()?,
+            token_out.token.amount.parse::<u128>()?,
+            amount_in,
+            amount_out,
+        )?;
+        weighted_spot_price(
+            balance_in,
+            token_in.weight.parse::<u128>()?,
+            balance_out,
+            token_out.weight.parse::<u128>()?,
+        )
+    }
+
+    fn fee(
+        &self,
+        amount_in: u128,
+        _amount_out: u128,
+        token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Fee> {
+        Fee::from_input(
+            self.pool_params.swap_fee.parse::<Dec>()?,
+            amount_in,
+            token_in_denom,
+        )
+    }
+
     fn token_denoms(&self) -> Vec<String> {
         let mut denoms: Vec<String> = self
             .pool_assets
@@ -173,9 +406,7 @@ impl Pool for OsmosisPool {
         let native_denoms: Vec<String> = self
             .pool_assets
             .iter()
-            .map(|x| x.token.native_name.clone())
-            .filter(|x| x.is_some())
-            .map(|x| x.unwrap())
+            .filter_map(|x| x.token.native_name.clone())
             .collect();
 
         denoms.extend(native_denoms);
@@ -183,12 +414,43 @@ impl Pool for OsmosisPool {
         denoms
     }
 
+    fn asset_denoms(&self) -> Vec<String> {
+        self.pool_assets
+            .iter()
+            .map(|x| x.token.denom.clone())
+            .collect()
+    }
+
+    fn token_pairs(&self) -> Vec<(String, String)> {
+        // ibc and native denom of an asset are the same token, so only pair different assets
+        let denoms: Vec<String> = self
+            .pool_assets
+            .iter()
+            .map(|x| x.token.native_name.clone().unwrap_or(x.token.denom.clone()))
+            .collect();
+        let mut pairs = vec![];
+        for (i, token_in) in denoms.iter().enumerate() {
+            for (j, token_out) in denoms.iter().enumerate() {
+                if i != j {
+                    pairs.push((token_in.clone(), token_out.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
     fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 
     fn chain(&self) -> String {
-        String::from("osmosis")
+        self.chain
+            .clone()
+            .unwrap_or_else(|| String::from("osmosis"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
     }
 
     fn address(&self) -> Result<String> {
@@ -199,9 +461,9 @@ impl Pool for OsmosisPool {
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct OsmosisPoolParams {
     #[serde(alias = "swapFee")]
-    swap_fee: String,
+    pub swap_fee: String,
     #[serde(alias = "exitFee")]
-    exit_fee: String,
+    pub exit_fee: String,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -217,40 +479,363 @@ pub struct OsmosisPoolAssets {
     pub weight: String,
 }
 
+pub const BALANCER_POOL_TYPE_URL: &str = "/osmosis.gamm.v1beta1.Pool";
+pub const STABLESWAP_POOL_TYPE_URL: &str = "/osmosis.gamm.poolmodels.stableswap.v1beta1.Pool";
+// pools per page when walking the pool list
+const PAGE_LIMIT: u64 = 100;
+
+/// Pool of the pool list which could not be loaded.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SkippedPool {
+    pub id: String,
+    pub type_url: String,
+    pub reason: String,
+}
+
+// sdk.Dec is encoded as integer with 18 decimals in protobuf, the REST api uses "0.002000000000000000"
+pub(crate) fn dec_from_proto(value: &str) -> String {
+    fixed_from_proto(value, 18)
+}
+
+// osmomath.BigDec, used for concentrated liquidity prices, has 36 decimals
+pub(crate) fn big_dec_from_proto(value: &str) -> String {
+    fixed_from_proto(value, 36)
+}
+
+fn fixed_from_proto(value: &str, decimals: usize) -> String {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(x) => ("-", x),
+        None => ("", value),
+    };
+    let digits = format!("{:0>width$}", value, width = decimals + 1);
+    let (int, frac) = digits.split_at(digits.len() - decimals);
+    format!("{}{}.{}", sign, int, frac)
+}
+
+pub(crate) fn coin_to_token(coin: Option<Coin>) -> OsmosisPoolToken {
+    let coin = coin.unwrap_or_default();
+    OsmosisPoolToken {
+        denom: coin.denom,
+        amount: coin.amount,
+        native_name: None,
+    }
+}
+
+impl From<BalancerPool> for OsmosisPool {
+    fn from(pool: BalancerPool) -> Self {
+        let params = pool.pool_params.unwrap_or_default();
+        OsmosisPool {
+            pool_address: pool.address,
+            id: pool.id.to_string(),
+            pool_params: OsmosisPoolParams {
+                swap_fee: dec_from_proto(&params.swap_fee),
+                exit_fee: dec_from_proto(&params.exit_fee),
+            },
+            future_pool_governor: pool.future_pool_governor,
+            total_shares: coin_to_token(pool.total_shares),
+            pool_assets: pool
+                .pool_assets
+                .into_iter()
+                .map(|x| OsmosisPoolAssets {
+                    token: coin_to_token(x.token),
+                    weight: x.weight,
+                })
+                .collect(),
+            total_weight: pool.total_weight,
+            chain: Some("osmosis".to_owned()),
+        }
+    }
+}
+
+/// Pool of the gamm pool list, typed by its type url.
+#[derive(Debug, Clone)]
+pub enum DecodedPool {
+    Balancer(OsmosisPool),
+    Stableswap(StableswapPool),
+    Concentrated(ConcentratedPool),
+}
+
+impl DecodedPool {
+    pub fn id(&self) -> &str {
+        match self {
+            DecodedPool::Balancer(x) => &x.id,
+            DecodedPool::Stableswap(x) => &x.id,
+            DecodedPool::Concentrated(x) => &x.id,
+        }
+    }
+
+    pub fn into_pool(self) -> Box<dyn Pool> {
+        match self {
+            DecodedPool::Balancer(x) => Box::new(x),
+            DecodedPool::Stableswap(x) => Box::new(x),
+            DecodedPool::Concentrated(x) => Box::new(x),
+        }
+    }
+}
+
+/// Decodes a pool of the gamm `pools` or `pool` query, fails for pool types tracy cannot quote.
+pub fn decode_pool(any: &Any) -> Result<DecodedPool> {
+    let value = any.value.as_slice();
+    match any.type_url.as_str() {
+        BALANCER_POOL_TYPE_URL => Ok(DecodedPool::Balancer(BalancerPool::decode(value)?.into())),
+        STABLESWAP_POOL_TYPE_URL => Ok(DecodedPool::Stableswap(
+            StableswapProtoPool::decode(value)?.into(),
+        )),
+        CONCENTRATED_POOL_TYPE_URL => Ok(DecodedPool::Concentrated(
+            ConcentratedProtoPool::decode(value)?.into(),
+        )),
+        x => Err(eyre!("Unsupported pool type {}", x)),
+    }
+}
+
+// only the id of a pool, tracy cannot decode every pool type. The id is field 2 of the
+// gamm and cosmwasm pools and field 4 of the concentrated ones.
+#[derive(Clone, PartialEq, Message)]
+struct PoolId {
+    #[prost(uint64, tag = "2")]
+    id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ConcentratedPoolId {
+    #[prost(uint64, tag = "4")]
+    id: u64,
+}
+
+/// Id of an encoded pool of any type, `None` if it has none.
+pub fn pool_id_of_any(any: &Any) -> Option<u64> {
+    let value = any.value.as_slice();
+    let id = match any.type_url.as_str() {
+        CONCENTRATED_POOL_TYPE_URL => ConcentratedPoolId::decode(value).ok()?.id,
+        _ => PoolId::decode(value).ok()?.id,
+    };
+    Some(id).filter(|x| *x != 0)
+}
+
+// same as `decode_pool` for the json of the REST api, the type url is in `@type`
+pub(crate) fn decode_pool_json(type_url: &str, pool: serde_json::Value) -> Result<DecodedPool> {
+    match type_url {
+        BALANCER_POOL_TYPE_URL => Ok(DecodedPool::Balancer(serde_json::from_value(pool)?)),
+        STABLESWAP_POOL_TYPE_URL => Ok(DecodedPool::Stableswap(serde_json::from_value(pool)?)),
+        CONCENTRATED_POOL_TYPE_URL => Ok(DecodedPool::Concentrated(serde_json::from_value(pool)?)),
+        x => Err(eyre!("Unsupported pool type {}", x)),
+    }
+}
+
+// walks all pages of the gamm `pools` query, or of the concentrated liquidity one
+async fn fetch_pool_list_grpc(
+    grpc: &EndpointPool,
+    concentrated: bool,
+) -> Result<(Vec<DecodedPool>, Vec<SkippedPool>)> {
+    let mut pools = vec![];
+    let mut skipped = vec![];
+    let mut key: Vec<u8> = vec![];
+    loop {
+        let pagination = Some(PageRequest {
+            key: key.clone(),
+            limit: PAGE_LIMIT,
+            ..Default::default()
+        });
+        let (page, pagination) = grpc
+            .run(|url| {
+                let pagination = pagination.clone();
+                async move {
+                    let channel = grpc.connections().channel(&url)?;
+                    if concentrated {
+                        let mut client = ConcentratedClient::new(channel);
+                        let response = client.pools(PoolsRequest { pagination }).await?;
+                        let response = response.into_inner();
+                        Ok((response.pools, response.pagination))
+                    } else {
+                        let mut client = QueryClient::new(channel);
+                        let response = client.pools(QueryPoolsRequest { pagination }).await?;
+                        let response = response.into_inner();
+                        Ok((response.pools, response.pagination))
+                    }
+                }
+            })
+            .await?;
+        for any in &page {
+            match decode_pool(any) {
+                Ok(x) => pools.push(x),
+                Err(e) => skipped.push(SkippedPool {
+                    id: pool_id_of_any(any)
+                        .map(|x| x.to_string())
+                        .unwrap_or_default(),
+                    type_url: any.type_url.clone(),
+                    reason: e.to_string(),
+                }),
+            }
+        }
+        let next_key = pagination.unwrap_or_default().next_key;
+        if next_key.is_empty() {
+            break;
+        }
+        if next_key == key || page.is_empty() {
+            return Err(eyre!("Pool list pagination did not advance"));
+        }
+        key = next_key;
+    }
+    Ok((pools, skipped))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RestPagination {
+    next_key: Option<String>,
+}
+
 #[derive(Debug, serde::Deserialize)]
-struct OsmosisPoolsFetchResult {
-    pools: Vec<OsmosisPool>,
+struct RestPoolsPage {
+    pools: Vec<serde_json::Value>,
+    pagination: Option<RestPagination>,
 }
 
-pub async fn fetch_osmosis_pools(lcd_api: &str) -> Result<()> {
-    // TODO: currently only ~800 pools, may need to use pagination
-    let resp: OsmosisPoolsFetchResult = reqwest::get(format!(
-        "{}/osmosis/gamm/v1beta1/pools?pagination.limit=1000",
-        lcd_api
-    ))
-    .await?
-    .json()
-    .await?;
+// same as `fetch_pool_list_grpc` over the LCD api
+async fn fetch_pool_list_rest(
+    rest: &EndpointPool,
+    concentrated: bool,
+) -> Result<(Vec<DecodedPool>, Vec<SkippedPool>)> {
+    let path = if concentrated {
+        "osmosis/concentratedliquidity/v1beta1/pools"
+    } else {
+        "osmosis/gamm/v1beta1/pools"
+    };
+    let mut pools = vec![];
+    let mut skipped = vec![];
+    let mut key: Option<String> = None;
+    loop {
+        let page: RestPoolsPage = rest
+            .run(|url| {
+                let key = key.clone();
+                async move {
+                    let url = format!("{}/{}", url, path);
+                    let mut query = vec![("pagination.limit".to_owned(), PAGE_LIMIT.to_string())];
+                    if let Some(key) = key {
+                        query.push(("pagination.key".to_owned(), key));
+                    }
+                    let http = rest.connections().http();
+                    Ok(http.get(url).query(&query).send().await?.json().await?)
+                }
+            })
+            .await?;
+        let page_size = page.pools.len();
+        for pool in page.pools {
+            let type_url = pool["@type"].as_str().unwrap_or_default().to_owned();
+            let id = pool["id"].as_str().unwrap_or_default().to_owned();
+            match decode_pool_json(&type_url, pool) {
+                Ok(x) => pools.push(x),
+                Err(e) => skipped.push(SkippedPool {
+                    id,
+                    type_url,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+        let next_key = page
+            .pagination
+            .and_then(|x| x.next_key)
+            .filter(|x| !x.is_empty());
+        match next_key {
+            None => break,
+            Some(x) if Some(&x) == key.as_ref() || page_size == 0 => {
+                return Err(eyre!("Pool list pagination did not advance"))
+            }
+            Some(x) => key = Some(x),
+        }
+    }
+    Ok((pools, skipped))
+}
+
+// pools are quoted by the base denom of their assets, e.g. "uatom" for an ibc denom of ATOM
+async fn native_denom(
+    registry: &mut AssetRegistry,
+    api: &EndpointPool,
+    chain: &str,
+    denom: &str,
+) -> Result<Option<String>> {
+    Ok(Some(registry.resolve(api, chain, denom).await?.base_denom))
+}
+
+/// Pools of the gamm pool list by type.
+#[derive(Debug, Clone, Default)]
+pub struct OsmosisPools {
+    pub balancer: Vec<OsmosisPool>,
+    pub stableswap: Vec<StableswapPool>,
+    pub concentrated: Vec<ConcentratedPool>,
+    // pools of types tracy cannot quote
+    pub skipped: Vec<SkippedPool>,
+}
+
+/// Loads every pool page by page, over gRPC if the config has gRPC urls and over REST otherwise.
+///
+/// The denoms of the pools are added to `registry`, IBC denoms are resolved over REST, and the
+/// tick liquidity of concentrated liquidity pools is loaded as well. Pools of unsupported types
+/// are returned with the reason instead of failing the whole load.
+pub async fn fetch_osmosis_pool_list(
+    config: &PoolConfig,
+    registry: &mut AssetRegistry,
+    chain: &str,
+) -> Result<OsmosisPools> {
+    let mut decoded = vec![];
+    let mut skipped = vec![];
+    // gamm and concentrated liquidity pools are listed by their own modules
+    for concentrated in [false, true] {
+        let list = if config.grpc.is_empty() {
+            fetch_pool_list_rest(&config.rest, concentrated).await
+        } else {
+            fetch_pool_list_grpc(&config.grpc, concentrated).await
+        };
+        match list {
+            Ok((pools, skipped_pools)) => {
+                decoded.extend(pools);
+                skipped.extend(skipped_pools);
+            }
+            // nodes without the concentrated liquidity module still have the gamm pools
+            Err(e) if concentrated => skipped.push(SkippedPool {
+                id: "".to_owned(),
+                type_url: CONCENTRATED_POOL_TYPE_URL.to_owned(),
+                reason: format!("Could not list concentrated liquidity pools: {:#}", e),
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+    let lcd_api = &config.rest;
 
-    let pools_raw = resp.pools;
     // TODO: can we not copy here?
     let mut pools: Vec<OsmosisPool> = vec![];
-    let mut ibc_cache = load_denom_trace_cache_from_file(Path::new("denom_traces.json"))?;
-
+    let mut stableswap_pools: Vec<StableswapPool> = vec![];
+    let mut concentrated_pools: Vec<ConcentratedPool> = vec![];
     // #TODO: this loop is parallelizable ~~but that makes no sense at this time because the api server would rate limit us~~
-    // nevermind, this is already pretty fast using the cache
-    for pool in pools_raw {
+    // nevermind, this is already pretty fast using the registry
+    for pool in decoded {
+        let pool = match pool {
+            DecodedPool::Balancer(x) => x,
+            DecodedPool::Stableswap(mut x) => {
+                for token in x.pool_liquidity.iter_mut() {
+                    token.native_name =
+                        native_denom(registry, lcd_api, chain, &token.denom).await?;
+                }
+                x.chain = Some(chain.to_owned());
+                stableswap_pools.push(x);
+                continue;
+            }
+            DecodedPool::Concentrated(mut x) => {
+                x.token0_native_name = native_denom(registry, lcd_api, chain, &x.token0).await?;
+                x.token1_native_name = native_denom(registry, lcd_api, chain, &x.token1).await?;
+                x.chain = Some(chain.to_owned());
+                concentrated_pools.push(x);
+                continue;
+            }
+        };
         // TODO: this should probably be mapable
         let mut assets: Vec<OsmosisPoolAssets> = vec![];
         for asset in pool.pool_assets {
-            let (native_denom, cache) =
-                resolve_ibc(ibc_cache, lcd_api, asset.token.denom.clone(), true).await?;
-            ibc_cache = cache;
+            let native_name = native_denom(registry, lcd_api, chain, &asset.token.denom).await?;
             assets.push(OsmosisPoolAssets {
                 token: OsmosisPoolToken {
                     denom: asset.token.denom,
                     amount: asset.token.amount,
-                    native_name: native_denom,
+                    native_name,
                 },
                 weight: asset.weight,
             })
@@ -264,28 +849,192 @@ pub async fn fetch_osmosis_pools(lcd_api: &str) -> Result<()> {
             total_shares: pool.total_shares,
             pool_assets: assets,
             total_weight: pool.total_weight,
-            chain: Some("osmosis".to_owned()),
+            chain: Some(chain.to_owned()),
         })
     }
 
-    let save_result = save_denom_trace_cache_to_file(Path::new("denom_traces.json"), ibc_cache);
-    if let Err(x) = save_result {
-        println!("could not save trace cache file error: {}", x);
+    // one query per pool, the connection manager limits how many run at once
+    let ranges = join_all(
+        concentrated_pools
+            .iter()
+            .map(|x| async move { fetch_tick_ranges(config, x.pool_id()?).await }),
+    )
+    .await;
+    let mut concentrated = vec![];
+    for (mut pool, ranges) in concentrated_pools.into_iter().zip(ranges) {
+        match ranges {
+            Ok(x) => {
+                pool.liquidity = x;
+                concentrated.push(pool);
+            }
+            Err(e) => skipped.push(SkippedPool {
+                id: pool.id,
+                type_url: CONCENTRATED_POOL_TYPE_URL.to_owned(),
+                reason: format!("Could not load tick liquidity: {:#}", e),
+            }),
+        }
     }
-    let text = serde_json::to_string(&pools)?;
-    let path = Path::new("osmosis_pools_hackathon.json");
-    let mut file = File::create(path)?;
-    file.write(text.as_bytes())?;
 
-    Ok(())
+    Ok(OsmosisPools {
+        balancer: pools,
+        stableswap: stableswap_pools,
+        concentrated,
+        skipped,
+    })
+}
+
+impl OsmosisPools {
+    /// Writes the pools of the type of `adapter` to its snapshot, cosmwasm adapters are ignored.
+    pub fn save(&self, adapter: &AdapterConfig) -> Result<()> {
+        let path = Path::new(&adapter.snapshot);
+        match adapter.adapter {
+            PoolAdapter::Osmosis => save_osmo_pools_to_file(path, &self.balancer),
+            PoolAdapter::Stableswap => save_stableswap_pools_to_file(path, &self.stableswap),
+            PoolAdapter::Concentrated => save_concentrated_pools_to_file(path, &self.concentrated),
+            PoolAdapter::Wasmswap | PoolAdapter::Terraswap => Ok(()),
+        }
+    }
+}
+
+/// Loads every pool of `chain` like [`fetch_osmosis_pool_list`] and writes every pool type to
+/// the snapshot of its adapter in `adapters`.
+pub async fn fetch_osmosis_pools(
+    config: &PoolConfig,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    adapters: &[AdapterConfig],
+) -> Result<Vec<SkippedPool>> {
+    let pools = fetch_osmosis_pool_list(config, registry, chain).await?;
+    for adapter in adapters {
+        pools.save(adapter)?;
+    }
+    Ok(pools.skipped)
+}
+
+pub fn save_osmo_pools_to_file(path: &Path, pools: &[OsmosisPool]) -> Result<()> {
+    save_pools_to_file(path, pools)
 }
 
 // TODO: move fetch + load to trait
 pub fn load_osmo_pools_from_file_boxed(path: &Path) -> Result<Vec<Box<OsmosisPool>>> {
-    let mut file = File::open(path)?;
+    load_pools_from_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::concentrated_pool::load_concentrated_pools_from_file_boxed;
+    use crate::pools::stableswap_pool::load_stableswap_pools_from_file_boxed;
+
+    #[test]
+    fn id_of_undecoded_pools() {
+        // a cosmwasm pool has the contract address in field 1 and the id in field 2 too
+        let cosmwasm = Any {
+            type_url: "/osmosis.cosmwasmpool.v1beta1.CosmWasmPool".to_owned(),
+            value: BalancerPool {
+                address: "osmo1contract".to_owned(),
+                id: 1212,
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        };
+        assert!(decode_pool(&cosmwasm).is_err());
+        assert_eq!(pool_id_of_any(&cosmwasm), Some(1212));
+
+        let concentrated = Any {
+            type_url: CONCENTRATED_POOL_TYPE_URL.to_owned(),
+            value: ConcentratedProtoPool {
+                address: "osmo1pool".to_owned(),
+                id: 1066,
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        };
+        assert_eq!(pool_id_of_any(&concentrated), Some(1066));
 
-    let mut text: String = "".to_string();
-    file.read_to_string(&mut text)?;
-    let pools: Vec<Box<OsmosisPool>> = serde_json::from_str(&text)?;
-    Ok(pools)
+        let broken = Any {
+            type_url: BALANCER_POOL_TYPE_URL.to_owned(),
+            value: vec![0x10],
+        };
+        assert!(decode_pool(&broken).is_err());
+        assert_eq!(pool_id_of_any(&broken), None);
+    }
+
+    #[test]
+    fn every_pool_type_is_saved_to_its_snapshot() {
+        let fixture = format!(
+            "{}/tests/fixtures/osmosis_pools.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let balancer: Vec<OsmosisPool> = load_osmo_pools_from_file_boxed(Path::new(&fixture))
+            .unwrap()
+            .into_iter()
+            .map(|x| *x)
+            .collect();
+        let stableswap: StableswapPool = serde_json::from_value(serde_json::json!({
+            "pool_address": "osmo1stable",
+            "id": "1088",
+            "pool_params": { "swap_fee": "0.001", "exit_fee": "0" },
+            "future_pool_governor": "",
+            "total_shares": { "denom": "gamm/pool/1088", "amount": "1", "native_name": null },
+            "pool_liquidity": [],
+            "scaling_factors": [],
+            "scaling_factor_controller": "",
+            "chain": "osmosis",
+        }))
+        .unwrap();
+        let concentrated: ConcentratedPool = serde_json::from_value(serde_json::json!({
+            "pool_address": "osmo1concentrated",
+            "id": "1066",
+            "token0": "uosmo",
+            "token1": "uion",
+            "current_tick_liquidity": "0",
+            "current_sqrt_price": "1",
+            "current_tick": "0",
+            "tick_spacing": "100",
+            "exponent_at_price_one": "-6",
+            "spread_factor": "0.002",
+            "chain": "osmosis",
+        }))
+        .unwrap();
+        let pools = OsmosisPools {
+            balancer: balancer.clone(),
+            stableswap: vec![stableswap],
+            concentrated: vec![concentrated],
+            skipped: vec![],
+        };
+
+        let dir = std::env::temp_dir().join(format!("tracy-osmosis-save-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let adapter = |adapter: PoolAdapter, name: &str| AdapterConfig {
+            adapter,
+            snapshot: dir.join(name).to_string_lossy().into_owned(),
+            factories: vec![],
+            code_ids: vec![],
+        };
+        for config in [
+            adapter(PoolAdapter::Osmosis, "balancer.json"),
+            adapter(PoolAdapter::Stableswap, "stableswap.json"),
+            adapter(PoolAdapter::Concentrated, "concentrated.json"),
+            adapter(PoolAdapter::Wasmswap, "wasmswap.json"),
+        ] {
+            pools.save(&config).unwrap();
+        }
+
+        let saved = load_osmo_pools_from_file_boxed(&dir.join("balancer.json")).unwrap();
+        assert_eq!(
+            saved.iter().map(|x| x.id.clone()).collect::<Vec<_>>(),
+            balancer.iter().map(|x| x.id.clone()).collect::<Vec<_>>()
+        );
+        let saved = load_stableswap_pools_from_file_boxed(&dir.join("stableswap.json")).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id, "1088");
+        let saved =
+            load_concentrated_pools_from_file_boxed(&dir.join("concentrated.json")).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id, "1066");
+        // cosmwasm snapshots are written by their own loaders
+        assert!(!dir.join("wasmswap.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
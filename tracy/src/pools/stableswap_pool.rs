@@ -0,0 +1,339 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+
+use super::math::{
+    reserves_after_swap, stableswap_in_given_out, stableswap_out_given_in, stableswap_spot_price,
+};
+use super::osmosis_pool::{
+    coin_to_token, dec_from_proto, estimate_swap_exact_in, estimate_swap_exact_out,
+    fetch_liquidity, GammPool, OsmosisPoolParams, OsmosisPoolToken,
+};
+use super::{load_pools_from_file, save_pools_to_file};
+use crate::decimal::Dec;
+use crate::util::proto::osmosis_gamm_poolmodels_stableswap_v1beta1::Pool as StableswapProtoPool;
+use crate::{Fee, Pool, PoolConfig, Quote};
+
+/// Osmosis stableswap pool, made for assets which trade close to 1:1 like stablecoins
+/// or liquid staking tokens.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct StableswapPool {
+    #[serde(alias = "address")]
+    pub pool_address: String,
+    pub id: String,
+    #[serde(alias = "poolParams")]
+    pub pool_params: OsmosisPoolParams,
+    pub future_pool_governor: String,
+    #[serde(alias = "totalShares")]
+    pub total_shares: OsmosisPoolToken,
+    #[serde(alias = "poolLiquidity")]
+    pub pool_liquidity: Vec<OsmosisPoolToken>,
+    // reserves are divided by these before the swap math, uint64 is a string in the REST api
+    #[serde(alias = "scalingFactors")]
+    pub scaling_factors: Vec<String>,
+    #[serde(alias = "scalingFactorController")]
+    pub scaling_factor_controller: String,
+    pub chain: Option<String>,
+}
+
+impl From<StableswapProtoPool> for StableswapPool {
+    fn from(pool: StableswapProtoPool) -> Self {
+        let params = pool.pool_params.unwrap_or_default();
+        StableswapPool {
+            pool_address: pool.address,
+            id: pool.id.to_string(),
+            pool_params: OsmosisPoolParams {
+                swap_fee: dec_from_proto(&params.swap_fee),
+                exit_fee: dec_from_proto(&params.exit_fee),
+            },
+            future_pool_governor: pool.future_pool_governor,
+            total_shares: coin_to_token(pool.total_shares),
+            pool_liquidity: pool
+                .pool_liquidity
+                .into_iter()
+                .map(|x| coin_to_token(Some(x)))
+                .collect(),
+            scaling_factors: pool.scaling_factors.iter().map(|x| x.to_string()).collect(),
+            scaling_factor_controller: pool.scaling_factor_controller,
+            chain: Some("osmosis".to_owned()),
+        }
+    }
+}
+
+impl StableswapPool {
+    // takes ibc or native denom
+    fn asset_for_denom(&self, denom: &str) -> Result<usize> {
+        self.pool_liquidity
+            .iter()
+            .position(|x| x.denom == denom || x.native_name.as_deref() == Some(denom))
+            .ok_or_else(|| eyre!("Pool does not contain {}", denom))
+    }
+
+    // reserves and scaling factors in the order of the pool assets
+    fn reserves(&self) -> Result<(Vec<u128>, Vec<u64>)> {
+        let reserves = self
+            .pool_liquidity
+            .iter()
+            .map(|x| x.amount.parse::<u128>())
+            .collect::<Result<Vec<u128>, _>>()?;
+        let scaling_factors = self
+            .scaling_factors
+            .iter()
+            .map(|x| x.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()?;
+        Ok((reserves, scaling_factors))
+    }
+
+    fn calculate_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserves, scaling_factors) = self.reserves()?;
+        stableswap_out_given_in(
+            &reserves,
+            &scaling_factors,
+            self.asset_for_denom(token_in_denom)?,
+            self.asset_for_denom(token_out_denom)?,
+            amount,
+            self.pool_params.swap_fee.parse::<Dec>()?,
+        )
+    }
+
+    fn calculate_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserves, scaling_factors) = self.reserves()?;
+        stableswap_in_given_out(
+            &reserves,
+            &scaling_factors,
+            self.asset_for_denom(token_in_denom)?,
+            self.asset_for_denom(token_out_denom)?,
+            amount,
+            self.pool_params.swap_fee.parse::<Dec>()?,
+        )
+    }
+
+    fn chain_denoms(
+        &self,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<(String, String)> {
+        let denom = |x: &str| -> Result<String> {
+            Ok(self.pool_liquidity[self.asset_for_denom(x)?].denom.clone())
+        };
+        Ok((denom(token_in_denom)?, denom(token_out_denom)?))
+    }
+}
+
+impl GammPool for StableswapPool {
+    fn pool_id(&self) -> Result<u64> {
+        Ok(self.id.parse::<u64>()?)
+    }
+
+    fn chain_denom(&self, denom: &str) -> Option<String> {
+        self.asset_for_denom(denom)
+            .ok()
+            .map(|x| self.pool_liquidity[x].denom.clone())
+    }
+}
+
+#[async_trait]
+impl Pool for StableswapPool {
+    async fn get_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let token_out = if config.estimate_quote {
+            let (token_in, token_out) = self.chain_denoms(token_in_denom, token_out_denom)?;
+            estimate_swap_exact_in(
+                config,
+                &self.pool_address,
+                self.pool_id()?,
+                &token_in,
+                &token_out,
+                amount,
+            )
+            .await?
+        } else {
+            self.calculate_quote(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount),
+            token_out: Some(token_out),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let token_in = if config.estimate_quote {
+            let (token_in, token_out) = self.chain_denoms(token_in_denom, token_out_denom)?;
+            estimate_swap_exact_out(
+                config,
+                &self.pool_address,
+                self.pool_id()?,
+                &token_in,
+                &token_out,
+                amount,
+            )
+            .await?
+        } else {
+            self.calculate_quote_exact_out(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(token_in),
+            token_out: Some(amount),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn refresh(&self, config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        let liquidity = fetch_liquidity(config, self.pool_id()?).await?;
+        let mut pool = self.clone();
+        for asset in pool.pool_liquidity.iter_mut() {
+            let coin = liquidity
+                .iter()
+                .find(|x| x.denom == asset.denom)
+                .ok_or_else(|| eyre!("Pool {} has no {} liquidity", self.id, asset.denom))?;
+            asset.amount = coin.amount.clone();
+        }
+        Ok(Box::new(pool))
+    }
+
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote(amount, token_in_denom, token_out_denom)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        self.calculate_quote_exact_out(amount, token_in_denom, token_out_denom)
+    }
+
+    fn spot_price_after(
+        &self,
+        amount_in: u128,
+        amount_out: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Dec> {
+        let (mut reserves, scaling_factors) = self.reserves()?;
+        let token_in = self.asset_for_denom(token_in_denom)?;
+        let token_out = self.asset_for_denom(token_out_denom)?;
+        (reserves[token_in], reserves[token_out]) = reserves_after_swap(
+            reserves[token_in],
+            reserves[token_out],
+            amount_in,
+            amount_out,
+        )?;
+        stableswap_spot_price(&reserves, &scaling_factors, token_in, token_out)
+    }
+
+    fn fee(
+        &self,
+        amount_in: u128,
+        _amount_out: u128,
+        token_in_denom: &str,
+        _token_out_denom: &str,
+    ) -> Result<Fee> {
+        Fee::from_input(
+            self.pool_params.swap_fee.parse::<Dec>()?,
+            amount_in,
+            token_in_denom,
+        )
+    }
+
+    fn token_denoms(&self) -> Vec<String> {
+        let mut denoms: Vec<String> = self
+            .pool_liquidity
+            .iter()
+            .map(|x| x.denom.clone())
+            .collect();
+        denoms.extend(
+            self.pool_liquidity
+                .iter()
+                .filter_map(|x| x.native_name.clone()),
+        );
+        denoms
+    }
+
+    fn asset_denoms(&self) -> Vec<String> {
+        self.pool_liquidity
+            .iter()
+            .map(|x| x.denom.clone())
+            .collect()
+    }
+
+    fn token_pairs(&self) -> Vec<(String, String)> {
+        // ibc and native denom of an asset are the same token, so only pair different assets
+        let denoms: Vec<String> = self
+            .pool_liquidity
+            .iter()
+            .map(|x| x.native_name.clone().unwrap_or(x.denom.clone()))
+            .collect();
+        let mut pairs = vec![];
+        for (i, token_in) in denoms.iter().enumerate() {
+            for (j, token_out) in denoms.iter().enumerate() {
+                if i != j {
+                    pairs.push((token_in.clone(), token_out.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    fn chain(&self) -> String {
+        self.chain
+            .clone()
+            .unwrap_or_else(|| String::from("osmosis"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn address(&self) -> Result<String> {
+        Ok(self.pool_address.clone())
+    }
+}
+
+/// Writes a stableswap snapshot, see [`crate::pools::osmosis_pool::fetch_osmosis_pool_list`].
+pub fn save_stableswap_pools_to_file(path: &Path, pools: &[StableswapPool]) -> Result<()> {
+    save_pools_to_file(path, pools)
+}
+
+pub fn load_stableswap_pools_from_file_boxed(path: &Path) -> Result<Vec<Box<StableswapPool>>> {
+    load_pools_from_file(path)
+}
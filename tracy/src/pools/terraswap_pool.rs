@@ -0,0 +1,520 @@
+use std::path::Path;
+use std::str::from_utf8;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::juno_pool::{extract_token, query_contract, JunoDenom, JunoToken};
+use super::math::{
+    constant_product_commission_in_given_out, constant_product_commission_out_given_in,
+    constant_product_spot_price, reserves_after_swap, TERRASWAP_COMMISSION_RATE,
+};
+use super::{load_pools_from_file, save_pools_to_file};
+use crate::assets::AssetRegistry;
+use crate::decimal::Dec;
+use crate::util::endpoint::EndpointPool;
+use crate::{Fee, Pool, PoolConfig, Quote};
+
+// page size of the factory `pairs` query, the contracts cap it at 30
+const PAIRS_PAGE_LIMIT: u32 = 30;
+
+/// Token of a Terraswap style pair, either a cw20 contract or a native denom.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: String },
+    NativeToken { denom: String },
+}
+
+impl AssetInfo {
+    /// On-chain denom of the token, `cw20:<address>` for cw20 tokens.
+    pub fn chain_denom(&self) -> String {
+        match self {
+            AssetInfo::Token { contract_addr } => format!("cw20:{}", contract_addr),
+            AssetInfo::NativeToken { denom } => denom.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TerraswapAsset {
+    pub info: AssetInfo,
+    pub amount: String,
+}
+
+/// Answer of the `pair` query of a pair and of the `pairs` query of a factory.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PairInfo {
+    pub asset_infos: Vec<AssetInfo>,
+    pub contract_addr: String,
+    pub liquidity_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PairsResponse {
+    pub pairs: Vec<PairInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolResponse {
+    pub assets: Vec<TerraswapAsset>,
+    pub total_share: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimulationResponse {
+    pub return_amount: String,
+    pub spread_amount: String,
+    pub commission_amount: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReverseSimulationResponse {
+    pub offer_amount: String,
+    pub spread_amount: String,
+    pub commission_amount: String,
+}
+
+/// Constant product pair of a Terraswap style DEX (Terraswap, Astroport, Loop, White Whale),
+/// queried through the `pair`, `pool`, `simulation` and `reverse_simulation` messages.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TerraswapPool {
+    pub pool_address: String,
+    pub lp_token_address: String,
+    pub total_share: String,
+    pub assets: Vec<TerraswapAsset>,
+    // token of every asset in the same order, quotes take the symbol or the on-chain denom
+    pub tokens: Vec<JunoToken>,
+    // taken from the output. The pairs have no query for it, so fetched pairs get the
+    // Terraswap rate, snapshots of forks with another rate can set their own
+    #[serde(default = "default_commission_rate")]
+    pub commission_rate: String,
+    pub chain: Option<String>,
+}
+
+fn default_commission_rate() -> String {
+    TERRASWAP_COMMISSION_RATE.to_owned()
+}
+
+async fn query_smart<T: DeserializeOwned>(
+    api: &EndpointPool,
+    contract_address: &str,
+    msg: &serde_json::Value,
+) -> Result<T> {
+    let msg = base64::encode(msg.to_string());
+    let res = query_contract(api, contract_address, msg.as_str()).await?;
+    let decoded = base64::decode_config(res, base64::STANDARD)?;
+    Ok(serde_json::from_str::<T>(from_utf8(&decoded)?)?)
+}
+
+pub async fn get_pair_info(api: &EndpointPool, contract_address: &str) -> Result<PairInfo> {
+    query_smart(api, contract_address, &serde_json::json!({ "pair": {} })).await
+}
+
+pub async fn get_pair_pool(api: &EndpointPool, contract_address: &str) -> Result<PoolResponse> {
+    query_smart(api, contract_address, &serde_json::json!({ "pool": {} })).await
+}
+
+pub async fn simulation(
+    api: &EndpointPool,
+    contract_address: &str,
+    offer_asset: &TerraswapAsset,
+) -> Result<SimulationResponse> {
+    let msg = serde_json::json!({ "simulation": { "offer_asset": offer_asset } });
+    query_smart(api, contract_address, &msg).await
+}
+
+pub async fn reverse_simulation(
+    api: &EndpointPool,
+    contract_address: &str,
+    ask_asset: &TerraswapAsset,
+) -> Result<ReverseSimulationResponse> {
+    let msg = serde_json::json!({ "reverse_simulation": { "ask_asset": ask_asset } });
+    query_smart(api, contract_address, &msg).await
+}
+
+/// Every pair of a Terraswap style factory, the `pairs` query is paged by the asset infos
+/// of the last pair.
+pub async fn get_factory_pairs(api: &EndpointPool, factory: &str) -> Result<Vec<PairInfo>> {
+    let mut pairs: Vec<PairInfo> = vec![];
+    loop {
+        let msg = match pairs.last() {
+            Some(last) => serde_json::json!({
+                "pairs": { "start_after": last.asset_infos, "limit": PAIRS_PAGE_LIMIT }
+            }),
+            None => serde_json::json!({ "pairs": { "limit": PAIRS_PAGE_LIMIT } }),
+        };
+        let page: PairsResponse = query_smart(api, factory, &msg).await?;
+        let done = page.pairs.len() < PAIRS_PAGE_LIMIT as usize;
+        pairs.extend(page.pairs);
+        if done {
+            return Ok(pairs);
+        }
+    }
+}
+
+async fn asset_token(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    info: &AssetInfo,
+) -> Result<JunoToken> {
+    let denom = match info {
+        AssetInfo::Token { contract_addr } => JunoDenom {
+            native: None,
+            cw20: Some(contract_addr.clone()),
+        },
+        AssetInfo::NativeToken { denom } => JunoDenom {
+            native: Some(denom.clone()),
+            cw20: None,
+        },
+    };
+    extract_token(api, registry, chain, &denom).await
+}
+
+/// Pair with its reserves and tokens, `None` if a reserve is empty.
+pub async fn fetch_terraswap_pool(
+    api: &EndpointPool,
+    registry: &mut AssetRegistry,
+    chain: &str,
+    contract_address: &str,
+) -> Result<Option<TerraswapPool>> {
+    let pair = get_pair_info(api, contract_address).await?;
+    let pool = get_pair_pool(api, contract_address).await?;
+    if pool.assets.iter().any(|x| x.amount == "0") {
+        return Ok(None); // Empty pool, probably invalid
+    }
+    let mut tokens = vec![];
+    for asset in &pool.assets {
+        tokens.push(asset_token(api, registry, chain, &asset.info).await?);
+    }
+    Ok(Some(TerraswapPool {
+        pool_address: contract_address.to_owned(),
+        lp_token_address: pair.liquidity_token,
+        total_share: pool.total_share,
+        assets: pool.assets,
+        tokens,
+        commission_rate: default_commission_rate(),
+        chain: Some(chain.to_owned()),
+    }))
+}
+
+/// Loads the given pair contracts and writes them to `path`, empty pairs are skipped.
+/// See [`crate::pools::discovery::discover_contracts`] to find the contracts.
+pub async fn fetch_terraswap_pools(
+    api: &EndpointPool,
+    contracts: &[String],
+    path: &Path,
+    registry: &mut AssetRegistry,
+    chain: &str,
+) -> Result<Vec<TerraswapPool>> {
+    let mut res = vec![];
+    for contract in contracts {
+        if let Some(pool) = fetch_terraswap_pool(api, registry, chain, contract).await? {
+            res.push(pool);
+        }
+    }
+    save_terraswap_pools_to_file(path, &res)?;
+    Ok(res)
+}
+
+pub fn save_terraswap_pools_to_file(path: &Path, pools: &[TerraswapPool]) -> Result<()> {
+    save_pools_to_file(path, pools)
+}
+
+pub fn load_terraswap_pools_from_file(path: &Path) -> Result<Vec<Box<TerraswapPool>>> {
+    load_pools_from_file(path)
+}
+
+impl TerraswapPool {
+    // takes the symbol of the token or its on-chain denom
+    fn asset_for_denom(&self, denom: &str) -> Option<usize> {
+        self.tokens
+            .iter()
+            .position(|x| x.symbol.as_deref() == Some(denom))
+            .or_else(|| {
+                self.assets
+                    .iter()
+                    .position(|x| x.info.chain_denom() == denom)
+            })
+    }
+
+    // indices of the (token_in, token_out) assets
+    fn assets_for_pair(
+        &self,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<(usize, usize)> {
+        match (
+            self.asset_for_denom(token_in_denom),
+            self.asset_for_denom(token_out_denom),
+        ) {
+            (Some(token_in), Some(token_out)) if token_in != token_out => Ok((token_in, token_out)),
+            _ => Err(eyre!(
+                "Cannot find pair: {} | {}",
+                token_in_denom,
+                token_out_denom
+            )),
+        }
+    }
+
+    fn reserves_for(&self, token_in_denom: &str, token_out_denom: &str) -> Result<(u128, u128)> {
+        let (token_in, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+        Ok((
+            self.assets[token_in].amount.parse::<u128>()?,
+            self.assets[token_out].amount.parse::<u128>()?,
+        ))
+    }
+}
+
+#[async_trait]
+impl Pool for TerraswapPool {
+    async fn get_quote(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let amount_out = if config.estimate_quote {
+            let (token_in, _) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+            let offer_asset = TerraswapAsset {
+                info: self.assets[token_in].info.clone(),
+                amount: amount.to_string(),
+            };
+            simulation(&config.rest, &self.pool_address, &offer_asset)
+                .await?
+                .return_amount
+                .parse::<u128>()?
+        } else {
+            self.simulate_swap(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount),
+            token_out: Some(amount_out),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn get_quote_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+        config: &PoolConfig,
+    ) -> Result<Quote> {
+        let amount_in = if config.estimate_quote {
+            let (_, token_out) = self.assets_for_pair(token_in_denom, token_out_denom)?;
+            let ask_asset = TerraswapAsset {
+                info: self.assets[token_out].info.clone(),
+                amount: amount.to_string(),
+            };
+            reverse_simulation(&config.rest, &self.pool_address, &ask_asset)
+                .await?
+                .offer_amount
+                .parse::<u128>()?
+        } else {
+            self.simulate_swap_exact_out(amount, token_in_denom, token_out_denom)?
+        };
+        Ok(Quote {
+            token_in: Some(amount_in),
+            token_out: Some(amount),
+            pool_address: Some(self.pool_address.clone()),
+            error: None,
+            source: Some(config.quote_source()),
+            ..Default::default()
+        })
+    }
+
+    async fn refresh(&self, config: &PoolConfig) -> Result<Box<dyn Pool>> {
+        let info = get_pair_pool(&config.rest, &self.pool_address).await?;
+        let mut pool = self.clone();
+        for asset in pool.assets.iter_mut() {
+            let new_asset = info
+                .assets
+                .iter()
+                .find(|x| x.info == asset.info)
+                .ok_or_else(|| eyre!("Pool {} has no {:?} asset", self.pool_address, asset.info))?;
+            asset.amount = new_asset.amount.clone();
+        }
+        pool.total_share = info.total_share;
+        Ok(Box::new(pool))
+    }
+
+    fn simulate_swap(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves_for(token_in_denom, token_out_denom)?;
+        constant_product_commission_out_given_in(
+            reserve_in,
+            reserve_out,
+            amount,
+            self.commission_rate.parse::<Dec>()?,
+        )
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        amount: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves_for(token_in_denom, token_out_denom)?;
+        constant_product_commission_in_given_out(
+            reserve_in,
+            reserve_out,
+            amount,
+            self.commission_rate.parse::<Dec>()?,
+        )
+    }
+
+    fn spot_price_after(
+        &self,
+        amount_in: u128,
+        amount_out: u128,
+        token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Dec> {
+        let (reserve_in, reserve_out) = self.reserves_for(token_in_denom, token_out_denom)?;
+        let (reserve_in, reserve_out) =
+            reserves_after_swap(reserve_in, reserve_out, amount_in, amount_out)?;
+        constant_product_spot_price(reserve_in, reserve_out)
+    }
+
+    fn fee(
+        &self,
+        _amount_in: u128,
+        amount_out: u128,
+        _token_in_denom: &str,
+        token_out_denom: &str,
+    ) -> Result<Fee> {
+        Fee::from_output(
+            self.commission_rate.parse::<Dec>()?,
+            amount_out,
+            token_out_denom,
+        )
+    }
+
+    fn token_denoms(&self) -> Vec<String> {
+        self.tokens
+            .iter()
+            .filter_map(|x| x.symbol.clone())
+            .collect()
+    }
+
+    fn asset_denoms(&self) -> Vec<String> {
+        self.assets.iter().map(|x| x.info.chain_denom()).collect()
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    fn chain(&self) -> String {
+        self.chain.clone().unwrap_or_else(|| String::from("juno"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn address(&self) -> Result<String> {
+        Ok(self.pool_address.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "cw20:juno1token0000000000000000000000000000000000000000000000000";
+
+    // ujuno/TKN pair with 0.3% commission on the output, the expected inputs are the smallest
+    // ones for which the output of the pair contract reaches the requested amount
+    fn fixture_pool() -> TerraswapPool {
+        let path = format!(
+            "{}/tests/fixtures/terraswap_pools.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        *load_terraswap_pools_from_file(Path::new(&path))
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn assets_by_symbol_and_chain_denom() {
+        let pool = fixture_pool();
+        assert_eq!(pool.asset_for_denom("TKN"), Some(1));
+        assert_eq!(pool.asset_for_denom(TOKEN), Some(1));
+        assert_eq!(pool.asset_for_denom("ujuno"), Some(0));
+        assert_eq!(pool.asset_for_denom("uatom"), None);
+        assert_eq!(pool.assets_for_pair(TOKEN, "ujuno").unwrap(), (1, 0));
+        assert!(pool.assets_for_pair("TKN", TOKEN).is_err());
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000, TOKEN, "ujuno").unwrap(),
+            pool.simulate_swap(1_000_000_000, "TKN", "ujuno").unwrap()
+        );
+    }
+
+    #[test]
+    fn exact_in_of_fixture() {
+        let pool = fixture_pool();
+        assert_eq!(
+            pool.simulate_swap(1_000_000, "ujuno", TOKEN).unwrap(),
+            7_975_354_069
+        );
+        assert_eq!(
+            pool.simulate_swap(1_000_000_000, "TKN", "ujuno").unwrap(),
+            124_624
+        );
+    }
+
+    #[test]
+    fn exact_out_of_fixture() {
+        let pool = fixture_pool();
+        for (amount_out, token_in, token_out, amount_in) in [
+            (7_975_354_069, "ujuno", "TKN", 1_000_000),
+            (124_624, "TKN", "ujuno", 999_994_134),
+            (1_000_000_000_000, "ujuno", TOKEN, 126_662_444),
+            (10_000_000_000, TOKEN, "ujuno", 427_807_507_065_625),
+        ] {
+            assert_eq!(
+                pool.simulate_swap_exact_out(amount_out, token_in, token_out)
+                    .unwrap(),
+                amount_in
+            );
+            assert!(pool.simulate_swap(amount_in, token_in, token_out).unwrap() >= amount_out);
+            assert!(
+                pool.simulate_swap(amount_in - 1, token_in, token_out)
+                    .unwrap()
+                    < amount_out
+            );
+        }
+        assert!(pool
+            .simulate_swap_exact_out(12_345_678_901, "TKN", "ujuno")
+            .is_err());
+    }
+
+    #[test]
+    fn commission_is_taken_from_the_output() {
+        let pool = fixture_pool();
+        let fee = pool.fee(1_000_000, 7_975_354_069, "ujuno", "TKN").unwrap();
+        assert_eq!(fee.denom, "TKN");
+        assert_eq!(fee.rate.to_string(), "0.003000000000000000");
+        // a lower rate of a fork gives more output
+        let mut fork = pool.clone();
+        fork.commission_rate = "0.0025".to_owned();
+        assert!(
+            fork.simulate_swap(1_000_000, "ujuno", "TKN").unwrap()
+                > pool.simulate_swap(1_000_000, "ujuno", "TKN").unwrap()
+        );
+    }
+}
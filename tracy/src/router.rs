@@ -0,0 +1,543 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use eyre::{eyre, Result};
+use futures::{stream, StreamExt};
+
+use crate::assets::{AssetId, AssetRegistry};
+use crate::dex::DEFAULT_QUOTE_CONCURRENCY;
+use crate::{Pool, PoolConfig, Quote};
+
+pub const DEFAULT_MAX_HOPS: usize = 3;
+// partial paths per (chain, asset) which [`Router::paths`] extends in every layer
+pub const PATHS_PER_ASSET: usize = 8;
+
+/// One swap through a single pool as part of a [`Route`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RouteHop {
+    pub pool_address: String,
+    pub chain: String,
+    pub token_in: AssetId,
+    pub token_out: AssetId,
+    // on-chain denoms of the pool
+    pub token_in_denom: String,
+    pub token_out_denom: String,
+    pub amount_in: u128,
+    pub amount_out: u128,
+}
+
+/// A chain of swaps where the output of every hop is the input of the next one.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Route {
+    pub token_in: AssetId,
+    pub token_out: AssetId,
+    // on-chain denoms of the chain of the route
+    pub token_in_denom: String,
+    pub token_out_denom: String,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub hops: Vec<RouteHop>,
+}
+
+impl Route {
+    // the denoms are only known once the first hop picks the chain
+    fn start(amount: u128, token_in: &AssetId) -> Self {
+        Route {
+            token_in: token_in.clone(),
+            token_out: token_in.clone(),
+            token_in_denom: String::new(),
+            token_out_denom: String::new(),
+            amount_in: amount,
+            amount_out: amount,
+            hops: vec![],
+        }
+    }
+
+    fn chain(&self) -> Option<&str> {
+        self.hops.first().map(|x| x.chain.as_str())
+    }
+
+    fn visits(&self, asset: &AssetId) -> bool {
+        self.token_in == *asset || self.hops.iter().any(|x| x.token_out == *asset)
+    }
+
+    // a pool swapped in twice would be quoted without the changes of the first swap
+    fn uses_pool(&self, address: &str) -> bool {
+        self.hops.iter().any(|x| x.pool_address == address)
+    }
+
+    fn extend(&self, hop: RouteHop) -> Self {
+        let mut route = self.clone();
+        if route.hops.is_empty() {
+            route.token_in_denom = hop.token_in_denom.clone();
+        }
+        route.token_out = hop.token_out.clone();
+        route.token_out_denom = hop.token_out_denom.clone();
+        route.amount_out = hop.amount_out;
+        route.hops.push(hop);
+        route
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    pool: usize,
+    token_in_denom: String,
+    token_out: AssetId,
+    token_out_denom: String,
+}
+
+/// Swap through one pool of a path found by [`Router::paths`].
+#[derive(Debug, Clone)]
+pub(crate) struct PathHop {
+    pub pool: usize,
+    pub token_in: AssetId,
+    pub token_out: AssetId,
+    pub token_in_denom: String,
+    pub token_out_denom: String,
+}
+
+/// Token graph over a set of pools, nodes are asset ids and every tradable pair of a pool
+/// is an edge.
+pub struct Router {
+    pools: Vec<Box<dyn Pool>>,
+    edges: HashMap<AssetId, Vec<Edge>>,
+}
+
+impl Router {
+    pub fn new(pools: Vec<Box<dyn Pool>>, assets: &AssetRegistry) -> Self {
+        let mut edges: HashMap<AssetId, Vec<Edge>> = HashMap::new();
+        for (index, pool) in pools.iter().enumerate() {
+            let pool_assets = assets.pool_assets(pool.as_ref());
+            for token_in in &pool_assets {
+                for token_out in &pool_assets {
+                    if token_in.id() == token_out.id() {
+                        continue;
+                    }
+                    edges.entry(token_in.id()).or_default().push(Edge {
+                        pool: index,
+                        token_in_denom: token_in.denom.clone(),
+                        token_out: token_out.id(),
+                        token_out_denom: token_out.denom.clone(),
+                    });
+                }
+            }
+        }
+        Router { pools, edges }
+    }
+
+    // number of hops needed from every asset to reach `target`, the graph is undirected
+    fn distances_to(&self, target: &AssetId) -> HashMap<AssetId, usize> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        distances.insert(target.clone(), 0);
+        queue.push_back(target.clone());
+        while let Some(asset) = queue.pop_front() {
+            let distance = distances[&asset];
+            for edge in self.edges.get(&asset).into_iter().flatten() {
+                if !distances.contains_key(&edge.token_out) {
+                    distances.insert(edge.token_out.clone(), distance + 1);
+                    queue.push_back(edge.token_out.clone());
+                }
+            }
+        }
+        distances
+    }
+
+    // chains of the pools which trade `asset`, sorted
+    fn chains_of(&self, asset: &AssetId) -> Vec<String> {
+        let chains: HashSet<String> = self
+            .edges
+            .get(asset)
+            .into_iter()
+            .flatten()
+            .map(|x| self.pools[x.pool].chain())
+            .collect();
+        let mut chains: Vec<String> = chains.into_iter().collect();
+        chains.sort();
+        chains
+    }
+
+    pub(crate) fn pool(&self, index: usize) -> &dyn Pool {
+        self.pools[index].as_ref()
+    }
+
+    /// Every path of at most `max_hops` pools without cycles or a pool used twice, all pools
+    /// of a path are on the same chain. Comes with the output of the path for `amount` from
+    /// the stored reserves, 0 if a pool cannot be simulated.
+    ///
+    /// Every layer only extends the `PATHS_PER_ASSET` partial paths with the highest output
+    /// per (chain, asset), so the number of paths grows linearly with `max_hops` instead of
+    /// exponentially.
+    pub(crate) fn paths(
+        &self,
+        amount: u128,
+        token_in: &AssetId,
+        token_out: &AssetId,
+        max_hops: usize,
+    ) -> Vec<(u128, Vec<PathHop>)> {
+        let distances = self.distances_to(token_out);
+        let mut paths = vec![];
+        let mut frontier: Vec<(u128, Vec<PathHop>)> = vec![(amount, vec![])];
+        for hop in 0..max_hops {
+            let remaining = max_hops - hop - 1;
+            let mut next = vec![];
+            for (amount, path) in frontier {
+                let asset = path.last().map_or(token_in, |x| &x.token_out);
+                for edge in self.edges.get(asset).into_iter().flatten() {
+                    let visited = edge.token_out == *token_in
+                        || path
+                            .iter()
+                            .any(|x| x.token_out == edge.token_out || x.pool == edge.pool);
+                    if visited {
+                        continue;
+                    }
+                    match distances.get(&edge.token_out) {
+                        Some(distance) if *distance <= remaining => {}
+                        _ => continue,
+                    }
+                    let chain = self.pools[edge.pool].chain();
+                    if path
+                        .first()
+                        .is_some_and(|x| self.pools[x.pool].chain() != chain)
+                    {
+                        continue;
+                    }
+                    let amount_out = if amount == 0 {
+                        0
+                    } else {
+                        self.pools[edge.pool]
+                            .simulate_swap(amount, &edge.token_in_denom, &edge.token_out_denom)
+                            .unwrap_or(0)
+                    };
+                    let mut extended = path.clone();
+                    extended.push(PathHop {
+                        pool: edge.pool,
+                        token_in: asset.clone(),
+                        token_out: edge.token_out.clone(),
+                        token_in_denom: edge.token_in_denom.clone(),
+                        token_out_denom: edge.token_out_denom.clone(),
+                    });
+                    if edge.token_out == *token_out {
+                        paths.push((amount_out, extended));
+                    } else {
+                        next.push((amount_out, extended));
+                    }
+                }
+            }
+            // outputs of different assets are not comparable, so the best are kept per asset
+            next.sort_by_key(|x| Reverse(x.0));
+            let mut kept: HashMap<(String, AssetId), usize> = HashMap::new();
+            next.retain(|(_, path)| {
+                let last = &path[path.len() - 1];
+                let count = kept
+                    .entry((self.pools[last.pool].chain(), last.token_out.clone()))
+                    .or_default();
+                *count += 1;
+                *count <= PATHS_PER_ASSET
+            });
+            frontier = next;
+        }
+        paths
+    }
+
+    /// Finds the route with the highest output of at most `max_hops` swaps.
+    ///
+    /// Every layer keeps the best partial route per (chain, asset) and only expands
+    /// assets that can still reach `token_out` with the remaining hops. The pools of a
+    /// layer are quoted concurrently and a route uses every pool at most once.
+    /// All hops of a route have to be on the same chain, routes between assets which are
+    /// not traded on a common chain fail as unsupported.
+    pub async fn best_route(
+        &self,
+        amount: u128,
+        token_in: &AssetId,
+        token_out: &AssetId,
+        max_hops: usize,
+        config: &HashMap<String, PoolConfig>,
+    ) -> Result<Route> {
+        if token_in == token_out {
+            return Err(eyre!("Cannot route {} to itself", token_in));
+        }
+        let chains_in = self.chains_of(token_in);
+        let chains_out = self.chains_of(token_out);
+        if !chains_in.is_empty()
+            && !chains_out.is_empty()
+            && !chains_in.iter().any(|x| chains_out.contains(x))
+        {
+            return Err(eyre!(
+                "Cross-chain routes are unsupported: {} is traded on {} and {} on {}",
+                token_in,
+                chains_in.join(", "),
+                token_out,
+                chains_out.join(", ")
+            ));
+        }
+        let distances = self.distances_to(token_out);
+        let mut best: Option<Route> = None;
+        let mut frontier = vec![Route::start(amount, token_in)];
+
+        for hop in 0..max_hops {
+            let remaining = max_hops - hop - 1;
+            let mut extensions = vec![];
+            for route in &frontier {
+                for edge in self.edges.get(&route.token_out).into_iter().flatten() {
+                    if route.visits(&edge.token_out) {
+                        continue;
+                    }
+                    match distances.get(&edge.token_out) {
+                        Some(distance) if *distance <= remaining => {}
+                        _ => continue,
+                    }
+                    let pool = &self.pools[edge.pool];
+                    if route.chain().is_some_and(|x| x != pool.chain())
+                        || route.uses_pool(&pool.address()?)
+                    {
+                        continue;
+                    }
+                    if let Some(pool_config) = config.get(&pool.chain()) {
+                        extensions.push((route, edge, pool_config));
+                    }
+                }
+            }
+            // one round trip per pool with simulations, so the whole layer at once,
+            // collected first for the same reason as in quote_all
+            let quotes: Vec<_> = extensions
+                .iter()
+                .map(|(route, edge, pool_config)| {
+                    self.pools[edge.pool].get_quote(
+                        route.amount_out,
+                        &edge.token_in_denom,
+                        &edge.token_out_denom,
+                        pool_config,
+                    )
+                })
+                .collect();
+            let quotes: Vec<Result<Quote>> = stream::iter(quotes)
+                .buffered(DEFAULT_QUOTE_CONCURRENCY)
+                .collect()
+                .await;
+
+            let mut next: HashMap<(String, AssetId), Route> = HashMap::new();
+            for ((route, edge, _), quote) in extensions.into_iter().zip(quotes) {
+                let amount_out = match quote.map(|x| x.token_out) {
+                    Ok(Some(x)) if x > 0 => x,
+                    _ => continue,
+                };
+                let pool = &self.pools[edge.pool];
+                let chain = pool.chain();
+                let candidate = route.extend(RouteHop {
+                    pool_address: pool.address()?,
+                    chain: chain.clone(),
+                    token_in: route.token_out.clone(),
+                    token_out: edge.token_out.clone(),
+                    token_in_denom: edge.token_in_denom.clone(),
+                    token_out_denom: edge.token_out_denom.clone(),
+                    amount_in: route.amount_out,
+                    amount_out,
+                });
+                if edge.token_out == *token_out {
+                    if best.as_ref().is_none_or(|x| x.amount_out < amount_out) {
+                        best = Some(candidate);
+                    }
+                    continue;
+                }
+                let key = (chain, edge.token_out.clone());
+                if next.get(&key).is_none_or(|x| x.amount_out < amount_out) {
+                    next.insert(key, candidate);
+                }
+            }
+            frontier = next.into_values().collect();
+        }
+
+        best.ok_or_else(|| {
+            eyre!(
+                "No route found: {} -> {} in {} hops",
+                token_in,
+                token_out,
+                max_hops
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::mock_pool::MockPool;
+
+    fn id(denom: &str) -> AssetId {
+        AssetId::new("osmosis", denom)
+    }
+
+    fn config() -> HashMap<String, PoolConfig> {
+        HashMap::from([
+            ("osmosis".to_owned(), PoolConfig::default()),
+            ("juno".to_owned(), PoolConfig::default()),
+        ])
+    }
+
+    async fn best_route(pools: Vec<MockPool>, amount: u128, from: &str, to: &str) -> Result<Route> {
+        let pools = pools.into_iter().map(MockPool::boxed).collect();
+        Router::new(pools, &AssetRegistry::default())
+            .best_route(amount, &id(from), &id(to), DEFAULT_MAX_HOPS, &config())
+            .await
+    }
+
+    fn pool_addresses(route: &Route) -> Vec<&str> {
+        route.hops.iter().map(|x| x.pool_address.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn two_hop_route_chains_amounts() {
+        let pools = vec![
+            MockPool::pair("osmosis", "pool-ab", ("a", 1_000_000), ("b", 2_000_000)),
+            MockPool::pair("osmosis", "pool-bc", ("b", 2_000_000), ("c", 500_000)),
+        ];
+        let route = best_route(pools, 10_000, "a", "c").await.unwrap();
+        assert_eq!(pool_addresses(&route), vec!["pool-ab", "pool-bc"]);
+        let (first, second) = (&route.hops[0], &route.hops[1]);
+        assert_eq!(first.amount_in, 10_000);
+        // 10000 * 0.997 * 2000000 / (1000000 + 9970)
+        assert_eq!(first.amount_out, 19_743);
+        assert_eq!(second.token_in, id("b"));
+        assert_eq!(second.amount_in, first.amount_out);
+        // 19743 * 0.997 * 500000 / (2000000 + 19683.771)
+        assert_eq!(second.amount_out, 4_872);
+        assert_eq!(route.amount_in, 10_000);
+        assert_eq!(route.amount_out, second.amount_out);
+        assert_eq!(route.token_in_denom, "a");
+        assert_eq!(route.token_out_denom, "c");
+    }
+
+    #[tokio::test]
+    async fn better_route_wins() {
+        let direct = MockPool::pair("osmosis", "pool-ac", ("a", 1_000_000), ("c", 250_000));
+        let over_b = vec![
+            MockPool::pair("osmosis", "pool-ab", ("a", 1_000_000), ("b", 2_000_000)),
+            MockPool::pair("osmosis", "pool-bc", ("b", 2_000_000), ("c", 1_000_000)),
+        ];
+        let mut pools = over_b.clone();
+        pools.push(direct.clone());
+        let route = best_route(pools, 10_000, "a", "c").await.unwrap();
+        assert_eq!(pool_addresses(&route), vec!["pool-ab", "pool-bc"]);
+
+        // with a deeper direct pool the single hop is better
+        let direct = MockPool::pair("osmosis", "pool-ac", ("a", 1_000_000), ("c", 2_000_000));
+        let mut pools = over_b;
+        pools.push(direct);
+        let route = best_route(pools, 10_000, "a", "c").await.unwrap();
+        assert_eq!(pool_addresses(&route), vec!["pool-ac"]);
+    }
+
+    #[tokio::test]
+    async fn route_uses_a_pool_once() {
+        // a -> b -> c through pool-abc twice would pay out more than its bad a/c curve
+        let pools = vec![
+            MockPool::pair("osmosis", "pool-abc", ("a", 1_000_000), ("b", 1_000_000))
+                .with_pair(("b", 1_000_000), ("c", 1_000_000))
+                .with_pair(("a", 1_000_000), ("c", 1_000)),
+            MockPool::pair("osmosis", "pool-bc", ("b", 1_000_000), ("c", 500_000)),
+        ];
+        let route = best_route(pools, 10_000, "a", "c").await.unwrap();
+        assert_eq!(pool_addresses(&route), vec!["pool-abc", "pool-bc"]);
+    }
+
+    fn path_pools(router: &Router, path: &[PathHop]) -> Vec<String> {
+        path.iter()
+            .map(|x| router.pool(x.pool).address().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn paths_use_a_pool_once() {
+        let pools = vec![
+            MockPool::pair("osmosis", "pool-abc", ("a", 1_000_000), ("b", 1_000_000))
+                .with_pair(("b", 1_000_000), ("c", 1_000_000))
+                .with_pair(("a", 1_000_000), ("c", 1_000))
+                .boxed(),
+            MockPool::pair("osmosis", "pool-bc", ("b", 1_000_000), ("c", 500_000)).boxed(),
+        ];
+        let router = Router::new(pools, &AssetRegistry::default());
+        let mut paths: Vec<Vec<String>> = router
+            .paths(10_000, &id("a"), &id("c"), 2)
+            .iter()
+            .map(|(_, path)| path_pools(&router, path))
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec![vec!["pool-abc"], vec!["pool-abc", "pool-bc"]]);
+    }
+
+    #[test]
+    fn paths_keep_the_best_partial_paths_per_asset() {
+        // 12 a/b pools of growing depth times 2 b/c pools would be 24 paths
+        let mut pools = vec![];
+        for index in 0..12u128 {
+            let reserve = 1_000_000 * (index + 1);
+            let address = format!("pool-ab-{}", index);
+            pools.push(MockPool::pair("osmosis", &address, ("a", reserve), ("b", reserve)).boxed());
+        }
+        for address in ["pool-bc-0", "pool-bc-1"] {
+            pools.push(
+                MockPool::pair("osmosis", address, ("b", 1_000_000), ("c", 1_000_000)).boxed(),
+            );
+        }
+        let router = Router::new(pools, &AssetRegistry::default());
+        let paths = router.paths(100_000, &id("a"), &id("c"), 2);
+        assert_eq!(paths.len(), PATHS_PER_ASSET * 2);
+        let first_hops: HashSet<String> = paths
+            .iter()
+            .map(|(_, path)| path_pools(&router, path)[0].clone())
+            .collect();
+        let deepest: HashSet<String> = (12 - PATHS_PER_ASSET..12)
+            .map(|x| format!("pool-ab-{}", x))
+            .collect();
+        assert_eq!(first_hops, deepest);
+        for (amount_out, path) in &paths {
+            let mut amount = 100_000;
+            for hop in path {
+                amount = router
+                    .pool(hop.pool)
+                    .simulate_swap(amount, &hop.token_in_denom, &hop.token_out_denom)
+                    .unwrap();
+            }
+            assert_eq!(*amount_out, amount);
+        }
+    }
+
+    #[tokio::test]
+    async fn no_route() {
+        let pools = vec![
+            MockPool::pair("osmosis", "pool-ab", ("a", 1_000_000), ("b", 1_000_000)),
+            MockPool::pair("osmosis", "pool-cd", ("c", 1_000_000), ("d", 1_000_000)),
+        ];
+        let err = best_route(pools, 10_000, "a", "d").await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No route found: osmosis:a -> osmosis:d in 3 hops"
+        );
+    }
+
+    #[tokio::test]
+    async fn cross_chain_route_is_unsupported() {
+        let pools = vec![
+            MockPool::pair("juno", "pool-juno", ("ujuno", 1_000), ("uusdc", 1_000)).boxed(),
+            MockPool::pair("osmosis", "pool-osmo", ("uosmo", 1_000), ("uatom", 1_000)).boxed(),
+        ];
+        let router = Router::new(pools, &AssetRegistry::default());
+        let err = router
+            .best_route(
+                1_000_000,
+                &AssetId::new("juno", "ujuno"),
+                &AssetId::new("osmosis", "uosmo"),
+                DEFAULT_MAX_HOPS,
+                &config(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cross-chain routes are unsupported: juno:ujuno is traded on juno and osmosis:uosmo on osmosis"
+        );
+    }
+}
@@ -1,7 +1,16 @@
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::time::UNIX_EPOCH;
 
-use tracy::{dex::DexAgg, Quote};
-use warp::{http::Response, Filter};
+use tracy::{
+    dex::{DexAgg, QuoteOptions},
+    router::DEFAULT_MAX_HOPS,
+    split::SplitOptions,
+};
+use warp::{
+    http::{Response, StatusCode},
+    Filter,
+};
 
 pub type Db = DexAgg;
 
@@ -9,6 +18,22 @@ pub fn with_db(db: Db) -> impl Filter<Extract = (Db,), Error = std::convert::Inf
     warp::any().map(move || db.clone())
 }
 
+fn json_response(body: String) -> Response<String> {
+    Response::builder()
+        .header("content-type", "application/json")
+        .header("access-control-allow-origin", "*")
+        .body(body)
+        .unwrap()
+}
+
+// errors are returned as {"error": "..."} with a non-200 status
+fn error_response(status: StatusCode, error: eyre::Report) -> Response<String> {
+    let body = serde_json::json!({ "error": error.to_string() });
+    let mut response = json_response(body.to_string());
+    *response.status_mut() = status;
+    response
+}
+
 pub async fn list_pools_for_denom(param: String, db: Db) -> Result<impl warp::Reply, Infallible> {
     let db = db;
     let text = db
@@ -20,14 +45,10 @@ pub async fn list_pools_for_denom(param: String, db: Db) -> Result<impl warp::Re
         .reduce(|acc, x| acc + "," + &x);
     let body = match text {
         Some(text) => format!("[{}]", text),
-        None => format!("[]"),
+        None => "[]".to_string(),
     };
 
-    Ok(Response::builder()
-        .header("content-type", "application/json")
-        .header("access-control-allow-origin", "*")
-        .body(body)
-        .unwrap())
+    Ok(json_response(body))
 }
 
 pub async fn list_pools_for_denoms(
@@ -45,13 +66,9 @@ pub async fn list_pools_for_denoms(
         .reduce(|acc, x| acc + "," + &x);
     let body = match text {
         Some(text) => format!("[{}]", text),
-        None => format!("[]"),
+        None => "[]".to_string(),
     };
-    Ok(Response::builder()
-        .header("content-type", "application/json")
-        .header("access-control-allow-origin", "*")
-        .body(body)
-        .unwrap())
+    Ok(json_response(body))
 }
 
 pub async fn get_quotes(
@@ -59,43 +76,81 @@ pub async fn get_quotes(
     denom2: String,
     amount: String,
     db: Db,
+) -> Result<impl warp::Reply, Infallible> {
+    quotes_response(denom1, denom2, amount, db, false).await
+}
+
+pub async fn get_quotes_exact_out(
+    denom1: String,
+    denom2: String,
+    amount: String,
+    db: Db,
+) -> Result<impl warp::Reply, Infallible> {
+    quotes_response(denom1, denom2, amount, db, true).await
+}
+
+async fn quotes_response(
+    denom1: String,
+    denom2: String,
+    amount: String,
+    db: Db,
+    exact_out: bool,
+) -> Result<Response<String>, Infallible> {
+    // the amount is in token_out for exact out quotes
+    let amount_denom = if exact_out { &denom2 } else { &denom1 };
+    let options = QuoteOptions {
+        exact_out,
+        ..Default::default()
+    };
+    let quotes = match db.parse_amount(&amount, amount_denom).await {
+        Ok(amount) => db.quote_all(amount, &denom1, &denom2, &options).await,
+        Err(e) => Err(e),
+    };
+    // still an array of quotes, failed pools are quotes with an error
+    Ok(match quotes {
+        Ok(quotes) => json_response(serde_json::to_string(&quotes).unwrap()),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    })
+}
+
+pub async fn get_route(
+    denom1: String,
+    denom2: String,
+    amount: String,
+    db: Db,
 ) -> Result<impl warp::Reply, Infallible> {
     let db = db;
-    let pools = db
-        .with_denoms(vec![denom1.to_string(), denom2.to_string()])
-        .await
-        .clone();
-    let mut quotes = vec![];
-    for pool in pools {
-        let quote = pool
-            .get_quote(
-                u128::from_str_radix(&amount, 10).unwrap(),
-                &denom1,
-                &denom2,
-                db.config
-                    .get(&pool.chain())
-                    .expect(&format!("No config for chain {}", pool.chain())),
-            )
-            .await;
-        quotes.push(quote);
-    }
+    let route = match db.parse_amount(&amount, &denom1).await {
+        Ok(amount) => {
+            db.best_route(amount, &denom1, &denom2, DEFAULT_MAX_HOPS)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    Ok(match route {
+        Ok(route) => json_response(serde_json::to_string(&route).unwrap()),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    })
+}
 
-    let returnarray: Vec<Quote> = quotes
-        .into_iter()
-        .map(|x| match x {
-            Ok(x) => x,
-            Err(x) => Quote {
-                error: Some(format!("{{{}}}", x).to_string()),
-                token_in: None,
-                token_out: None,
-                pool_address: None,
-            },
-        })
-        .collect();
-    Ok(Response::builder()
-        .header("access-control-allow-origin", "*")
-        .body(serde_json::to_string(&returnarray).unwrap())
-        .unwrap())
+pub async fn get_split(
+    denom1: String,
+    denom2: String,
+    amount: String,
+    db: Db,
+) -> Result<impl warp::Reply, Infallible> {
+    let db = db;
+    let split = match db.parse_amount(&amount, &denom1).await {
+        Ok(amount) => {
+            db.best_split(amount, &denom1, &denom2, &SplitOptions::default())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    Ok(match split {
+        Ok(split) => json_response(serde_json::to_string(&split).unwrap()),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    })
 }
 
 pub async fn get_pool_by_address_handler(
@@ -104,16 +159,10 @@ pub async fn get_pool_by_address_handler(
 ) -> Result<impl warp::Reply, Infallible> {
     let db = db;
     let pool = db.with_address(&address).await;
-    let body = match pool {
-        Ok(p) => p.to_json(),
-        Err(e) => format!("{{\"error\": \"{}\"}}", e.to_string()),
-    };
-
-    Ok(Response::builder()
-        .header("content-type", "application/json")
-        .header("access-control-allow-origin", "*")
-        .body(body)
-        .unwrap())
+    Ok(match pool {
+        Ok(p) => json_response(p.to_json()),
+        Err(e) => error_response(StatusCode::NOT_FOUND, e),
+    })
 }
 
 pub async fn get_pools_handler(db: Db) -> Result<impl warp::Reply, Infallible> {
@@ -126,9 +175,56 @@ pub async fn get_pools_handler(db: Db) -> Result<impl warp::Reply, Infallible> {
         .map(|x| x.to_json())
         .reduce(|acc: String, x: String| format!("{},{}", acc, x));
 
-    Ok(Response::builder()
-        .header("content-type", "application/json")
-        .header("access-control-allow-origin", "*")
-        .body(format!("[{}]", objs.unwrap_or("".to_owned())))
-        .unwrap())
+    Ok(json_response(format!(
+        "[{}]",
+        objs.unwrap_or("".to_owned())
+    )))
+}
+
+pub async fn get_assets_handler(db: Db) -> Result<impl warp::Reply, Infallible> {
+    let body = serde_json::to_string(&db.assets.assets()).unwrap();
+
+    Ok(json_response(body))
+}
+
+pub async fn get_endpoints_handler(db: Db) -> Result<impl warp::Reply, Infallible> {
+    let body = serde_json::to_string(&db.endpoint_stats()).unwrap();
+
+    Ok(json_response(body))
+}
+
+pub async fn get_last_updated_handler(db: Db) -> Result<impl warp::Reply, Infallible> {
+    // unix timestamps in seconds by pool address
+    let updated: HashMap<String, u64> = db
+        .last_updated
+        .lock()
+        .await
+        .iter()
+        .map(|(address, time)| {
+            let secs = time.duration_since(UNIX_EPOCH).map_or(0, |x| x.as_secs());
+            (address.clone(), secs)
+        })
+        .collect();
+
+    Ok(json_response(serde_json::to_string(&updated).unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_json() {
+        let response = error_response(
+            StatusCode::BAD_REQUEST,
+            eyre::eyre!("Cannot parse \"1.5.0\""),
+        );
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "Cannot parse \"1.5.0\"" })
+        );
+    }
 }
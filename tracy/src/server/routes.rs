@@ -1,8 +1,9 @@
 use warp::Filter;
 
 use crate::handler::{
-    get_pool_by_address_handler, get_pools_handler, get_quotes, list_pools_for_denom,
-    list_pools_for_denoms, with_db, Db,
+    get_assets_handler, get_endpoints_handler, get_last_updated_handler,
+    get_pool_by_address_handler, get_pools_handler, get_quotes, get_quotes_exact_out, get_route,
+    get_split, list_pools_for_denom, list_pools_for_denoms, with_db, Db,
 };
 
 fn pools_with_denom(
@@ -35,6 +36,33 @@ fn get_quotes_route(
         .and_then(get_quotes)
 }
 
+fn get_quotes_exact_out_route(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("quote_exact_out" / String / String / String)
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_quotes_exact_out)
+}
+
+fn get_route_route(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("route" / String / String / String)
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_route)
+}
+
+fn get_split_route(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("split" / String / String / String)
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_split)
+}
+
 fn get_pool_by_address(
     dex_agg: Db,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -53,12 +81,45 @@ fn get_pools(
         .and_then(get_pools_handler)
 }
 
+fn get_assets(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("assets")
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_assets_handler)
+}
+
+fn get_endpoints(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("endpoints")
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_endpoints_handler)
+}
+
+fn get_last_updated(
+    dex_agg: Db,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("last_updated")
+        .and(warp::get())
+        .and(with_db(dex_agg))
+        .and_then(get_last_updated_handler)
+}
+
 pub fn all_routes(
     dex_agg: Db,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     pools_with_denom(dex_agg.clone())
         .or(pools_with_denoms(dex_agg.clone()))
         .or(get_quotes_route(dex_agg.clone()))
+        .or(get_quotes_exact_out_route(dex_agg.clone()))
+        .or(get_route_route(dex_agg.clone()))
+        .or(get_split_route(dex_agg.clone()))
         .or(get_pool_by_address(dex_agg.clone()))
         .or(get_pools(dex_agg.clone()))
+        .or(get_assets(dex_agg.clone()))
+        .or(get_endpoints(dex_agg.clone()))
+        .or(get_last_updated(dex_agg.clone()))
 }
@@ -1,4 +1,7 @@
+use std::env;
 use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::Path;
+use std::time::Duration;
 
 use handler::Db;
 use tracy::dex::DexAgg;
@@ -10,10 +13,21 @@ mod routes;
 
 #[tokio::main]
 async fn main() {
+    // warnings of the background refreshes and subscriptions, RUST_LOG overrides the level
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
     println!("server");
 
+    // config file from the first argument or TRACY_CONFIG, the public nodes are used without one
+    let config_path = env::args().nth(1).or_else(|| env::var("TRACY_CONFIG").ok());
     // TODO: do we need arc?
-    let dexes: Db = DexAgg::new(None).unwrap();
+    let dexes: Db = match config_path {
+        Some(path) => DexAgg::from_config_file(Path::new(&path), None).unwrap(),
+        None => DexAgg::new(None).unwrap(),
+    };
+    dexes.spawn_health_checks(Duration::from_secs(30));
+    dexes.start_refresher(Duration::from_secs(60));
+    // reserves of pools with a swap are updated right after the block
+    dexes.spawn_subscribers();
     let api = all_routes(dexes);
     warp::serve(api)
         .run(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080))
@@ -0,0 +1,310 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use eyre::{eyre, Result};
+
+use crate::assets::AssetId;
+use crate::router::{PathHop, Route, RouteHop, Router};
+
+pub const DEFAULT_SPLIT_STEPS: usize = 100;
+pub const DEFAULT_SPLIT_ROUTES: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SplitOptions {
+    // 1 only splits over pools with both denoms, more also uses routes over several pools
+    pub max_hops: usize,
+    // number of routes the amount may be split over
+    pub max_routes: usize,
+    // the amount is allocated in this many parts, more parts find better splits but take longer
+    pub steps: usize,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            max_hops: 1,
+            max_routes: DEFAULT_SPLIT_ROUTES,
+            steps: DEFAULT_SPLIT_STEPS,
+        }
+    }
+}
+
+/// An amount split over several routes, every allocation is swapped on its own.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SplitQuote {
+    pub token_in: AssetId,
+    pub token_out: AssetId,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub allocations: Vec<Route>,
+}
+
+// output of a path from the stored reserves, 0 if a pool cannot be simulated
+fn simulate_path(router: &Router, path: &[PathHop], amount: u128) -> u128 {
+    let mut amount = amount;
+    for hop in path {
+        if amount == 0 {
+            return 0;
+        }
+        amount = router
+            .pool(hop.pool)
+            .simulate_swap(amount, &hop.token_in_denom, &hop.token_out_denom)
+            .unwrap_or(0);
+    }
+    amount
+}
+
+fn to_route(router: &Router, path: &[PathHop], amount: u128) -> Result<Route> {
+    let mut hops = vec![];
+    let mut amount_in = amount;
+    for hop in path {
+        let pool = router.pool(hop.pool);
+        let amount_out =
+            pool.simulate_swap(amount_in, &hop.token_in_denom, &hop.token_out_denom)?;
+        hops.push(RouteHop {
+            pool_address: pool.address()?,
+            chain: pool.chain(),
+            token_in: hop.token_in.clone(),
+            token_out: hop.token_out.clone(),
+            token_in_denom: hop.token_in_denom.clone(),
+            token_out_denom: hop.token_out_denom.clone(),
+            amount_in,
+            amount_out,
+        });
+        amount_in = amount_out;
+    }
+    Ok(Route {
+        token_in: path[0].token_in.clone(),
+        token_out: path[path.len() - 1].token_out.clone(),
+        token_in_denom: path[0].token_in_denom.clone(),
+        token_out_denom: path[path.len() - 1].token_out_denom.clone(),
+        amount_in: amount,
+        amount_out: amount_in,
+        hops,
+    })
+}
+
+impl Router {
+    /// Splits `amount` over several routes to maximize the total output.
+    ///
+    /// Candidates are the routes with the best output for the whole amount which do not
+    /// share a pool. The amount is then handed out in `steps` parts, each part goes to
+    /// the route where it adds the most output. Uses the stored reserves only.
+    pub fn best_split(
+        &self,
+        amount: u128,
+        token_in: &AssetId,
+        token_out: &AssetId,
+        options: &SplitOptions,
+    ) -> Result<SplitQuote> {
+        if token_in == token_out {
+            return Err(eyre!("Cannot route {} to itself", token_in));
+        }
+        let mut paths: Vec<(u128, Vec<PathHop>)> = self
+            .paths(amount, token_in, token_out, options.max_hops)
+            .into_iter()
+            .filter(|(amount_out, _)| *amount_out > 0)
+            .collect();
+        paths.sort_by_key(|x| Reverse(x.0));
+
+        // routes sharing a pool would change each others reserves
+        let mut used_pools = HashSet::new();
+        let mut candidates = vec![];
+        for (_, path) in paths {
+            if candidates.len() >= options.max_routes.max(1) {
+                break;
+            }
+            if path.iter().any(|x| used_pools.contains(&x.pool)) {
+                continue;
+            }
+            used_pools.extend(path.iter().map(|x| x.pool));
+            candidates.push(path);
+        }
+        if candidates.is_empty() {
+            return Err(eyre!(
+                "No route found: {} -> {} in {} hops",
+                token_in,
+                token_out,
+                options.max_hops
+            ));
+        }
+
+        let steps = (options.steps.max(1) as u128).min(amount.max(1));
+        let part = amount / steps;
+        let mut allocated = vec![0u128; candidates.len()];
+        let mut outputs = vec![0u128; candidates.len()];
+        for step in 0..steps {
+            // the last part also takes the remainder
+            let part = if step == steps - 1 {
+                amount - part * (steps - 1)
+            } else {
+                part
+            };
+            let mut best = 0;
+            let mut best_output = 0;
+            let mut best_gain = 0;
+            for (index, path) in candidates.iter().enumerate() {
+                let output = simulate_path(self, path, allocated[index] + part);
+                let gain = output.saturating_sub(outputs[index]);
+                if gain > best_gain {
+                    best = index;
+                    best_output = output;
+                    best_gain = gain;
+                }
+            }
+            if best_gain == 0 {
+                best_output = simulate_path(self, &candidates[best], allocated[best] + part);
+            }
+            allocated[best] += part;
+            outputs[best] = best_output;
+        }
+
+        let mut allocations = vec![];
+        for (index, path) in candidates.iter().enumerate() {
+            if allocated[index] > 0 {
+                allocations.push(to_route(self, path, allocated[index])?);
+            }
+        }
+        allocations.sort_by_key(|x| Reverse(x.amount_in));
+        Ok(SplitQuote {
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount_in: amount,
+            amount_out: allocations.iter().map(|x| x.amount_out).sum(),
+            allocations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::assets::AssetRegistry;
+    use crate::pools::mock_pool::MockPool;
+    use crate::PoolConfig;
+
+    fn id(denom: &str) -> AssetId {
+        AssetId::new("osmosis", denom)
+    }
+
+    // two a/b pools and two routes over c, the c/b pools share pool-ac
+    fn router() -> Router {
+        let pools = vec![
+            MockPool::pair("osmosis", "pool-ab-1", ("a", 1_000_000), ("b", 1_000_000)),
+            MockPool::pair("osmosis", "pool-ab-2", ("a", 2_000_000), ("b", 2_000_000)),
+            MockPool::pair("osmosis", "pool-ac", ("a", 1_000_000), ("c", 1_000_000)),
+            MockPool::pair("osmosis", "pool-cb-1", ("c", 1_000_000), ("b", 1_000_000)),
+            MockPool::pair("osmosis", "pool-cb-2", ("c", 900_000), ("b", 900_000)),
+        ];
+        let pools = pools.into_iter().map(MockPool::boxed).collect();
+        Router::new(pools, &AssetRegistry::default())
+    }
+
+    fn options(max_hops: usize) -> SplitOptions {
+        SplitOptions {
+            max_hops,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn split_is_at_least_the_best_route() {
+        let router = router();
+        let config = HashMap::from([("osmosis".to_owned(), PoolConfig::default())]);
+        for amount in [1_000, 100_000, 1_000_000] {
+            let route = router
+                .best_route(amount, &id("a"), &id("b"), 2, &config)
+                .await
+                .unwrap();
+            let split = router
+                .best_split(amount, &id("a"), &id("b"), &options(2))
+                .unwrap();
+            assert!(split.amount_out >= route.amount_out);
+            assert_eq!(
+                split.amount_out,
+                split.allocations.iter().map(|x| x.amount_out).sum::<u128>()
+            );
+        }
+
+        // a large amount is worth splitting over every independent route
+        let amount = 1_000_000;
+        let route = router
+            .best_route(amount, &id("a"), &id("b"), 2, &config)
+            .await
+            .unwrap();
+        let split = router
+            .best_split(amount, &id("a"), &id("b"), &options(2))
+            .unwrap();
+        assert!(split.amount_out > route.amount_out);
+        assert_eq!(split.allocations.len(), 3);
+    }
+
+    #[test]
+    fn allocations_sum_to_the_amount() {
+        let router = router();
+        // not a multiple of the steps, the last part takes the remainder
+        for (amount, steps) in [(1_000_003, 100), (7, 100), (999_999, 7)] {
+            let options = SplitOptions {
+                max_hops: 2,
+                steps,
+                ..Default::default()
+            };
+            let split = router
+                .best_split(amount, &id("a"), &id("b"), &options)
+                .unwrap();
+            assert_eq!(split.amount_in, amount);
+            assert_eq!(
+                split.allocations.iter().map(|x| x.amount_in).sum::<u128>(),
+                amount
+            );
+            for route in &split.allocations {
+                assert_eq!(route.hops[0].amount_in, route.amount_in);
+            }
+        }
+    }
+
+    #[test]
+    fn allocations_do_not_share_a_pool() {
+        let split = router()
+            .best_split(1_000_000, &id("a"), &id("b"), &options(2))
+            .unwrap();
+        let pools: Vec<&str> = split
+            .allocations
+            .iter()
+            .flat_map(|x| x.hops.iter().map(|x| x.pool_address.as_str()))
+            .collect();
+        let unique: HashSet<&str> = pools.iter().copied().collect();
+        assert_eq!(pools.len(), unique.len());
+        // only the better route over c gets pool-ac
+        assert!(pools.contains(&"pool-cb-1"));
+        assert!(!pools.contains(&"pool-cb-2"));
+    }
+
+    #[test]
+    fn max_routes_limits_the_allocations() {
+        let options = SplitOptions {
+            max_hops: 2,
+            max_routes: 1,
+            ..Default::default()
+        };
+        let split = router()
+            .best_split(1_000_000, &id("a"), &id("b"), &options)
+            .unwrap();
+        assert_eq!(split.allocations.len(), 1);
+        assert_eq!(split.allocations[0].amount_in, 1_000_000);
+        assert_eq!(split.allocations[0].hops[0].pool_address, "pool-ab-2");
+    }
+
+    #[test]
+    fn no_split_route() {
+        let err = router()
+            .best_split(1_000, &id("a"), &id("d"), &options(2))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No route found: osmosis:a -> osmosis:d in 2 hops"
+        );
+    }
+}
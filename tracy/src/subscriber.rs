@@ -0,0 +1,631 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use futures::{stream, SinkExt, StreamExt};
+use log::warn;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::dex::{DexAgg, DEFAULT_REFRESH_CONCURRENCY};
+use crate::pools::osmosis_pool::as_gamm_pool;
+use crate::Pool;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// waiting time before connecting to the next rpc node after the connection was lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// cometbft rejects further subscriptions of a client, `max_subscriptions_per_client` defaults to 5
+const MAX_SUBSCRIPTIONS: usize = 5;
+
+const NEW_BLOCK_QUERY: &str = "tm.event='NewBlockHeader'";
+
+// events which change the reserves of a pool
+const POOL_QUERIES: [&str; 7] = [
+    "tm.event='Tx' AND token_swapped.module='gamm'",
+    "tm.event='Tx' AND pool_joined.module='gamm'",
+    "tm.event='Tx' AND pool_exited.module='gamm'",
+    "tm.event='Tx' AND token_swapped.module='concentratedliquidity'",
+    "tm.event='Tx' AND create_position.pool_id EXISTS",
+    "tm.event='Tx' AND withdraw_position.pool_id EXISTS",
+    "tm.event='Tx' AND wasm._contract_address EXISTS",
+];
+
+// queries of every connection, each one also follows the new blocks to know when a block is complete
+fn connection_queries() -> Vec<Vec<&'static str>> {
+    POOL_QUERIES
+        .chunks(MAX_SUBSCRIPTIONS - 1)
+        .map(|x| {
+            std::iter::once(NEW_BLOCK_QUERY)
+                .chain(x.iter().copied())
+                .collect()
+        })
+        .collect()
+}
+
+// event attributes holding the osmosis pool id or the executed contract
+const POOL_ID_ATTRIBUTES: [&str; 5] = [
+    "token_swapped.pool_id",
+    "pool_joined.pool_id",
+    "pool_exited.pool_id",
+    "create_position.pool_id",
+    "withdraw_position.pool_id",
+];
+const CONTRACT_ATTRIBUTES: [&str; 2] = ["wasm._contract_address", "execute._contract_address"];
+
+/// Pools touched by the transactions of a block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolEvents {
+    pub height: Option<u64>,
+    pub pool_ids: HashSet<u64>,
+    pub contracts: HashSet<String>,
+    pub new_block: bool,
+}
+
+impl PoolEvents {
+    pub fn is_empty(&self) -> bool {
+        self.pool_ids.is_empty() && self.contracts.is_empty()
+    }
+
+    fn merge(&mut self, other: PoolEvents) {
+        self.height = self.height.max(other.height);
+        self.pool_ids.extend(other.pool_ids);
+        self.contracts.extend(other.contracts);
+    }
+
+    fn affects(&self, pool: &dyn Pool) -> bool {
+        if let Ok(address) = pool.address() {
+            if self.contracts.contains(&address) {
+                return true;
+            }
+        }
+        match as_gamm_pool(pool) {
+            Some(x) => x.pool_id().is_ok_and(|x| self.pool_ids.contains(&x)),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SubscriptionMessage {
+    result: Option<SubscriptionResult>,
+    error: Option<RpcError>,
+}
+
+/// JSON-RPC error of the node, e.g. a rejected subscription.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)?;
+        if let Some(data) = &self.data {
+            write!(f, ": {}", data)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+#[derive(Debug, serde::Deserialize)]
+struct SubscriptionResult {
+    #[serde(default)]
+    events: HashMap<String, Vec<String>>,
+    data: Option<SubscriptionData>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SubscriptionData {
+    #[serde(rename = "type")]
+    kind: String,
+    value: Option<EventValue>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct EventValue {
+    header: Option<Header>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Header {
+    // int64 is a string in the json of tendermint
+    height: String,
+}
+
+/// Reads a message of a tendermint `subscribe` websocket.
+///
+/// Returns `None` for messages without events, like the empty answers to the subscribe requests,
+/// and an [`RpcError`] for error answers.
+pub fn parse_events(message: &str) -> Result<Option<PoolEvents>> {
+    let message: SubscriptionMessage = serde_json::from_str(message)?;
+    if let Some(error) = message.error {
+        return Err(error.into());
+    }
+    let result = match message.result {
+        Some(x) if !x.events.is_empty() || x.data.is_some() => x,
+        _ => return Ok(None),
+    };
+    let attribute = |key: &str| result.events.get(key).into_iter().flatten();
+    let mut events = PoolEvents {
+        new_block: result
+            .data
+            .as_ref()
+            .is_some_and(|x| x.kind == "tendermint/event/NewBlockHeader"),
+        ..Default::default()
+    };
+    let header = result
+        .data
+        .as_ref()
+        .and_then(|x| x.value.as_ref())
+        .and_then(|x| x.header.as_ref());
+    if let Some(height) = attribute("tx.height").next().or(header.map(|x| &x.height)) {
+        events.height = Some(height.parse::<u64>()?);
+    }
+    for key in POOL_ID_ATTRIBUTES {
+        for id in attribute(key) {
+            events.pool_ids.insert(id.parse::<u64>()?);
+        }
+    }
+    for key in CONTRACT_ATTRIBUTES {
+        events.contracts.extend(attribute(key).cloned());
+    }
+    Ok(Some(events))
+}
+
+// tendermint serves the websocket on /websocket of the rpc url
+fn websocket_url(rpc_url: &str) -> String {
+    let url = rpc_url.trim_end_matches('/');
+    let url = if let Some(x) = url.strip_prefix("https://") {
+        format!("wss://{}", x)
+    } else if let Some(x) = url.strip_prefix("http://") {
+        format!("ws://{}", x)
+    } else {
+        url.to_owned()
+    };
+    if url.ends_with("/websocket") {
+        url
+    } else {
+        format!("{}/websocket", url)
+    }
+}
+
+async fn refresh_affected(dex: &DexAgg, chain: &str, events: &PoolEvents) {
+    if events.is_empty() {
+        return;
+    }
+    let errors = dex
+        .refresh_pools_where(
+            |pool| pool.chain() == chain && events.affects(pool),
+            DEFAULT_REFRESH_CONCURRENCY,
+        )
+        .await;
+    if !errors.is_empty() {
+        warn!("Could not refresh {} pools: {}", errors.len(), errors[0]);
+    }
+}
+
+// connection to the websocket of `rpc_url` with a subscription for every query
+async fn connect(rpc_url: &str, queries: &[&str]) -> Result<Socket> {
+    let (mut socket, _) = connect_async(websocket_url(rpc_url)).await?;
+    for (id, query) in queries.iter().enumerate() {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "subscribe",
+            "id": id,
+            "params": { "query": query },
+        });
+        socket.send(Message::Text(request.to_string())).await?;
+    }
+    Ok(socket)
+}
+
+/// Listens to the pool events of `chain` on the rpc node at `rpc_url` until a connection closes.
+///
+/// The queries are spread over several connections to stay below the subscription limit of
+/// the node, a rejected subscription ends the subscription with an error. Affected pools are
+/// collected per block and connection and refreshed once the next block starts, only pools of
+/// `chain` are updated.
+pub async fn subscribe(dex: &DexAgg, chain: &str, rpc_url: &str) -> Result<()> {
+    let mut sockets = vec![];
+    for queries in connection_queries() {
+        sockets.push(connect(rpc_url, &queries).await?);
+    }
+    let mut pending = vec![PoolEvents::default(); sockets.len()];
+    // messages of all connections with the index of their connection
+    let mut messages = stream::select_all(
+        sockets
+            .into_iter()
+            .enumerate()
+            .map(|(index, socket)| socket.map(move |x| (index, x))),
+    );
+
+    let result = loop {
+        let (index, message) = match messages.next().await {
+            Some(x) => x,
+            None => break Ok(()),
+        };
+        let text = match message {
+            Ok(Message::Text(x)) => x,
+            Ok(Message::Close(_)) => break Ok(()),
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
+        };
+        // a single unreadable message does not end the subscription
+        let events = match parse_events(&text) {
+            Ok(Some(x)) => x,
+            Ok(None) => continue,
+            Err(e) if e.is::<RpcError>() => {
+                break Err(e.wrap_err(format!("Subscription to {} failed", rpc_url)))
+            }
+            Err(e) => {
+                warn!("Could not read event of {}: {}", chain, e);
+                continue;
+            }
+        };
+        let pending = &mut pending[index];
+        let next_block = match (pending.height, events.height) {
+            (Some(a), Some(b)) => b > a,
+            _ => false,
+        };
+        if events.new_block || next_block {
+            refresh_affected(dex, chain, pending).await;
+            *pending = PoolEvents::default();
+        }
+        pending.merge(events);
+    };
+    for events in &pending {
+        refresh_affected(dex, chain, events).await;
+    }
+    result
+}
+
+impl DexAgg {
+    /// Subscribes to the pool events of every chain with rpc urls, reconnects to the next
+    /// rpc node when the connection is lost.
+    pub fn spawn_subscribers(&self) -> Vec<JoinHandle<()>> {
+        let mut handles = vec![];
+        for (chain, config) in &self.config {
+            let urls = config.rpc.urls();
+            if urls.is_empty() {
+                continue;
+            }
+            let dex = self.clone();
+            let chain = chain.clone();
+            handles.push(tokio::spawn(async move {
+                for url in urls.iter().cycle() {
+                    let error = match subscribe(&dex, &chain, url).await {
+                        Ok(()) => eyre!("Connection closed"),
+                        Err(e) => e,
+                    };
+                    warn!("Subscription to {} ended: {}", url, error);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }));
+        }
+        handles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::config::{ChainConfig, TracyConfig};
+    use crate::pools::osmosis_pool::{load_osmo_pools_from_file_boxed, GammPool};
+    use crate::{PoolConfig, Quote};
+
+    const WASMSWAP_POOL: &str = "juno1qg9m2zdqaxx4udxxyun4cjq5myazlldymdmhkuy7fmvretyxz92q89zdvv";
+    const TERRASWAP_PAIR: &str = "juno1pair0000000000000000000000000000000000000000000000000000";
+
+    fn fixture_path(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    // messages of a juno websocket subscription, two blocks with swaps of two pools
+    fn fixture_messages() -> Vec<String> {
+        let text = std::fs::read_to_string(fixture_path("juno_events.json")).unwrap();
+        let messages: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        messages.iter().map(|x| x.to_string()).collect()
+    }
+
+    // pool which writes its address to `refreshed` when it is refreshed
+    #[derive(Clone)]
+    struct RefreshedPool {
+        chain: String,
+        address: String,
+        refreshed: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Pool for RefreshedPool {
+        async fn get_quote(
+            &self,
+            _amount: u128,
+            _token_in_denom: &str,
+            _token_out_denom: &str,
+            _config: &PoolConfig,
+        ) -> Result<Quote> {
+            Err(eyre!("Not quoted"))
+        }
+
+        async fn get_quote_exact_out(
+            &self,
+            _amount: u128,
+            _token_in_denom: &str,
+            _token_out_denom: &str,
+            _config: &PoolConfig,
+        ) -> Result<Quote> {
+            Err(eyre!("Not quoted"))
+        }
+
+        fn simulate_swap(&self, _amount: u128, _token_in: &str, _token_out: &str) -> Result<u128> {
+            Err(eyre!("Not quoted"))
+        }
+
+        fn simulate_swap_exact_out(
+            &self,
+            _amount: u128,
+            _token_in: &str,
+            _token_out: &str,
+        ) -> Result<u128> {
+            Err(eyre!("Not quoted"))
+        }
+
+        async fn refresh(&self, _config: &PoolConfig) -> Result<Box<dyn Pool>> {
+            self.refreshed.lock().unwrap().push(self.address.clone());
+            Ok(Box::new(self.clone()))
+        }
+
+        fn token_denoms(&self) -> Vec<String> {
+            vec![]
+        }
+
+        fn to_json(&self) -> String {
+            String::new()
+        }
+
+        fn chain(&self) -> String {
+            self.chain.clone()
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn address(&self) -> Result<String> {
+            Ok(self.address.clone())
+        }
+    }
+
+    fn juno_dex(refreshed: &Arc<Mutex<Vec<String>>>) -> DexAgg {
+        let pool = |chain: &str, address: &str| -> Box<dyn Pool> {
+            Box::new(RefreshedPool {
+                chain: chain.to_owned(),
+                address: address.to_owned(),
+                refreshed: refreshed.clone(),
+            })
+        };
+        let mut pools = vec![
+            pool("juno", WASMSWAP_POOL),
+            pool("juno", TERRASWAP_PAIR),
+            pool("juno", "juno1untouched"),
+            // same address on another chain
+            pool("osmosis", TERRASWAP_PAIR),
+        ];
+        let config = TracyConfig {
+            chains: vec![ChainConfig {
+                name: "juno".to_owned(),
+                chain_id: "juno-1".to_owned(),
+                bech32_prefix: "juno".to_owned(),
+                grpc_urls: vec![],
+                rest_urls: vec![],
+                rpc_urls: vec![],
+                pools: vec![],
+                estimate_quote: false,
+                connection: Default::default(),
+                asset_list: None,
+            }],
+            assets: fixture_path("missing_assets.json"),
+        };
+        DexAgg::from_config(&config, Some(&mut pools)).unwrap()
+    }
+
+    type ServerSocket = WebSocketStream<TcpStream>;
+
+    // fake rpc node which rejects subscriptions beyond `MAX_SUBSCRIPTIONS` of a connection and
+    // the `rejected` query like cometbft. Every connection gets the answers and the messages of
+    // its queries from `messages`, connections with pool events are closed afterwards, the
+    // others are returned open.
+    async fn serve(
+        messages: Vec<String>,
+        rejected: Option<&'static str>,
+    ) -> (String, JoinHandle<(Vec<Vec<String>>, Vec<ServerSocket>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut connections = vec![];
+            // the client connects one after another, in the order of `connection_queries`
+            for expected in connection_queries() {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let mut queries = vec![];
+                while queries.len() < expected.len() {
+                    if let Message::Text(x) = socket.next().await.unwrap().unwrap() {
+                        let request: serde_json::Value = serde_json::from_str(&x).unwrap();
+                        let query = request["params"]["query"].as_str().unwrap().to_owned();
+                        if queries.len() >= MAX_SUBSCRIPTIONS || rejected == Some(query.as_str()) {
+                            let error = serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "id": request["id"],
+                                "error": {
+                                    "code": -32603,
+                                    "message": "Internal error",
+                                    "data": "max_subscriptions_per_client 5 reached",
+                                },
+                            });
+                            socket.send(Message::Text(error.to_string())).await.unwrap();
+                        }
+                        queries.push(query);
+                    }
+                }
+                connections.push((queries, socket));
+            }
+
+            let mut all_queries = vec![];
+            let mut open = vec![];
+            for (queries, mut socket) in connections {
+                let mut pool_events = false;
+                for message in &messages {
+                    // unreadable messages go to every connection
+                    let value: serde_json::Value =
+                        serde_json::from_str(message).unwrap_or_default();
+                    let query = value["result"]["query"].as_str();
+                    if query.is_some_and(|x| !queries.iter().any(|y| y == x)) {
+                        continue;
+                    }
+                    pool_events |= query.is_some_and(|x| x != NEW_BLOCK_QUERY);
+                    socket.send(Message::Text(message.clone())).await.unwrap();
+                }
+                if pool_events {
+                    socket.close(None).await.unwrap();
+                } else {
+                    open.push(socket);
+                }
+                all_queries.push(queries);
+            }
+            (all_queries, open)
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn queries_fit_the_subscription_limit() {
+        let connections = connection_queries();
+        for queries in &connections {
+            assert!(queries.len() <= MAX_SUBSCRIPTIONS);
+            assert_eq!(queries[0], NEW_BLOCK_QUERY);
+        }
+        let pool_queries: Vec<&str> = connections.iter().flat_map(|x| x[1..].to_vec()).collect();
+        assert_eq!(pool_queries, POOL_QUERIES);
+    }
+
+    #[test]
+    fn parse_fixture_events() {
+        let messages = fixture_messages();
+        assert_eq!(parse_events(&messages[0]).unwrap(), None);
+        assert_eq!(
+            parse_events(&messages[2]).unwrap(),
+            Some(PoolEvents {
+                height: Some(14250100),
+                new_block: true,
+                ..Default::default()
+            })
+        );
+        let events = parse_events(&messages[3]).unwrap().unwrap();
+        assert_eq!(events.height, Some(14250100));
+        assert!(!events.new_block);
+        assert!(events.contracts.contains(WASMSWAP_POOL));
+        assert!(parse_events("{").is_err());
+
+        let error = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "error": {
+                "code": -32603,
+                "message": "Internal error",
+                "data": "max_subscriptions_per_client 5 reached",
+            },
+        });
+        let err = parse_events(&error.to_string()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RpcError>(),
+            Some(&RpcError {
+                code: -32603,
+                message: "Internal error".to_owned(),
+                data: Some("max_subscriptions_per_client 5 reached".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn events_affect_pools_by_id_and_contract() {
+        let pools = load_osmo_pools_from_file_boxed(Path::new(&fixture_path("osmosis_pools.json")))
+            .unwrap();
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "events": {
+                    "tm.event": ["Tx"],
+                    "tx.height": ["7"],
+                    "token_swapped.pool_id": ["1"],
+                }
+            }
+        });
+        let events = parse_events(&message.to_string()).unwrap().unwrap();
+        let affected: Vec<u64> = pools
+            .iter()
+            .filter(|x| events.affects(x.as_ref()))
+            .map(|x| x.pool_id().unwrap())
+            .collect();
+        assert_eq!(affected, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn subscribe_refreshes_pools_of_finished_blocks() {
+        let mut messages = fixture_messages();
+        // unreadable messages are skipped
+        messages.insert(3, "{".to_owned());
+        let (url, server) = serve(messages, None).await;
+        let refreshed = Arc::new(Mutex::new(vec![]));
+        let dex = juno_dex(&refreshed);
+
+        subscribe(&dex, "juno", &url).await.unwrap();
+
+        let (queries, _open) = server.await.unwrap();
+        let expected: Vec<Vec<String>> = connection_queries()
+            .iter()
+            .map(|x| x.iter().map(|x| x.to_string()).collect())
+            .collect();
+        assert_eq!(queries, expected);
+        // once per block, the pool swapped twice in the first block is refreshed once
+        assert_eq!(
+            *refreshed.lock().unwrap(),
+            vec![WASMSWAP_POOL.to_owned(), TERRASWAP_PAIR.to_owned()]
+        );
+        let last_updated = dex.last_updated.lock().await;
+        assert!(last_updated.contains_key(WASMSWAP_POOL));
+        assert!(!last_updated.contains_key("juno1untouched"));
+    }
+
+    #[tokio::test]
+    async fn rejected_subscription_is_an_error() {
+        let wasm_query = POOL_QUERIES[POOL_QUERIES.len() - 1];
+        let (url, server) = serve(vec![], Some(wasm_query)).await;
+        let refreshed = Arc::new(Mutex::new(vec![]));
+        let dex = juno_dex(&refreshed);
+
+        let err = subscribe(&dex, "juno", &url).await.unwrap_err();
+        assert_eq!(err.to_string(), format!("Subscription to {} failed", url));
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Internal error (-32603): max_subscriptions_per_client 5 reached"
+        );
+        assert!(refreshed.lock().unwrap().is_empty());
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+use eyre::{eyre, Result};
+use prost_types::Any;
+use serde::Serialize;
+
+use super::{min_amount_out, to_any};
+use crate::decimal::Dec;
+use crate::pools::juno_pool::{JunoDenom, WasmPool};
+use crate::util::proto::cosmos_base_v1beta1::Coin;
+use crate::util::proto::cosmwasm_wasm_v1::MsgExecuteContract;
+use crate::Quote;
+
+pub const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum TokenSelect {
+    Token1,
+    Token2,
+}
+
+/// cw-utils `Expiration`, the swap fails if it is executed after this point.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    AtHeight(u64),
+    // unix timestamp in nanoseconds, serialized as string like cosmwasm `Timestamp`
+    AtTime(String),
+    Never {},
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum WasmSwapExecuteMsg {
+    Swap {
+        input_token: TokenSelect,
+        input_amount: String,
+        min_output: String,
+        expiration: Option<Expiration>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Cw20ExecuteMsg {
+    Send {
+        contract: String,
+        amount: String,
+        // base64 encoded hook message for the receiving contract
+        msg: String,
+    },
+}
+
+fn input_token(
+    pool: &WasmPool,
+    token_in_denom: &str,
+    token_out_denom: &str,
+) -> Result<TokenSelect> {
+    if pool.is_token1(token_in_denom) && pool.is_token2(token_out_denom) {
+        Ok(TokenSelect::Token1)
+    } else if pool.is_token2(token_in_denom) && pool.is_token1(token_out_denom) {
+        Ok(TokenSelect::Token2)
+    } else {
+        Err(eyre!(
+            "Cannot find pair: {} | {}",
+            token_in_denom,
+            token_out_denom
+        ))
+    }
+}
+
+/// Builds the `swap` execution of a WasmSwap pool.
+///
+/// Native inputs are sent as funds to the pool, cw20 inputs are sent to the pool by
+/// executing `send` on the token contract with the swap as hook message.
+pub fn swap_msg(
+    sender: &str,
+    pool: &WasmPool,
+    token_in_denom: &str,
+    token_out_denom: &str,
+    amount_in: u128,
+    min_output: u128,
+    expiration: Option<Expiration>,
+) -> Result<MsgExecuteContract> {
+    let pool_address = pool
+        .pool_address
+        .clone()
+        .ok_or_else(|| eyre!("Pool has no address"))?;
+    let input_token = input_token(pool, token_in_denom, token_out_denom)?;
+    let input_denom: &JunoDenom = match input_token {
+        TokenSelect::Token1 => &pool.token1_denom,
+        TokenSelect::Token2 => &pool.token2_denom,
+    };
+    let swap = WasmSwapExecuteMsg::Swap {
+        input_token,
+        input_amount: amount_in.to_string(),
+        min_output: min_output.to_string(),
+        expiration,
+    };
+
+    if let Some(cw20) = input_denom.cw20_address() {
+        let send = Cw20ExecuteMsg::Send {
+            contract: pool_address,
+            amount: amount_in.to_string(),
+            msg: base64::encode(serde_json::to_vec(&swap)?),
+        };
+        return Ok(MsgExecuteContract {
+            sender: sender.to_owned(),
+            contract: cw20.to_owned(),
+            msg: serde_json::to_vec(&send)?,
+            funds: vec![],
+        });
+    }
+    let native = input_denom
+        .native
+        .clone()
+        .ok_or_else(|| eyre!("Token {} has no denom", token_in_denom))?;
+    Ok(MsgExecuteContract {
+        sender: sender.to_owned(),
+        contract: pool_address,
+        msg: serde_json::to_vec(&swap)?,
+        funds: vec![Coin {
+            denom: native,
+            amount: amount_in.to_string(),
+        }],
+    })
+}
+
+/// [`swap_msg`] for a quote of `pool`, the minimum output is derived from `slippage`.
+pub fn swap_msg_for_quote(
+    sender: &str,
+    pool: &WasmPool,
+    token_in_denom: &str,
+    token_out_denom: &str,
+    quote: &Quote,
+    slippage: Dec,
+    expiration: Option<Expiration>,
+) -> Result<MsgExecuteContract> {
+    let token_in = quote
+        .token_in
+        .ok_or_else(|| eyre!("Quote has no token in amount"))?;
+    let token_out = quote
+        .token_out
+        .ok_or_else(|| eyre!("Quote has no token out amount"))?;
+    swap_msg(
+        sender,
+        pool,
+        token_in_denom,
+        token_out_denom,
+        token_in,
+        min_amount_out(token_out, slippage)?,
+        expiration,
+    )
+}
+
+pub fn execute_contract_any(msg: &MsgExecuteContract) -> Any {
+    to_any(msg, MSG_EXECUTE_CONTRACT_TYPE_URL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL: &str = "juno1qg9m2zdqaxx4udxxyun4cjq5myazlldymdmhkuy7fmvretyxz92q89zdvv";
+    const SFOT: &str = "juno17c7zyezg3m8p2tf9hqgue9jhahvle70d59e8j9nmrvhw9anrpk8qxlrghx";
+    const UUSD: &str = "ibc/2DA4136457810BCB9DAAB620CA67BC342B17C3C70151CA70490A170DF7C9CB27";
+    const SENDER: &str = "juno1sender";
+
+    // sFOT (cw20) / uusd (native) pool
+    fn fixture_pool() -> WasmPool {
+        let path = format!(
+            "{}/tests/fixtures/juno_pools.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let text = std::fs::read_to_string(path).unwrap();
+        let pools: Vec<WasmPool> = serde_json::from_str(&text).unwrap();
+        pools
+            .into_iter()
+            .find(|x| x.pool_address.as_deref() == Some(POOL))
+            .unwrap()
+    }
+
+    fn msg_json(msg: &MsgExecuteContract) -> String {
+        String::from_utf8(msg.msg.clone()).unwrap()
+    }
+
+    #[test]
+    fn native_input_is_sent_as_funds() {
+        let sfot = format!("cw20:{}", SFOT);
+        let msg = swap_msg(
+            SENDER,
+            &fixture_pool(),
+            UUSD,
+            &sfot,
+            100_000,
+            2_000_000,
+            None,
+        )
+        .unwrap();
+        assert_eq!(msg.sender, SENDER);
+        assert_eq!(msg.contract, POOL);
+        assert_eq!(
+            msg_json(&msg),
+            r#"{"swap":{"input_token":"Token2","input_amount":"100000","min_output":"2000000","expiration":null}}"#
+        );
+        assert_eq!(
+            msg.funds,
+            vec![Coin {
+                denom: UUSD.to_owned(),
+                amount: "100000".to_owned()
+            }]
+        );
+    }
+
+    fn assert_cw20_send(msg: &MsgExecuteContract) {
+        assert_eq!(msg.sender, SENDER);
+        assert_eq!(msg.contract, SFOT);
+        // the hook message is {"swap":{"input_token":"Token1","input_amount":"1000000000000","min_output":"98000","expiration":null}}
+        assert_eq!(
+            msg_json(msg),
+            format!(
+                r#"{{"send":{{"contract":"{}","amount":"1000000000000","msg":"{}"}}}}"#,
+                POOL,
+                "eyJzd2FwIjp7ImlucHV0X3Rva2VuIjoiVG9rZW4xIiwiaW5wdXRfYW1vdW50IjoiMTAwMDAwMDAwMDAwMCIsIm1pbl9vdXRwdXQiOiI5ODAwMCIsImV4cGlyYXRpb24iOm51bGx9fQ=="
+            )
+        );
+        assert!(msg.funds.is_empty());
+    }
+
+    #[test]
+    fn cw20_input_is_sent_through_the_token_contract() {
+        let sfot = format!("cw20:{}", SFOT);
+        let msg = swap_msg(
+            SENDER,
+            &fixture_pool(),
+            &sfot,
+            UUSD,
+            1_000_000_000_000,
+            98_000,
+            None,
+        )
+        .unwrap();
+        assert_cw20_send(&msg);
+    }
+
+    #[test]
+    fn cw20_input_listed_as_native_is_sent_through_the_token_contract() {
+        let mut pool = fixture_pool();
+        pool.token1_denom = JunoDenom {
+            native: Some(SFOT.to_owned()),
+            cw20: None,
+        };
+        let sfot = format!("cw20:{}", SFOT);
+        let msg = swap_msg(SENDER, &pool, &sfot, UUSD, 1_000_000_000_000, 98_000, None).unwrap();
+        assert_cw20_send(&msg);
+    }
+}
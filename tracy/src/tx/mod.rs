@@ -0,0 +1,365 @@
+use eyre::{eyre, Result};
+use prost::Message;
+use prost_types::Any;
+
+use crate::decimal::Dec;
+use crate::util::endpoint::EndpointPool;
+use crate::util::proto::cosmos_base_v1beta1::Coin;
+use crate::util::proto::cosmos_crypto_secp256k1::PubKey;
+use crate::util::proto::cosmos_tx_signing_v1beta1::SignMode;
+use crate::util::proto::cosmos_tx_v1beta1::{
+    mode_info, AuthInfo, Fee, ModeInfo, SignDoc, SignerInfo, TxBody, TxRaw,
+};
+
+pub mod juno;
+pub mod osmosis;
+pub mod signer;
+
+use signer::Signer;
+
+const PUB_KEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+/// Everything besides the messages that goes into a signed transaction.
+#[derive(Debug, Clone)]
+pub struct TxOptions {
+    pub chain_id: String,
+    pub account_number: u64,
+    pub sequence: u64,
+    pub fee: Coin,
+    pub gas_limit: u64,
+    pub memo: String,
+    // 0 disables the timeout
+    pub timeout_height: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BroadcastResult {
+    pub txhash: String,
+    pub code: u32,
+    pub raw_log: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct BroadcastResponse {
+    tx_response: BroadcastResult,
+}
+
+pub fn to_any<M: Message>(msg: &M, type_url: &str) -> Any {
+    Any {
+        type_url: type_url.to_owned(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+fn check_slippage(slippage: Dec) -> Result<()> {
+    if slippage.is_negative() || slippage >= Dec::ONE {
+        return Err(eyre!("Invalid slippage: {}", slippage));
+    }
+    Ok(())
+}
+
+// slippage is a fraction, 0.01 allows 1% less than quoted. Rounded down, the bound never
+// exceeds the quote.
+pub fn min_amount_out(amount: u128, slippage: Dec) -> Result<u128> {
+    check_slippage(slippage)?;
+    Dec::from(amount)
+        .mul_truncate(Dec::ONE.checked_sub(slippage)?)?
+        .truncate_int()
+        .to_u128()
+}
+
+// rounded up, the bound is never below the quote
+pub fn max_amount_in(amount: u128, slippage: Dec) -> Result<u128> {
+    check_slippage(slippage)?;
+    Dec::from(amount)
+        .mul_round_up(Dec::ONE.checked_add(slippage)?)?
+        .ceil_int()
+        .to_u128()
+}
+
+/// The SIGN_MODE_DIRECT document of `messages` which the signer signs in [`sign_tx`].
+pub fn sign_doc(signer: &Signer, messages: Vec<Any>, options: &TxOptions) -> Result<SignDoc> {
+    if messages.is_empty() {
+        return Err(eyre!("Cannot sign transaction without messages"));
+    }
+    let body = TxBody {
+        messages,
+        memo: options.memo.clone(),
+        timeout_height: options.timeout_height,
+        extension_options: vec![],
+        non_critical_extension_options: vec![],
+    };
+    let public_key = PubKey {
+        key: signer.public_key(),
+    };
+    let auth_info = AuthInfo {
+        signer_infos: vec![SignerInfo {
+            public_key: Some(to_any(&public_key, PUB_KEY_TYPE_URL)),
+            mode_info: Some(ModeInfo {
+                sum: Some(mode_info::Sum::Single(mode_info::Single {
+                    mode: SignMode::Direct as i32,
+                })),
+            }),
+            sequence: options.sequence,
+        }],
+        fee: Some(Fee {
+            amount: vec![options.fee.clone()],
+            gas_limit: options.gas_limit,
+            payer: "".to_owned(),
+            granter: "".to_owned(),
+        }),
+    };
+    Ok(SignDoc {
+        body_bytes: body.encode_to_vec(),
+        auth_info_bytes: auth_info.encode_to_vec(),
+        chain_id: options.chain_id.clone(),
+        account_number: options.account_number,
+    })
+}
+
+/// Builds a SIGN_MODE_DIRECT transaction and returns the encoded `TxRaw`, ready to broadcast.
+///
+/// The output only depends on the inputs, signing the same messages with the same key
+/// and options always returns the same bytes.
+pub fn sign_tx(signer: &Signer, messages: Vec<Any>, options: &TxOptions) -> Result<Vec<u8>> {
+    let sign_doc = sign_doc(signer, messages, options)?;
+    let signature = signer.sign(&sign_doc.encode_to_vec());
+    let tx = TxRaw {
+        body_bytes: sign_doc.body_bytes,
+        auth_info_bytes: sign_doc.auth_info_bytes,
+        signatures: vec![signature],
+    };
+    Ok(tx.encode_to_vec())
+}
+
+// account number and sequence are needed to sign, works for base and vesting accounts
+pub async fn fetch_account(rest: &EndpointPool, address: &str) -> Result<(u64, u64)> {
+    let resp: serde_json::Value = rest
+        .run(|url| async move {
+            let url = format!("{}/cosmos/auth/v1beta1/accounts/{}", url, address);
+            Ok(rest
+                .connections()
+                .http()
+                .get(url)
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await?;
+    let account = &resp["account"];
+    let account = if account["account_number"].is_string() {
+        account
+    } else {
+        &account["base_vesting_account"]["base_account"]
+    };
+    let account_number = account["account_number"]
+        .as_str()
+        .ok_or_else(|| eyre!("Cannot find account {}: {}", address, resp))?;
+    let sequence = account["sequence"].as_str().unwrap_or("0");
+    Ok((account_number.parse::<u64>()?, sequence.parse::<u64>()?))
+}
+
+// another node only gets the same signed bytes, so a tx cannot be included twice
+pub async fn broadcast_tx(rest: &EndpointPool, tx_bytes: &[u8]) -> Result<BroadcastResult> {
+    let body = serde_json::json!({
+        "tx_bytes": base64::encode(tx_bytes),
+        "mode": "BROADCAST_MODE_SYNC",
+    });
+    let resp: BroadcastResponse = rest
+        .run(|url| {
+            let body = &body;
+            async move {
+                let url = format!("{}/cosmos/tx/v1beta1/txs", url);
+                let http = rest.connections().http();
+                Ok(http.post(url).json(body).send().await?.json().await?)
+            }
+        })
+        .await?;
+    if resp.tx_response.code != 0 {
+        return Err(eyre!(
+            "Transaction {} failed: {}",
+            resp.tx_response.txhash,
+            resp.tx_response.raw_log
+        ));
+    }
+    Ok(resp.tx_response)
+}
+
+/// Looks up the signer's account, signs `messages` and broadcasts them to the nodes of `rest`.
+pub async fn sign_and_broadcast(
+    rest: &EndpointPool,
+    signer: &Signer,
+    prefix: &str,
+    messages: Vec<Any>,
+    options: TxOptions,
+) -> Result<BroadcastResult> {
+    let (account_number, sequence) = fetch_account(rest, &signer.address(prefix)?).await?;
+    let options = TxOptions {
+        account_number,
+        sequence,
+        ..options
+    };
+    let tx_bytes = sign_tx(signer, messages, &options)?;
+    broadcast_tx(rest, &tx_bytes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::osmosis::swap_exact_amount_in_any;
+    use crate::util::proto::osmosis_gamm_v1beta1::{MsgSwapExactAmountIn, SwapAmountInRoute};
+
+    // test mnemonic of cosmjs, its cosmos address is published with it. The expected bytes
+    // were built with a separate protobuf encoder and RFC 6979 secp256k1 implementation.
+    const MNEMONIC: &str =
+        "enlist hip relief stomach skate base shallow young switch frequent cry park";
+    const PRIVATE_KEY: &str = "1bdd5c2105f62c51d72c90d9e5ca6854a94337bcbcbb0b959846b85813d69380";
+    const ATOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    const SIGN_DOC: &str = "0acc010ac2010a2a2f6f736d6f7369732e67616d6d2e763162657461312e4d7367537761704578616374416d6f756e74496e1293010a2b6f736d6f313471656d7130767736793367633375336530617479326537363475346773356c333279646d30120908011205756f736d6f1a4f0a446962632f32373339344642303932443245434344353631323343373446333645344331463932363030314345414441394341393745413632324232354634314535454232120731303030303030220831313537393939391205747261637912670a500a460a1f2f636f736d6f732e63727970746f2e736563703235366b312e5075624b657912230a2103d7178566fc669a82ce403c75008f576f4a75e44b577ad4631ae41ee74b6d0b5212040a020801180712130a0d0a05756f736d6f1204323530301090a10f1a096f736d6f7369732d3120b960";
+    const SIGNATURE: &str = "440fbc767e5ab5865eb22117096a5e2edf1e1fc69cfc08842b597c31dd0381916d2896976f2d10b9820138e23774dfef2b7fdf2619593e19d776b9ec2a5f7b91";
+
+    fn signer() -> Signer {
+        Signer::from_mnemonic(MNEMONIC, None).unwrap()
+    }
+
+    fn swap(sender: &str) -> Any {
+        swap_exact_amount_in_any(&MsgSwapExactAmountIn {
+            sender: sender.to_owned(),
+            routes: vec![SwapAmountInRoute {
+                pool_id: 1,
+                token_out_denom: "uosmo".to_owned(),
+            }],
+            token_in: Some(Coin {
+                denom: ATOM.to_owned(),
+                amount: "1000000".to_owned(),
+            }),
+            token_out_min_amount: "11579999".to_owned(),
+        })
+    }
+
+    fn options() -> TxOptions {
+        TxOptions {
+            chain_id: "osmosis-1".to_owned(),
+            account_number: 12345,
+            sequence: 7,
+            fee: Coin {
+                denom: "uosmo".to_owned(),
+                amount: "2500".to_owned(),
+            },
+            gas_limit: 250_000,
+            memo: "tracy".to_owned(),
+            timeout_height: 0,
+        }
+    }
+
+    #[test]
+    fn mnemonic_and_hex_key_give_the_same_address() {
+        let signer = signer();
+        assert_eq!(
+            signer.address("cosmos").unwrap(),
+            "cosmos14qemq0vw6y3gc3u3e0aty2e764u4gs5le3hada"
+        );
+        assert_eq!(
+            signer.address("osmo").unwrap(),
+            "osmo14qemq0vw6y3gc3u3e0aty2e764u4gs5l32ydm0"
+        );
+        assert_eq!(
+            Signer::from_hex(PRIVATE_KEY).unwrap().public_key(),
+            signer.public_key()
+        );
+    }
+
+    #[test]
+    fn sign_doc_bytes() {
+        let signer = signer();
+        let sender = signer.address("osmo").unwrap();
+        let doc = sign_doc(&signer, vec![swap(&sender)], &options()).unwrap();
+        assert_eq!(hex::encode(doc.encode_to_vec()), SIGN_DOC);
+    }
+
+    #[test]
+    fn signed_tx_bytes() {
+        let signer = signer();
+        let sender = signer.address("osmo").unwrap();
+        let tx_bytes = sign_tx(&signer, vec![swap(&sender)], &options()).unwrap();
+        let tx = TxRaw::decode(tx_bytes.as_slice()).unwrap();
+        assert_eq!(tx.signatures, vec![hex::decode(SIGNATURE).unwrap()]);
+
+        let doc = hex::decode(SIGN_DOC).unwrap();
+        let doc = SignDoc::decode(doc.as_slice()).unwrap();
+        let expected = TxRaw {
+            body_bytes: doc.body_bytes,
+            auth_info_bytes: doc.auth_info_bytes,
+            signatures: vec![hex::decode(SIGNATURE).unwrap()],
+        };
+        assert_eq!(tx_bytes, expected.encode_to_vec());
+        // signing again gives the same bytes
+        assert_eq!(
+            sign_tx(&signer, vec![swap(&sender)], &options()).unwrap(),
+            tx_bytes
+        );
+    }
+
+    #[test]
+    fn signatures_have_low_s() {
+        let signer = signer();
+        // RFC 6979 gives a low s for this message
+        assert_eq!(
+            hex::encode(signer.sign(b"tracy 0")),
+            "1cefebe430b872b10f62e008f9437332d56087257f6fbdf36f3269549be6919a0319e6f12cfc9117a2a29e05c9b07aa8a8527a7eeffd042202d026bbea8f4d3a"
+        );
+        // and a high one for this, which is negated
+        assert_eq!(
+            hex::encode(signer.sign(b"tracy 1")),
+            "ba6c342d6eb412add91c9ce5dbb14dbef0abc99d8aa68c4b973008992f1b578337f08f5d828a5af7691b97ac22a7b748da440c496d3e332f789bc628ebc07bd5"
+        );
+    }
+
+    #[test]
+    fn sign_tx_needs_messages() {
+        assert!(sign_tx(&signer(), vec![], &options()).is_err());
+    }
+
+    #[test]
+    fn slippage_bounds() {
+        let slippage = |x: &str| x.parse::<Dec>().unwrap();
+        assert_eq!(min_amount_out(100, slippage("0.01")).unwrap(), 99);
+        assert_eq!(min_amount_out(199, slippage("0.005")).unwrap(), 198);
+        assert_eq!(max_amount_in(100, slippage("0.01")).unwrap(), 101);
+        assert_eq!(max_amount_in(199, slippage("0.005")).unwrap(), 200);
+        assert_eq!(min_amount_out(0, slippage("0.01")).unwrap(), 0);
+    }
+
+    #[test]
+    fn slippage_bounds_above_f64_precision() {
+        let slippage = |x: &str| x.parse::<Dec>().unwrap();
+        // 2^53 + 1 is the first integer f64 cannot represent
+        let amount = (1u128 << 53) + 1;
+        assert_eq!(min_amount_out(amount, Dec::ZERO).unwrap(), amount);
+        assert_eq!(max_amount_in(amount, Dec::ZERO).unwrap(), amount);
+        // 18 decimal token amounts
+        let amount = 1_234_567_890_123_456_789_012_345_679;
+        assert_eq!(
+            min_amount_out(amount, slippage("0.005")).unwrap(),
+            1_228_395_050_672_839_505_067_283_950
+        );
+        assert_eq!(
+            max_amount_in(amount, slippage("0.005")).unwrap(),
+            1_240_740_729_574_074_072_957_407_408
+        );
+        assert_eq!(min_amount_out(u128::MAX, Dec::ZERO).unwrap(), u128::MAX);
+        assert!(min_amount_out(u128::MAX, slippage("0.01")).unwrap() < u128::MAX);
+        assert_eq!(max_amount_in(u128::MAX, Dec::ZERO).unwrap(), u128::MAX);
+        assert!(max_amount_in(u128::MAX, slippage("0.01")).is_err());
+    }
+
+    #[test]
+    fn invalid_slippage() {
+        let slippage = |x: &str| x.parse::<Dec>().unwrap();
+        assert!(min_amount_out(100, slippage("-0.01")).is_err());
+        assert!(min_amount_out(100, Dec::ONE).is_err());
+        assert!(max_amount_in(100, slippage("1.5")).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use eyre::{eyre, Result};
+use prost_types::Any;
+
+use super::{max_amount_in, min_amount_out, to_any};
+use crate::decimal::Dec;
+use crate::pools::osmosis_pool::{as_gamm_pool, GammPool};
+use crate::router::Route;
+use crate::util::proto::cosmos_base_v1beta1::Coin;
+use crate::util::proto::osmosis_gamm_v1beta1::{
+    MsgSwapExactAmountIn, MsgSwapExactAmountOut, SwapAmountInRoute, SwapAmountOutRoute,
+};
+use crate::{Pool, Quote};
+
+pub const MSG_SWAP_EXACT_AMOUNT_IN_TYPE_URL: &str = "/osmosis.gamm.v1beta1.MsgSwapExactAmountIn";
+pub const MSG_SWAP_EXACT_AMOUNT_OUT_TYPE_URL: &str = "/osmosis.gamm.v1beta1.MsgSwapExactAmountOut";
+
+fn osmosis_pool<'a>(pools: &'a [Box<dyn Pool>], address: &str) -> Result<&'a dyn GammPool> {
+    pools
+        .iter()
+        .find(|x| x.address().map(|x| x == address).unwrap_or(false))
+        .and_then(|x| as_gamm_pool(x.as_ref()))
+        .ok_or_else(|| eyre!("No osmosis pool with address {}", address))
+}
+
+fn chain_denom(pool: &dyn GammPool, denom: &str) -> Result<String> {
+    pool.chain_denom(denom).ok_or_else(|| {
+        eyre!(
+            "Pool {} does not contain {}",
+            pool.pool_id().unwrap_or_default(),
+            denom
+        )
+    })
+}
+
+/// Builds a swap over every hop of `route`, osmosis executes multi-hop routes in one message.
+pub fn swap_exact_amount_in_msg(
+    sender: &str,
+    route: &Route,
+    pools: &[Box<dyn Pool>],
+    slippage: Dec,
+) -> Result<MsgSwapExactAmountIn> {
+    let first = route
+        .hops
+        .first()
+        .ok_or_else(|| eyre!("Cannot swap an empty route"))?;
+    let token_in_denom = chain_denom(
+        osmosis_pool(pools, &first.pool_address)?,
+        &first.token_in_denom,
+    )?;
+    let mut routes = vec![];
+    for hop in &route.hops {
+        let pool = osmosis_pool(pools, &hop.pool_address)?;
+        routes.push(SwapAmountInRoute {
+            pool_id: pool.pool_id()?,
+            token_out_denom: chain_denom(pool, &hop.token_out_denom)?,
+        });
+    }
+    Ok(MsgSwapExactAmountIn {
+        sender: sender.to_owned(),
+        routes,
+        token_in: Some(Coin {
+            denom: token_in_denom,
+            amount: route.amount_in.to_string(),
+        }),
+        token_out_min_amount: min_amount_out(route.amount_out, slippage)?.to_string(),
+    })
+}
+
+/// Builds a swap for an exact out quote of `pool`, see [`Pool::get_quote_exact_out`].
+pub fn swap_exact_amount_out_msg(
+    sender: &str,
+    pool: &dyn GammPool,
+    token_in_denom: &str,
+    token_out_denom: &str,
+    quote: &Quote,
+    slippage: Dec,
+) -> Result<MsgSwapExactAmountOut> {
+    let token_in = quote
+        .token_in
+        .ok_or_else(|| eyre!("Quote has no token in amount"))?;
+    let token_out = quote
+        .token_out
+        .ok_or_else(|| eyre!("Quote has no token out amount"))?;
+    Ok(MsgSwapExactAmountOut {
+        sender: sender.to_owned(),
+        routes: vec![SwapAmountOutRoute {
+            pool_id: pool.pool_id()?,
+            token_in_denom: chain_denom(pool, token_in_denom)?,
+        }],
+        token_in_max_amount: max_amount_in(token_in, slippage)?.to_string(),
+        token_out: Some(Coin {
+            denom: chain_denom(pool, token_out_denom)?,
+            amount: token_out.to_string(),
+        }),
+    })
+}
+
+pub fn swap_exact_amount_in_any(msg: &MsgSwapExactAmountIn) -> Any {
+    to_any(msg, MSG_SWAP_EXACT_AMOUNT_IN_TYPE_URL)
+}
+
+pub fn swap_exact_amount_out_any(msg: &MsgSwapExactAmountOut) -> Any {
+    to_any(msg, MSG_SWAP_EXACT_AMOUNT_OUT_TYPE_URL)
+}
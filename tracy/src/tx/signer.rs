@@ -0,0 +1,61 @@
+use bech32::{ToBase32, Variant};
+use bip32::XPrv;
+use bip39::Mnemonic;
+use eyre::{eyre, Result};
+use k256::ecdsa::{signature::Signer as _, Signature, SigningKey};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+// coin type 118 is shared by all cosmos sdk chains we support
+pub const COSMOS_DERIVATION_PATH: &str = "m/44'/118'/0'/0/0";
+
+/// Local secp256k1 key used to sign transactions.
+#[derive(Clone)]
+pub struct Signer {
+    key: SigningKey,
+}
+
+impl Signer {
+    pub fn from_mnemonic(phrase: &str, derivation_path: Option<&str>) -> Result<Self> {
+        let mnemonic = Mnemonic::parse_normalized(phrase.trim())
+            .map_err(|x| eyre!("Invalid mnemonic: {}", x))?;
+        let path = derivation_path
+            .unwrap_or(COSMOS_DERIVATION_PATH)
+            .parse()
+            .map_err(|x| eyre!("Invalid derivation path: {}", x))?;
+        let xprv = XPrv::derive_from_path(mnemonic.to_seed(""), &path)
+            .map_err(|x| eyre!("Cannot derive key: {}", x))?;
+        Ok(Signer {
+            key: xprv.private_key().clone(),
+        })
+    }
+
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let bytes = hex::decode(private_key.trim().trim_start_matches("0x"))?;
+        let key =
+            SigningKey::from_slice(&bytes).map_err(|x| eyre!("Invalid private key: {}", x))?;
+        Ok(Signer { key })
+    }
+
+    // compressed sec1 encoding, what cosmos expects in `PubKey`
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    pub fn address(&self, prefix: &str) -> Result<String> {
+        let hash = Ripemd160::digest(Sha256::digest(self.public_key()));
+        Ok(bech32::encode(prefix, hash.to_base32(), Variant::Bech32)?)
+    }
+
+    /// Signs sha256(`message`) with RFC 6979 nonces, so the signature is deterministic.
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let signature: Signature = self.key.sign(message);
+        // cosmos rejects high s values
+        let signature = signature.normalize_s().unwrap_or(signature);
+        signature.to_bytes().to_vec()
+    }
+}
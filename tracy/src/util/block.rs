@@ -0,0 +1,43 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::endpoint::EndpointPool;
+
+/// Height and time of a block, the time as RFC 3339 like in the block header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub height: u64,
+    pub time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockHeader {
+    // int64 is a string in the REST api
+    height: String,
+    time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    header: BlockHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestBlockResponse {
+    block: Block,
+}
+
+/// Latest block of the chain behind `api`.
+pub async fn latest_block(api: &EndpointPool) -> Result<BlockInfo> {
+    let response: LatestBlockResponse = api
+        .run(|url| async move {
+            let url = format!("{}/cosmos/base/tendermint/v1beta1/blocks/latest", url);
+            let http = api.connections().http();
+            Ok(http.get(url).send().await?.json().await?)
+        })
+        .await?;
+    Ok(BlockInfo {
+        height: response.block.header.height.parse()?,
+        time: response.block.header.time,
+    })
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tonic::transport::Channel;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ConnectionOptions {
+    // whole request, a slow node counts as failed so the next one gets a chance
+    pub timeout_ms: u64,
+    pub connect_timeout_ms: u64,
+    // requests in flight per chain, further requests wait for a free slot
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            timeout_ms: 10_000,
+            connect_timeout_ms: 5_000,
+            max_concurrent_requests: 16,
+        }
+    }
+}
+
+/// Long-lived connections to the nodes of a chain.
+///
+/// Holds one pooled HTTP client and one gRPC channel per url, so quotes reuse open
+/// (TLS) connections instead of connecting for every request. Clones share the connections.
+#[derive(Clone)]
+pub struct ConnectionManager {
+    options: ConnectionOptions,
+    http: reqwest::Client,
+    channels: Arc<Mutex<HashMap<String, Channel>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl ConnectionManager {
+    pub fn new(options: ConnectionOptions) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(options.timeout_ms))
+            .connect_timeout(Duration::from_millis(options.connect_timeout_ms))
+            .build()?;
+        Ok(ConnectionManager {
+            options,
+            http,
+            channels: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(options.max_concurrent_requests.max(1))),
+        })
+    }
+
+    pub fn options(&self) -> ConnectionOptions {
+        self.options
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.options.timeout_ms)
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Channel to a gRPC url, connects lazily on first use and reconnects on its own.
+    pub fn channel(&self, url: &str) -> Result<Channel> {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(channel) = channels.get(url) {
+            return Ok(channel.clone());
+        }
+        let channel = tonic::transport::Endpoint::from_shared(url.to_owned())?
+            .timeout(self.timeout())
+            .connect_timeout(Duration::from_millis(self.options.connect_timeout_ms))
+            .connect_lazy();
+        channels.insert(url.to_owned(), channel.clone());
+        Ok(channel)
+    }
+
+    /// Waits until less than `max_concurrent_requests` requests are in flight.
+    pub async fn permit(&self) -> SemaphorePermit<'_> {
+        // the semaphore is never closed
+        self.permits.acquire().await.unwrap()
+    }
+}
+
+impl Default for ConnectionManager {
+    fn default() -> Self {
+        ConnectionManager::new(ConnectionOptions::default()).expect("Cannot create http client")
+    }
+}
+
+impl fmt::Debug for ConnectionManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionManager")
+            .field("options", &self.options)
+            .finish()
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use eyre::{eyre, Result};
+use tokio::task::JoinHandle;
+
+use super::connection::ConnectionManager;
+
+// consecutive failures after which an endpoint is skipped
+const FAILURE_THRESHOLD: u32 = 3;
+// how long an endpoint is skipped before it is tried again
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+// weight of the newest sample in the latency average
+const LATENCY_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct EndpointStats {
+    pub url: String,
+    // exponentially weighted average of successful requests
+    pub latency_ms: Option<f64>,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    // circuit is open, the endpoint is skipped until it recovers
+    pub open: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    stats: Mutex<EndpointStats>,
+    open_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_open(&self) -> bool {
+        match *self.open_until.lock().unwrap() {
+            Some(x) => Instant::now() < x,
+            None => false,
+        }
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        let sample = latency.as_secs_f64() * 1000.0;
+        stats.latency_ms = Some(match stats.latency_ms {
+            Some(x) => x * (1.0 - LATENCY_WEIGHT) + sample * LATENCY_WEIGHT,
+            None => sample,
+        });
+        stats.successes += 1;
+        stats.consecutive_failures = 0;
+        *self.open_until.lock().unwrap() = None;
+    }
+
+    fn record_failure(&self) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.failures += 1;
+        stats.consecutive_failures += 1;
+        if stats.consecutive_failures >= FAILURE_THRESHOLD {
+            *self.open_until.lock().unwrap() = Some(Instant::now() + OPEN_DURATION);
+        }
+    }
+}
+
+/// List of interchangeable endpoints of a chain with failover.
+///
+/// Requests go to the healthiest endpoint first (fewest consecutive failures, then
+/// lowest latency) and fall through to the next one on error. Endpoints which failed
+/// `FAILURE_THRESHOLD` times in a row are skipped until they recover.
+#[derive(Clone, Default)]
+pub struct EndpointPool {
+    endpoints: Arc<Vec<Endpoint>>,
+    connections: ConnectionManager,
+}
+
+impl EndpointPool {
+    pub fn new(urls: Vec<String>) -> Self {
+        EndpointPool::with_connections(urls, ConnectionManager::default())
+    }
+
+    // pools of the same chain share their connections and concurrency limit
+    pub fn with_connections(urls: Vec<String>, connections: ConnectionManager) -> Self {
+        EndpointPool {
+            connections,
+            endpoints: Arc::new(
+                urls.into_iter()
+                    .map(|url| Endpoint {
+                        stats: Mutex::new(EndpointStats {
+                            url: url.clone(),
+                            ..Default::default()
+                        }),
+                        url,
+                        open_until: Mutex::new(None),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    pub fn connections(&self) -> &ConnectionManager {
+        &self.connections
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        self.endpoints.iter().map(|x| x.url.clone()).collect()
+    }
+
+    // endpoints with a closed circuit by score, best first
+    fn ordered(&self) -> Vec<&Endpoint> {
+        let mut closed: Vec<&Endpoint> = self.endpoints.iter().filter(|x| !x.is_open()).collect();
+        let score = |x: &Endpoint| {
+            let stats = x.stats.lock().unwrap();
+            // unmeasured endpoints go first so every endpoint gets a latency sample
+            (stats.consecutive_failures, stats.latency_ms.unwrap_or(0.0))
+        };
+        closed.sort_by(|a, b| {
+            let (a, b) = (score(a), score(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        closed
+    }
+
+    /// Runs `request` against the endpoints until one succeeds, returns the last error otherwise.
+    pub async fn run<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let timeout = self.connections.timeout();
+        let mut last_error = if self.endpoints.is_empty() {
+            eyre!("No endpoints configured")
+        } else {
+            // fail fast instead of waiting for timeouts of nodes known to be down
+            eyre!("All endpoints are unhealthy: {}", self.urls().join(", "))
+        };
+        for endpoint in self.ordered() {
+            let _permit = self.connections.permit().await;
+            let start = Instant::now();
+            let result = match tokio::time::timeout(timeout, request(endpoint.url.clone())).await {
+                Ok(x) => x,
+                Err(_) => Err(eyre!("Timed out after {:?}", timeout)),
+            };
+            match result {
+                Ok(x) => {
+                    endpoint.record_success(start.elapsed());
+                    return Ok(x);
+                }
+                Err(x) => {
+                    endpoint.record_failure();
+                    last_error = x.wrap_err(format!("Request to {} failed", endpoint.url));
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.endpoints
+            .iter()
+            .map(|x| EndpointStats {
+                open: x.is_open(),
+                ..x.stats.lock().unwrap().clone()
+            })
+            .collect()
+    }
+
+    /// Periodically calls `probe` for endpoints with an open circuit and closes it on success.
+    pub fn spawn_prober<F, Fut>(&self, interval: Duration, probe: F) -> JoinHandle<()>
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send,
+    {
+        let endpoints = self.endpoints.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for endpoint in endpoints.iter().filter(|x| x.is_open()) {
+                    let start = Instant::now();
+                    match probe(endpoint.url.clone()).await {
+                        Ok(()) => endpoint.record_success(start.elapsed()),
+                        Err(_) => endpoint.record_failure(),
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl fmt::Debug for EndpointPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.stats()).finish()
+    }
+}
+
+// `http` is the client of the pool, so probes use its timeouts
+pub async fn probe_rest(http: reqwest::Client, url: String) -> Result<()> {
+    http.get(format!("{}/cosmos/base/tendermint/v1beta1/node_info", url))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+pub async fn probe_grpc(url: String) -> Result<()> {
+    tonic::transport::Endpoint::from_shared(url)?
+        .connect_timeout(Duration::from_secs(5))
+        .connect()
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pool whose requests fail for the urls in `failing` and answer with the url otherwise
+    struct Fake {
+        pool: EndpointPool,
+        failing: Mutex<Vec<String>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl Fake {
+        fn new(urls: &[&str], failing: &[&str]) -> Self {
+            Fake {
+                pool: EndpointPool::new(urls.iter().map(|x| x.to_string()).collect()),
+                failing: Mutex::new(failing.iter().map(|x| x.to_string()).collect()),
+                calls: Mutex::new(vec![]),
+            }
+        }
+
+        async fn run(&self) -> Result<String> {
+            self.pool
+                .run(|url| async move {
+                    self.calls.lock().unwrap().push(url.clone());
+                    if self.failing.lock().unwrap().contains(&url) {
+                        Err(eyre!("{} is down", url))
+                    } else {
+                        Ok(url)
+                    }
+                })
+                .await
+        }
+
+        fn take_calls(&self) -> Vec<String> {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+
+        fn recover(&self) {
+            self.failing.lock().unwrap().clear();
+        }
+
+        // lets the open circuits run out instead of waiting `OPEN_DURATION`
+        fn expire(&self) {
+            for endpoint in self.pool.endpoints.iter() {
+                if endpoint.is_open() {
+                    *endpoint.open_until.lock().unwrap() = Some(Instant::now());
+                }
+            }
+        }
+
+        fn stats(&self, url: &str) -> EndpointStats {
+            self.pool
+                .stats()
+                .into_iter()
+                .find(|x| x.url == url)
+                .unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_the_next_endpoint() {
+        let fake = Fake::new(&["a", "b"], &["a"]);
+        assert_eq!(fake.run().await.unwrap(), "b");
+        assert_eq!(fake.take_calls(), vec!["a", "b"]);
+        assert_eq!(fake.stats("a").consecutive_failures, 1);
+        assert_eq!(fake.stats("b").successes, 1);
+
+        // the failed endpoint goes last
+        assert_eq!(fake.run().await.unwrap(), "b");
+        assert_eq!(fake.take_calls(), vec!["b"]);
+    }
+
+    #[tokio::test]
+    async fn returns_the_last_error_if_every_endpoint_fails() {
+        let fake = Fake::new(&["a", "b"], &["a", "b"]);
+        let err = fake.run().await.unwrap_err();
+        assert_eq!(fake.take_calls(), vec!["a", "b"]);
+        assert_eq!(err.to_string(), "Request to b failed");
+        assert_eq!(err.root_cause().to_string(), "b is down");
+    }
+
+    #[tokio::test]
+    async fn opens_the_circuit_after_consecutive_failures() {
+        let fake = Fake::new(&["a"], &["a"]);
+        for _ in 0..FAILURE_THRESHOLD {
+            assert!(!fake.stats("a").open);
+            assert!(fake.run().await.is_err());
+        }
+        assert_eq!(fake.take_calls().len(), FAILURE_THRESHOLD as usize);
+        assert!(fake.stats("a").open);
+
+        // an open endpoint is not requested at all
+        let err = fake.run().await.unwrap_err();
+        assert!(fake.take_calls().is_empty());
+        assert_eq!(err.to_string(), "All endpoints are unhealthy: a");
+
+        // and stays open for `OPEN_DURATION`
+        let open_until = fake.pool.endpoints[0].open_until.lock().unwrap().unwrap();
+        assert!(open_until > Instant::now() + OPEN_DURATION - Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn recovers_after_the_circuit_ran_out() {
+        let fake = Fake::new(&["a"], &["a"]);
+        for _ in 0..FAILURE_THRESHOLD {
+            assert!(fake.run().await.is_err());
+        }
+
+        // a failure after the circuit ran out opens it again right away
+        fake.expire();
+        assert!(fake.run().await.is_err());
+        assert!(fake.stats("a").open);
+
+        fake.expire();
+        fake.recover();
+        fake.take_calls();
+        assert_eq!(fake.run().await.unwrap(), "a");
+        assert_eq!(fake.take_calls(), vec!["a"]);
+        let stats = fake.stats("a");
+        assert!(!stats.open);
+        assert_eq!(stats.consecutive_failures, 0);
+        assert_eq!(stats.failures, FAILURE_THRESHOLD as u64 + 1);
+        assert_eq!(stats.successes, 1);
+    }
+
+    #[tokio::test]
+    async fn prober_closes_recovered_circuits() {
+        let fake = Fake::new(&["a", "b"], &["a", "b"]);
+        for _ in 0..FAILURE_THRESHOLD {
+            assert!(fake.run().await.is_err());
+        }
+        assert!(fake.stats("a").open && fake.stats("b").open);
+
+        let prober = fake
+            .pool
+            .spawn_prober(Duration::from_millis(10), |url| async move {
+                if url == "a" {
+                    Ok(())
+                } else {
+                    Err(eyre!("{} is down", url))
+                }
+            });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        prober.abort();
+
+        assert!(!fake.stats("a").open);
+        assert!(fake.stats("b").open);
+        fake.recover();
+        fake.take_calls();
+        assert_eq!(fake.run().await.unwrap(), "a");
+        assert_eq!(fake.take_calls(), vec!["a"]);
+    }
+}
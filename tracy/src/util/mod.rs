@@ -1,2 +1,7 @@
+pub mod block;
+pub mod connection;
 pub mod denom_trace;
+pub mod endpoint;
+// generated by tonic_build, see build.rs
+#[allow(clippy::all)]
 pub mod proto;
@@ -4,9 +4,9 @@
 /// signatures required by gogoproto.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Coin {
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub denom: ::prost::alloc::string::String,
-    #[prost(string, tag="2")]
+    #[prost(string, tag = "2")]
     pub amount: ::prost::alloc::string::String,
 }
 /// DecCoin defines a token with a denomination and a decimal amount.
@@ -15,20 +15,20 @@ pub struct Coin {
 /// signatures required by gogoproto.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DecCoin {
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub denom: ::prost::alloc::string::String,
-    #[prost(string, tag="2")]
+    #[prost(string, tag = "2")]
     pub amount: ::prost::alloc::string::String,
 }
 /// IntProto defines a Protobuf wrapper around an Int object.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct IntProto {
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub int: ::prost::alloc::string::String,
 }
 /// DecProto defines a Protobuf wrapper around a Dec object.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DecProto {
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub dec: ::prost::alloc::string::String,
 }
@@ -0,0 +1,10 @@
+/// PubKey defines a secp256k1 public key
+/// Key is the compressed form of the pubkey. The first byte depends is a 0x02 byte
+/// if the y-coordinate is the lexicographically largest of the two associated with
+/// the x-coordinate. Otherwise the first byte is a 0x03.
+/// This prefix is followed with the x-coordinate.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PubKey {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
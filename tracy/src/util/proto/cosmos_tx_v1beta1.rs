@@ -0,0 +1,158 @@
+/// TxRaw is a variant of Tx that pins the signer's exact binary representation
+/// of body and auth_info. This is used for signing, broadcasting and
+/// verification. The binary `serialize(tx: TxRaw)` is stored in Tendermint and
+/// the hash `sha256(serialize(tx: TxRaw))` becomes the "txhash", commonly used
+/// as the transaction ID.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TxRaw {
+    /// body_bytes is a protobuf serialization of a TxBody that matches the
+    /// representation in SignDoc.
+    #[prost(bytes = "vec", tag = "1")]
+    pub body_bytes: ::prost::alloc::vec::Vec<u8>,
+    /// auth_info_bytes is a protobuf serialization of an AuthInfo that matches the
+    /// representation in SignDoc.
+    #[prost(bytes = "vec", tag = "2")]
+    pub auth_info_bytes: ::prost::alloc::vec::Vec<u8>,
+    /// signatures is a list of signatures that matches the length and order of
+    /// AuthInfo's signer_infos to allow connecting signature meta information like
+    /// public key and signing mode by position.
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub signatures: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+/// SignDoc is the type used for generating sign bytes for SIGN_MODE_DIRECT.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignDoc {
+    /// body_bytes is protobuf serialization of a TxBody that matches the
+    /// representation in TxRaw.
+    #[prost(bytes = "vec", tag = "1")]
+    pub body_bytes: ::prost::alloc::vec::Vec<u8>,
+    /// auth_info_bytes is a protobuf serialization of an AuthInfo that matches the
+    /// representation in TxRaw.
+    #[prost(bytes = "vec", tag = "2")]
+    pub auth_info_bytes: ::prost::alloc::vec::Vec<u8>,
+    /// chain_id is the unique identifier of the chain this transaction targets.
+    /// It prevents signed transactions from being used on another chain by an
+    /// attacker
+    #[prost(string, tag = "3")]
+    pub chain_id: ::prost::alloc::string::String,
+    /// account_number is the account number of the account in state
+    #[prost(uint64, tag = "4")]
+    pub account_number: u64,
+}
+/// TxBody is the body of a transaction that all signers sign over.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TxBody {
+    /// messages is a list of messages to be executed. The required signers of
+    /// those messages define the number and order of elements in AuthInfo's
+    /// signer_infos and Tx's signatures. Each required signer address is added to
+    /// the list only the first time it occurs.
+    #[prost(message, repeated, tag = "1")]
+    pub messages: ::prost::alloc::vec::Vec<::prost_types::Any>,
+    /// memo is any arbitrary note/comment to be added to the transaction.
+    #[prost(string, tag = "2")]
+    pub memo: ::prost::alloc::string::String,
+    /// timeout is the block height after which this transaction will not
+    /// be processed by the chain
+    #[prost(uint64, tag = "3")]
+    pub timeout_height: u64,
+    /// extension_options are arbitrary options that can be added by chains
+    /// when the default options are not sufficient. If any of these are present
+    /// and can't be handled, the transaction will be rejected
+    #[prost(message, repeated, tag = "1023")]
+    pub extension_options: ::prost::alloc::vec::Vec<::prost_types::Any>,
+    /// extension_options are arbitrary options that can be added by chains
+    /// when the default options are not sufficient. If any of these are present
+    /// and can't be handled, they will be ignored
+    #[prost(message, repeated, tag = "2047")]
+    pub non_critical_extension_options: ::prost::alloc::vec::Vec<::prost_types::Any>,
+}
+/// AuthInfo describes the fee and signer modes that are used to sign a
+/// transaction.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthInfo {
+    /// signer_infos defines the signing modes for the required signers. The number
+    /// and order of elements must match the required signers from TxBody's
+    /// messages. The first element is the primary signer and the one which pays
+    /// the fee.
+    #[prost(message, repeated, tag = "1")]
+    pub signer_infos: ::prost::alloc::vec::Vec<SignerInfo>,
+    /// Fee is the fee and gas limit for the transaction. The first signer is the
+    /// primary signer and the one which pays the fee. The fee can be calculated
+    /// based on the cost of evaluating the body and doing signature verification
+    /// of the signers. This can be estimated via simulation.
+    #[prost(message, optional, tag = "2")]
+    pub fee: ::core::option::Option<Fee>,
+}
+/// SignerInfo describes the public key and signing mode of a single top-level
+/// signer.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignerInfo {
+    /// public_key is the public key of the signer. It is optional for accounts
+    /// that already exist in state. If unset, the verifier can use the required \
+    /// signer address for this position and lookup the public key.
+    #[prost(message, optional, tag = "1")]
+    pub public_key: ::core::option::Option<::prost_types::Any>,
+    /// mode_info describes the signing mode of the signer and is a nested
+    /// structure to support nested multisig pubkey's
+    #[prost(message, optional, tag = "2")]
+    pub mode_info: ::core::option::Option<ModeInfo>,
+    /// sequence is the sequence of the account, which describes the
+    /// number of committed transactions signed by a given address. It is used to
+    /// prevent replay attacks.
+    #[prost(uint64, tag = "3")]
+    pub sequence: u64,
+}
+/// ModeInfo describes the signing mode of a single or nested multisig signer.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ModeInfo {
+    /// sum is the oneof that specifies whether this represents a single or nested
+    /// multisig signer
+    #[prost(oneof = "mode_info::Sum", tags = "1")]
+    pub sum: ::core::option::Option<mode_info::Sum>,
+}
+/// Nested message and enum types in `ModeInfo`.
+pub mod mode_info {
+    /// Single is the mode info for a single signer. It is structured as a message
+    /// to allow for additional fields such as locale for SIGN_MODE_TEXTUAL in the
+    /// future
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Single {
+        /// mode is the signing mode of the single signer
+        #[prost(
+            enumeration = "super::super::cosmos_tx_signing_v1beta1::SignMode",
+            tag = "1"
+        )]
+        pub mode: i32,
+    }
+    /// sum is the oneof that specifies whether this represents a single or nested
+    /// multisig signer
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Sum {
+        /// single represents a single signer
+        #[prost(message, tag = "1")]
+        Single(Single),
+    }
+}
+/// Fee includes the amount of coins paid in fees and the maximum
+/// gas to be used by the transaction. The ratio yields an effective "gasprice",
+/// which must be above some miminum to be accepted into the mempool.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Fee {
+    /// amount is the amount of coins to be paid as a fee
+    #[prost(message, repeated, tag = "1")]
+    pub amount: ::prost::alloc::vec::Vec<super::cosmos_base_v1beta1::Coin>,
+    /// gas_limit is the maximum gas that can be used in transaction processing
+    /// before an out of gas error occurs
+    #[prost(uint64, tag = "2")]
+    pub gas_limit: u64,
+    /// if unset, the first signer is responsible for paying the fees. If set, the specified account must pay the fees.
+    /// the payer must be a tx signer (and thus have signed this field in AuthInfo).
+    /// setting this field does *not* change the ordering of required signers for the transaction.
+    #[prost(string, tag = "3")]
+    pub payer: ::prost::alloc::string::String,
+    /// if set, the fee payer (either the first signer or the value of the payer field) requests that a fee grant be used
+    /// to pay fees instead of the fee payer's own balance. If an appropriate fee grant does not exist or the chain does
+    /// not support fee grants, this will fail
+    #[prost(string, tag = "4")]
+    pub granter: ::prost::alloc::string::String,
+}
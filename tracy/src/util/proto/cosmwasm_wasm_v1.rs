@@ -0,0 +1,23 @@
+/// MsgExecuteContract submits the given message data to a smart contract
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgExecuteContract {
+    /// Sender is the that actor that signed the messages
+    #[prost(string, tag = "1")]
+    pub sender: ::prost::alloc::string::String,
+    /// Contract is the address of the smart contract
+    #[prost(string, tag = "2")]
+    pub contract: ::prost::alloc::string::String,
+    /// Msg json encoded message to be passed to the contract
+    #[prost(bytes = "vec", tag = "3")]
+    pub msg: ::prost::alloc::vec::Vec<u8>,
+    /// Funds coins that are transferred to the contract on execution
+    #[prost(message, repeated, tag = "5")]
+    pub funds: ::prost::alloc::vec::Vec<super::cosmos_base_v1beta1::Coin>,
+}
+/// MsgExecuteContractResponse returns execution result data.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgExecuteContractResponse {
+    /// Data contains base64-encoded bytes to returned from the contract
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
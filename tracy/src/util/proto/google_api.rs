@@ -6,7 +6,7 @@ pub struct Http {
     /// A list of HTTP configuration rules that apply to individual API methods.
     ///
     /// **NOTE:** All service configuration rules follow "last one wins" order.
-    #[prost(message, repeated, tag="1")]
+    #[prost(message, repeated, tag = "1")]
     pub rules: ::prost::alloc::vec::Vec<HttpRule>,
     /// When set to true, URL path parameters will be fully URI-decoded except in
     /// cases of single segment matches in reserved expansion, where "%2F" will be
@@ -14,7 +14,7 @@ pub struct Http {
     ///
     /// The default behavior is to not decode RFC 6570 reserved characters in multi
     /// segment matches.
-    #[prost(bool, tag="2")]
+    #[prost(bool, tag = "2")]
     pub fully_decode_reserved_expansion: bool,
 }
 /// # gRPC Transcoding
@@ -42,19 +42,21 @@ pub struct Http {
 ///
 /// Example:
 ///
-///      service Messaging {
-///        rpc GetMessage(GetMessageRequest) returns (Message) {
-///          option (google.api.http) = {
-///              get: "/v1/{name=messages/*}"
-///          };
-///        }
-///      }
-///      message GetMessageRequest {
-///        string name = 1; // Mapped to URL path.
-///      }
-///      message Message {
-///        string text = 1; // The resource content.
-///      }
+/// ```text
+///   service Messaging {
+///     rpc GetMessage(GetMessageRequest) returns (Message) {
+///       option (google.api.http) = {
+///           get: "/v1/{name=messages/*}"
+///       };
+///     }
+///   }
+///   message GetMessageRequest {
+///     string name = 1; // Mapped to URL path.
+///   }
+///   message Message {
+///     string text = 1; // The resource content.
+///   }
+/// ```
 ///
 /// This enables an HTTP REST to gRPC mapping as below:
 ///
@@ -66,21 +68,23 @@ pub struct Http {
 /// automatically become HTTP query parameters if there is no HTTP request body.
 /// For example:
 ///
-///      service Messaging {
-///        rpc GetMessage(GetMessageRequest) returns (Message) {
-///          option (google.api.http) = {
-///              get:"/v1/messages/{message_id}"
-///          };
-///        }
-///      }
-///      message GetMessageRequest {
-///        message SubMessage {
-///          string subfield = 1;
-///        }
-///        string message_id = 1; // Mapped to URL path.
-///        int64 revision = 2;    // Mapped to URL query parameter `revision`.
-///        SubMessage sub = 3;    // Mapped to URL query parameter `sub.subfield`.
-///      }
+/// ```text
+///   service Messaging {
+///     rpc GetMessage(GetMessageRequest) returns (Message) {
+///       option (google.api.http) = {
+///           get:"/v1/messages/{message_id}"
+///       };
+///     }
+///   }
+///   message GetMessageRequest {
+///     message SubMessage {
+///       string subfield = 1;
+///     }
+///     string message_id = 1; // Mapped to URL path.
+///     int64 revision = 2;    // Mapped to URL query parameter `revision`.
+///     SubMessage sub = 3;    // Mapped to URL query parameter `sub.subfield`.
+///   }
+/// ```
 ///
 /// This enables a HTTP JSON to RPC mapping as below:
 ///
@@ -101,18 +105,20 @@ pub struct Http {
 /// specifies the mapping. Consider a REST update method on the
 /// message resource collection:
 ///
-///      service Messaging {
-///        rpc UpdateMessage(UpdateMessageRequest) returns (Message) {
-///          option (google.api.http) = {
-///            patch: "/v1/messages/{message_id}"
-///            body: "message"
-///          };
-///        }
-///      }
-///      message UpdateMessageRequest {
-///        string message_id = 1; // mapped to the URL
-///        Message message = 2;   // mapped to the body
-///      }
+/// ```text
+///   service Messaging {
+///     rpc UpdateMessage(UpdateMessageRequest) returns (Message) {
+///       option (google.api.http) = {
+///         patch: "/v1/messages/{message_id}"
+///         body: "message"
+///       };
+///     }
+///   }
+///   message UpdateMessageRequest {
+///     string message_id = 1; // mapped to the URL
+///     Message message = 2;   // mapped to the body
+///   }
+/// ```
 ///
 /// The following HTTP JSON to RPC mapping is enabled, where the
 /// representation of the JSON in the request body is determined by
@@ -128,18 +134,20 @@ pub struct Http {
 /// request body.  This enables the following alternative definition of
 /// the update method:
 ///
-///      service Messaging {
-///        rpc UpdateMessage(Message) returns (Message) {
-///          option (google.api.http) = {
-///            patch: "/v1/messages/{message_id}"
-///            body: "*"
-///          };
-///        }
-///      }
-///      message Message {
-///        string message_id = 1;
-///        string text = 2;
-///      }
+/// ```text
+///   service Messaging {
+///     rpc UpdateMessage(Message) returns (Message) {
+///       option (google.api.http) = {
+///         patch: "/v1/messages/{message_id}"
+///         body: "*"
+///       };
+///     }
+///   }
+///   message Message {
+///     string message_id = 1;
+///     string text = 2;
+///   }
+/// ```
 ///
 ///
 /// The following HTTP JSON to RPC mapping is enabled:
@@ -158,20 +166,22 @@ pub struct Http {
 /// It is possible to define multiple HTTP methods for one RPC by using
 /// the `additional_bindings` option. Example:
 ///
-///      service Messaging {
-///        rpc GetMessage(GetMessageRequest) returns (Message) {
-///          option (google.api.http) = {
-///            get: "/v1/messages/{message_id}"
-///            additional_bindings {
-///              get: "/v1/users/{user_id}/messages/{message_id}"
-///            }
-///          };
-///        }
-///      }
-///      message GetMessageRequest {
-///        string message_id = 1;
-///        string user_id = 2;
-///      }
+/// ```text
+///   service Messaging {
+///     rpc GetMessage(GetMessageRequest) returns (Message) {
+///       option (google.api.http) = {
+///         get: "/v1/messages/{message_id}"
+///         additional_bindings {
+///           get: "/v1/users/{user_id}/messages/{message_id}"
+///         }
+///       };
+///     }
+///   }
+///   message GetMessageRequest {
+///     string message_id = 1;
+///     string user_id = 2;
+///   }
+/// ```
 ///
 /// This enables the following two alternative HTTP JSON to RPC mappings:
 ///
@@ -199,12 +209,14 @@ pub struct Http {
 ///
 /// ### Path template syntax
 ///
-///      Template = "/" Segments [ Verb ] ;
-///      Segments = Segment { "/" Segment } ;
-///      Segment  = "*" | "**" | LITERAL | Variable ;
-///      Variable = "{" FieldPath [ "=" Segments ] "}" ;
-///      FieldPath = IDENT { "." IDENT } ;
-///      Verb     = ":" LITERAL ;
+/// ```text
+///   Template = "/" Segments [ Verb ] ;
+///   Segments = Segment { "/" Segment } ;
+///   Segment  = "*" | "**" | LITERAL | Variable ;
+///   Variable = "{" FieldPath [ "=" Segments ] "}" ;
+///   FieldPath = IDENT { "." IDENT } ;
+///   Verb     = ":" LITERAL ;
+/// ```
 ///
 /// The syntax `*` matches a single URL path segment. The syntax `**` matches
 /// zero or more URL path segments, which must be the last part of the URL path
@@ -253,11 +265,13 @@ pub struct Http {
 ///
 /// Example:
 ///
-///      http:
-///        rules:
-///          # Selects a gRPC method and applies HttpRule to it.
-///          - selector: example.v1.Messaging.GetMessage
-///            get: /v1/messages/{message_id}/{sub.subfield}
+/// ```text
+///   http:
+///     rules:
+///       # Selects a gRPC method and applies HttpRule to it.
+///       - selector: example.v1.Messaging.GetMessage
+///         get: /v1/messages/{message_id}/{sub.subfield}
+/// ```
 ///
 /// ## Special notes
 ///
@@ -291,7 +305,7 @@ pub struct HttpRule {
     /// Selects a method to which this rule applies.
     ///
     /// Refer to \[selector][google.api.DocumentationRule.selector\] for syntax details.
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub selector: ::prost::alloc::string::String,
     /// The name of the request field whose value is mapped to the HTTP request
     /// body, or `*` for mapping all request fields not captured by the path
@@ -299,7 +313,7 @@ pub struct HttpRule {
     ///
     /// NOTE: the referred field must be present at the top-level of the request
     /// message type.
-    #[prost(string, tag="7")]
+    #[prost(string, tag = "7")]
     pub body: ::prost::alloc::string::String,
     /// Optional. The name of the response field whose value is mapped to the HTTP
     /// response body. When omitted, the entire response message will be used
@@ -307,17 +321,17 @@ pub struct HttpRule {
     ///
     /// NOTE: The referred field must be present at the top-level of the response
     /// message type.
-    #[prost(string, tag="12")]
+    #[prost(string, tag = "12")]
     pub response_body: ::prost::alloc::string::String,
     /// Additional HTTP bindings for the selector. Nested bindings must
     /// not contain an `additional_bindings` field themselves (that is,
     /// the nesting may only be one level deep).
-    #[prost(message, repeated, tag="11")]
+    #[prost(message, repeated, tag = "11")]
     pub additional_bindings: ::prost::alloc::vec::Vec<HttpRule>,
     /// Determines the URL pattern is matched by this rules. This pattern can be
     /// used with any of the {get|put|post|delete|patch} methods. A custom method
     /// can be defined using the 'custom' field.
-    #[prost(oneof="http_rule::Pattern", tags="2, 3, 4, 5, 6, 8")]
+    #[prost(oneof = "http_rule::Pattern", tags = "2, 3, 4, 5, 6, 8")]
     pub pattern: ::core::option::Option<http_rule::Pattern>,
 }
 /// Nested message and enum types in `HttpRule`.
@@ -329,25 +343,25 @@ pub mod http_rule {
     pub enum Pattern {
         /// Maps to HTTP GET. Used for listing and getting information about
         /// resources.
-        #[prost(string, tag="2")]
+        #[prost(string, tag = "2")]
         Get(::prost::alloc::string::String),
         /// Maps to HTTP PUT. Used for replacing a resource.
-        #[prost(string, tag="3")]
+        #[prost(string, tag = "3")]
         Put(::prost::alloc::string::String),
         /// Maps to HTTP POST. Used for creating a resource or performing an action.
-        #[prost(string, tag="4")]
+        #[prost(string, tag = "4")]
         Post(::prost::alloc::string::String),
         /// Maps to HTTP DELETE. Used for deleting a resource.
-        #[prost(string, tag="5")]
+        #[prost(string, tag = "5")]
         Delete(::prost::alloc::string::String),
         /// Maps to HTTP PATCH. Used for updating a resource.
-        #[prost(string, tag="6")]
+        #[prost(string, tag = "6")]
         Patch(::prost::alloc::string::String),
         /// The custom pattern is used for specifying an HTTP method that is not
         /// included in the `pattern` field, such as HEAD, or "*" to leave the
         /// HTTP method unspecified for this rule. The wild-card rule is useful
         /// for services that provide content to Web (HTML) clients.
-        #[prost(message, tag="8")]
+        #[prost(message, tag = "8")]
         Custom(super::CustomHttpPattern),
     }
 }
@@ -355,9 +369,9 @@ pub mod http_rule {
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CustomHttpPattern {
     /// The name of this custom HTTP verb.
-    #[prost(string, tag="1")]
+    #[prost(string, tag = "1")]
     pub kind: ::prost::alloc::string::String,
     /// The path matched by this custom verb.
-    #[prost(string, tag="2")]
+    #[prost(string, tag = "2")]
     pub path: ::prost::alloc::string::String,
 }
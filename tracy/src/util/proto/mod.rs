@@ -1,7 +1,14 @@
 pub mod cosmos_base_query_v1beta1;
 pub mod cosmos_base_v1beta1;
+pub mod cosmos_crypto_secp256k1;
 pub mod cosmos_proto;
+pub mod cosmos_tx_signing_v1beta1;
+pub mod cosmos_tx_v1beta1;
+pub mod cosmwasm_wasm_v1;
 pub mod gogoproto;
 pub mod google_api;
 pub mod google_protobuf;
+pub mod osmosis_concentratedliquidity_v1beta1;
+pub mod osmosis_gamm_poolmodels_stableswap_v1beta1;
 pub mod osmosis_gamm_v1beta1;
+pub mod osmosis_poolmanager_v1beta1;
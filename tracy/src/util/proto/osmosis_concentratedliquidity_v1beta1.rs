@@ -0,0 +1,178 @@
+/// Concentrated liquidity pool, the liquidity of all positions in the current
+/// tick range is in current_tick_liquidity.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Pool {
+    /// pool's address holding all liquidity tokens.
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// address holding the incentives liquidity.
+    #[prost(string, tag = "2")]
+    pub incentives_address: ::prost::alloc::string::String,
+    /// address holding spread rewards from swaps.
+    #[prost(string, tag = "3")]
+    pub spread_rewards_address: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "4")]
+    pub id: u64,
+    /// Amount of total liquidity, sdk.Dec
+    #[prost(string, tag = "5")]
+    pub current_tick_liquidity: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub token0: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub token1: ::prost::alloc::string::String,
+    /// osmomath.BigDec, encoded as integer string with 36 decimals
+    #[prost(string, tag = "8")]
+    pub current_sqrt_price: ::prost::alloc::string::String,
+    #[prost(int64, tag = "9")]
+    pub current_tick: i64,
+    /// tick_spacing must be one of the authorized_tick_spacing values set in the
+    /// concentrated-liquidity parameters
+    #[prost(uint64, tag = "10")]
+    pub tick_spacing: u64,
+    #[prost(int64, tag = "11")]
+    pub exponent_at_price_one: i64,
+    /// spread_factor is the ratio that is charged on the amount of token in, sdk.Dec
+    #[prost(string, tag = "12")]
+    pub spread_factor: ::prost::alloc::string::String,
+    /// last_liquidity_update is the last time either the pool liquidity or the
+    /// active tick changed
+    #[prost(message, optional, tag = "13")]
+    pub last_liquidity_update: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// =============================== Pools
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolsRequest {
+    /// pagination defines an optional pagination for the request.
+    #[prost(message, optional, tag = "2")]
+    pub pagination: ::core::option::Option<super::cosmos_base_query_v1beta1::PageRequest>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub pools: ::prost::alloc::vec::Vec<::prost_types::Any>,
+    /// pagination defines the pagination in the response.
+    #[prost(message, optional, tag = "2")]
+    pub pagination: ::core::option::Option<super::cosmos_base_query_v1beta1::PageResponse>,
+}
+/// =============================== LiquidityPerTickRange
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LiquidityPerTickRangeRequest {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LiquidityPerTickRangeResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub liquidity: ::prost::alloc::vec::Vec<LiquidityDepthWithRange>,
+}
+/// Liquidity of all positions between lower_tick and upper_tick, sdk.Dec
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LiquidityDepthWithRange {
+    #[prost(string, tag = "1")]
+    pub liquidity_amount: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub lower_tick: i64,
+    #[prost(int64, tag = "3")]
+    pub upper_tick: i64,
+}
+pub mod query_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+    #[derive(Debug, Clone)]
+    pub struct QueryClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl QueryClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: std::convert::TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> QueryClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> QueryClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
+                Into<StdError> + Send + Sync,
+        {
+            QueryClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        pub async fn pools(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PoolsRequest>,
+        ) -> Result<tonic::Response<super::PoolsResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/osmosis.concentratedliquidity.v1beta1.Query/Pools",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn liquidity_per_tick_range(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LiquidityPerTickRangeRequest>,
+        ) -> Result<tonic::Response<super::LiquidityPerTickRangeResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/osmosis.concentratedliquidity.v1beta1.Query/LiquidityPerTickRange",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+    }
+}
@@ -0,0 +1,35 @@
+/// PoolParams defined the parameters that will be managed by the pool
+/// governance in the future. swap_fee and exit_fee are sdk.Dec, encoded as
+/// integer string with 18 decimals.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolParams {
+    #[prost(string, tag = "1")]
+    pub swap_fee: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub exit_fee: ::prost::alloc::string::String,
+}
+/// Pool is the stableswap Pool struct
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Pool {
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub id: u64,
+    #[prost(message, optional, tag = "3")]
+    pub pool_params: ::core::option::Option<PoolParams>,
+    /// This string specifies who will govern the pool in the future.
+    #[prost(string, tag = "4")]
+    pub future_pool_governor: ::prost::alloc::string::String,
+    /// sum of all LP shares
+    #[prost(message, optional, tag = "5")]
+    pub total_shares: ::core::option::Option<super::cosmos_base_v1beta1::Coin>,
+    /// assets in the pool
+    #[prost(message, repeated, tag = "6")]
+    pub pool_liquidity: ::prost::alloc::vec::Vec<super::cosmos_base_v1beta1::Coin>,
+    /// for calculation amognst assets with different precisions
+    #[prost(uint64, repeated, tag = "7")]
+    pub scaling_factors: ::prost::alloc::vec::Vec<u64>,
+    /// scaling_factor_controller is the address can adjust pool scaling factors
+    #[prost(string, tag = "8")]
+    pub scaling_factor_controller: ::prost::alloc::string::String,
+}
@@ -1,3 +1,56 @@
+/// =============================== Balancer pool
+/// Parameters for changing the weights in a balancer pool smoothly from
+/// a start weight and end weight over a period of time.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SmoothWeightChangeParams {
+    #[prost(message, optional, tag = "1")]
+    pub start_time: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    pub duration: ::core::option::Option<::prost_types::Duration>,
+    #[prost(message, repeated, tag = "3")]
+    pub initial_pool_weights: ::prost::alloc::vec::Vec<PoolAsset>,
+    #[prost(message, repeated, tag = "4")]
+    pub target_pool_weights: ::prost::alloc::vec::Vec<PoolAsset>,
+}
+/// PoolParams defined the parameters that will be managed by the pool
+/// governance in the future. swap_fee and exit_fee are sdk.Dec, encoded as
+/// integer string with 18 decimals.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolParams {
+    #[prost(string, tag = "1")]
+    pub swap_fee: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub exit_fee: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub smooth_weight_change_params: ::core::option::Option<SmoothWeightChangeParams>,
+}
+/// Pool asset is an internal struct that combines the amount of the
+/// token in the pool, and its balancer weight.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolAsset {
+    #[prost(message, optional, tag = "1")]
+    pub token: ::core::option::Option<super::cosmos_base_v1beta1::Coin>,
+    #[prost(string, tag = "2")]
+    pub weight: ::prost::alloc::string::String,
+}
+/// Balancer pool, type url /osmosis.gamm.v1beta1.Pool
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Pool {
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub id: u64,
+    #[prost(message, optional, tag = "3")]
+    pub pool_params: ::core::option::Option<PoolParams>,
+    #[prost(string, tag = "4")]
+    pub future_pool_governor: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "5")]
+    pub total_shares: ::core::option::Option<super::cosmos_base_v1beta1::Coin>,
+    #[prost(message, repeated, tag = "6")]
+    pub pool_assets: ::prost::alloc::vec::Vec<PoolAsset>,
+    #[prost(string, tag = "7")]
+    pub total_weight: ::prost::alloc::string::String,
+}
 /// ===================== MsgJoinPool
 /// This is really MsgJoinPoolNoSwap
 #[derive(Clone, PartialEq, ::prost::Message)]
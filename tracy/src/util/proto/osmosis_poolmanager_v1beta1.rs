@@ -0,0 +1,181 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapAmountInRoute {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+    #[prost(string, tag = "2")]
+    pub token_out_denom: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapAmountOutRoute {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+    #[prost(string, tag = "2")]
+    pub token_in_denom: ::prost::alloc::string::String,
+}
+/// =============================== Pool
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolRequest {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolResponse {
+    #[prost(message, optional, tag = "1")]
+    pub pool: ::core::option::Option<::prost_types::Any>,
+}
+/// =============================== EstimateSwapExactAmountIn
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateSwapExactAmountInRequest {
+    /// DEPRECATED
+    #[deprecated]
+    #[prost(string, tag = "1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub pool_id: u64,
+    #[prost(string, tag = "3")]
+    pub token_in: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "4")]
+    pub routes: ::prost::alloc::vec::Vec<SwapAmountInRoute>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateSwapExactAmountInResponse {
+    #[prost(string, tag = "1")]
+    pub token_out_amount: ::prost::alloc::string::String,
+}
+/// =============================== EstimateSwapExactAmountOut
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateSwapExactAmountOutRequest {
+    /// DEPRECATED
+    #[deprecated]
+    #[prost(string, tag = "1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub pool_id: u64,
+    #[prost(message, repeated, tag = "3")]
+    pub routes: ::prost::alloc::vec::Vec<SwapAmountOutRoute>,
+    #[prost(string, tag = "4")]
+    pub token_out: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateSwapExactAmountOutResponse {
+    #[prost(string, tag = "1")]
+    pub token_in_amount: ::prost::alloc::string::String,
+}
+pub mod query_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+    #[derive(Debug, Clone)]
+    pub struct QueryClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl QueryClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: std::convert::TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> QueryClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> QueryClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
+                Into<StdError> + Send + Sync,
+        {
+            QueryClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        pub async fn estimate_swap_exact_amount_in(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EstimateSwapExactAmountInRequest>,
+        ) -> Result<tonic::Response<super::EstimateSwapExactAmountInResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/osmosis.poolmanager.v1beta1.Query/EstimateSwapExactAmountIn",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn estimate_swap_exact_amount_out(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EstimateSwapExactAmountOutRequest>,
+        ) -> Result<tonic::Response<super::EstimateSwapExactAmountOutResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/osmosis.poolmanager.v1beta1.Query/EstimateSwapExactAmountOut",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn pool(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PoolRequest>,
+        ) -> Result<tonic::Response<super::PoolResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/osmosis.poolmanager.v1beta1.Query/Pool");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+    }
+}